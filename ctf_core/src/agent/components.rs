@@ -2,18 +2,37 @@ use crate::{
     agent::AGENT_DEFAULT_SPEED,
     team::{Team, TeamId},
 };
+use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// The public ID of an agent, assigned from its team and config slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct AgentId(pub u32);
+
+/// Agent entities by [`AgentId`].
+#[derive(Debug, Default, Resource)]
+pub struct AgentIndex(pub HashMap<AgentId, Entity>);
+
+impl AgentIndex {
+    pub fn get(&self, id: u32) -> Option<Entity> {
+        self.0.get(&AgentId(id)).copied()
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Component, Reflect, Serialize, Deserialize)]
 #[reflect(Component)]
 pub struct Agent {
     pub speed: f32,
     pub flag: Option<Entity>,
+    #[serde(default)]
+    pub in_home_territory: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Bundle)]
 pub struct AgentBundle {
+    pub id: AgentId,
     pub name: Name,
     pub agent: Agent,
     pub team: Team,
@@ -22,16 +41,24 @@ pub struct AgentBundle {
 #[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum Action {
-    Move { id: u32, velocity: Vec2 },
+    Move {
+        id: u32,
+        velocity: Vec2,
+        /// Broadcast to teammates, if the match allows it.
+        #[serde(default)]
+        message: Option<String>,
+    },
 }
 
 impl Default for AgentBundle {
     fn default() -> Self {
         Self {
+            id: AgentId(0),
             name: Name::new("Agent"),
             agent: Agent {
                 speed: AGENT_DEFAULT_SPEED,
                 flag: None,
+                in_home_territory: false,
             },
             team: Team(TeamId::Red),
         }
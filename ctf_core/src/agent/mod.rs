@@ -20,6 +20,9 @@ pub struct AgentPlugin;
 impl Plugin for AgentPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Agent>();
+        app.register_type::<AgentId>();
+        app.init_resource::<AgentIndex>();
+        app.add_systems(PreUpdate, systems::index_agents);
         app.add_systems(
             PreStartup,
             spawn_agent_assets.run_if(|c: Res<CTFConfig>| !c.headless),
@@ -1,17 +1,22 @@
 use avian3d::prelude::*;
 use bevy::prelude::*;
 
-use crate::agent::COLLISION_LAYER_AGENT;
+use crate::agent::{Agent, AgentId, AgentIndex, COLLISION_LAYER_AGENT};
 use crate::character_controller::CharacterControllerBundle;
 use crate::core::{COLLISION_LAYER_GROUND, CTFConfig};
 use crate::interaction_range::VisibleRange;
+use crate::rules::GameRules;
 use crate::team::{COLLISION_LAYER_BLUE, COLLISION_LAYER_RED, Team, TeamId};
 use crate::wall::COLLISION_LAYER_WALL;
 
 use super::components::AgentBundle;
 use super::visual::AgentGraphicsAssets;
 
-pub fn spawn_agents_headless(mut commands: Commands, config: Res<CTFConfig>) {
+pub fn spawn_agents_headless(
+    mut commands: Commands,
+    config: Res<CTFConfig>,
+    rules: Res<GameRules>,
+) {
     for (team, positions) in [
         (TeamId::Red, &config.red_team_agent_positions),
         (TeamId::Blue, &config.blue_team_agent_positions),
@@ -39,9 +44,14 @@ pub fn spawn_agents_headless(mut commands: Commands, config: Res<CTFConfig>) {
 
             commands.spawn((
                 AgentBundle {
+                    id: AgentId(team.slot_id(i)),
                     name: Name::new(name),
+                    agent: Agent {
+                        speed: rules.agent_default_speed,
+                        flag: None,
+                        in_home_territory: false,
+                    },
                     team: Team(team),
-                    ..Default::default()
                 },
                 VisibleRange,
                 Transform::from_xyz(position.0, 0.0, position.1),
@@ -58,6 +68,7 @@ pub fn spawn_agents(
     mut commands: Commands,
     graphics: Res<AgentGraphicsAssets>,
     config: Res<CTFConfig>,
+    rules: Res<GameRules>,
 ) {
     for (team, positions) in [
         (TeamId::Red, &config.red_team_agent_positions),
@@ -91,9 +102,14 @@ pub fn spawn_agents(
 
             commands.spawn((
                 AgentBundle {
+                    id: AgentId(team.slot_id(i)),
                     name: Name::new(name),
+                    agent: Agent {
+                        speed: rules.agent_default_speed,
+                        flag: None,
+                        in_home_territory: false,
+                    },
                     team: Team(team),
-                    ..Default::default()
                 },
                 VisibleRange,
                 Mesh3d(graphics.mesh.clone()),
@@ -107,3 +123,16 @@ pub fn spawn_agents(
         }
     }
 }
+
+pub fn index_agents(
+    mut index: ResMut<AgentIndex>,
+    added: Query<(Entity, &AgentId), Added<AgentId>>,
+    mut removed: RemovedComponents<AgentId>,
+) {
+    for entity in removed.read() {
+        index.0.retain(|_, e| *e != entity);
+    }
+    for (entity, id) in &added {
+        index.0.insert(*id, entity);
+    }
+}
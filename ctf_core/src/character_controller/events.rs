@@ -1,6 +1,7 @@
 use avian3d::math::*;
 use bevy::prelude::*;
 
+/// Moves or turns the agent with the given [`AgentId`](crate::agent::AgentId).
 #[derive(Event)]
 #[allow(dead_code)]
 pub enum MovementEvent {
@@ -8,10 +8,20 @@ pub use components::*;
 #[allow(unused_imports)]
 pub use events::*;
 
+/// Applies the frame's `MovementEvent`s. Systems writing them run before it
+/// for them to take effect the same frame.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MovementSet;
+
 pub struct CharacterControllerPlugin;
 impl Plugin for CharacterControllerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<events::MovementEvent>()
-            .add_systems(Update, (systems::update_grounded, systems::movement));
+        app.add_event::<events::MovementEvent>().add_systems(
+            Update,
+            (
+                systems::update_grounded,
+                systems::movement.in_set(MovementSet),
+            ),
+        );
     }
 }
@@ -1,6 +1,7 @@
 use avian3d::prelude::*;
 use bevy::prelude::*;
 
+use crate::agent::AgentIndex;
 use crate::interaction_range::RecentlyDropped;
 
 use super::components::{CharacterController, Grounded};
@@ -20,37 +21,34 @@ pub fn update_grounded(
     }
 }
 
-#[allow(clippy::type_complexity)]
 pub fn movement(
     mut movement_event_reader: EventReader<MovementEvent>,
+    index: Res<AgentIndex>,
     mut controllers: Query<(
-        Entity,
         &mut LinearVelocity,
         &mut AngularVelocity,
-        Option<&RecentlyDropped>,
+        Has<RecentlyDropped>,
         Has<Grounded>,
     )>,
 ) {
     for event in movement_event_reader.read() {
-        for (entity, mut linear_velocity, mut angular_velocity, recently_tagged, is_grounded) in
-            &mut controllers
-        {
-            if recently_tagged.is_some() {
-                continue;
-            }
+        let (MovementEvent::TranslateById(id, _) | MovementEvent::RotateById(id, _)) = *event;
+        let Some(Ok((mut linear_velocity, mut angular_velocity, recently_tagged, is_grounded))) =
+            index.get(id).map(|entity| controllers.get_mut(entity))
+        else {
+            continue;
+        };
+        if recently_tagged || !is_grounded {
+            continue;
+        }
 
-            match *event {
-                MovementEvent::TranslateById(id, velocity) => {
-                    if is_grounded && entity.index() == id {
-                        linear_velocity.x = velocity.x;
-                        linear_velocity.z = velocity.y;
-                    }
-                }
-                MovementEvent::RotateById(id, omega) => {
-                    if is_grounded && entity.index() == id {
-                        angular_velocity.y = omega;
-                    }
-                }
+        match *event {
+            MovementEvent::TranslateById(_, velocity) => {
+                linear_velocity.x = velocity.x;
+                linear_velocity.z = velocity.y;
+            }
+            MovementEvent::RotateById(_, omega) => {
+                angular_velocity.y = omega;
             }
         }
     }
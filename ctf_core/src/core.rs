@@ -1,12 +1,23 @@
+use std::time::Duration;
+
 use avian3d::prelude::*;
+use bevy::ecs::schedule::ExecutorKind;
 use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 
 use crate::agent::{AgentPlugin, COLLISION_LAYER_AGENT};
 use crate::camera::CameraPlugin;
 use crate::character_controller::CharacterControllerPlugin;
 use crate::flag::FlagPlugin;
 use crate::interaction_range::InteractionRangePlugin;
+use crate::map::ArenaMap;
+use crate::match_state::MatchPlugin;
+use crate::navigation::NavigationPlugin;
+use crate::rules::GameRules;
 use crate::team::TeamPlugin;
+use crate::territory::TerritoryPlugin;
 use crate::wall::WallPlugin;
 
 pub const COLLISION_LAYER_GROUND: u32 = 1 << 4;
@@ -20,9 +31,16 @@ pub struct CTFConfig {
     pub blue_team_flag_positions: Vec<(f32, f32)>,
     pub red_team_capture_point_positions: Vec<(f32, f32)>,
     pub blue_team_capture_point_positions: Vec<(f32, f32)>,
+    pub map: ArenaMap,
     pub headless: bool,
+    pub replay: bool,
 }
 
+/// The single source of randomness for the simulation. Seeded from
+/// `CTFPlugin::seed` when given, otherwise from the OS.
+#[derive(Resource)]
+pub struct SimRng(pub StdRng);
+
 pub struct CTFPlugin {
     pub red_team_agent_positions: Vec<(f32, f32)>,
     pub blue_team_agent_positions: Vec<(f32, f32)>,
@@ -30,7 +48,16 @@ pub struct CTFPlugin {
     pub blue_team_flag_positions: Vec<(f32, f32)>,
     pub red_team_capture_point_positions: Vec<(f32, f32)>,
     pub blue_team_capture_point_positions: Vec<(f32, f32)>,
+    pub map: ArenaMap,
+    pub rules: GameRules,
+    pub seed: Option<u64>,
+    /// If set, every frame advances virtual (and therefore physics) time by
+    /// exactly this many seconds, regardless of wall-clock time.
+    pub fixed_timestep: Option<f32>,
     pub headless: bool,
+    /// If set, gameplay and physics are disabled so the world can be driven
+    /// from a recording.
+    pub replay: bool,
 }
 
 impl Plugin for CTFPlugin {
@@ -41,18 +68,66 @@ impl Plugin for CTFPlugin {
             CharacterControllerPlugin,
             FlagPlugin,
             InteractionRangePlugin,
+            MatchPlugin,
+            NavigationPlugin,
             TeamPlugin,
+            TerritoryPlugin,
             WallPlugin,
         ));
         app.register_type::<CTFConfig>();
+        app.register_type::<GameRules>();
+        app.insert_resource(self.rules.clone());
+
+        let rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        app.insert_resource(SimRng(rng));
+
+        if let Some(dt) = self.fixed_timestep {
+            let dt = Duration::from_secs_f32(dt);
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(dt));
+            // virtual time clamps each frame to max_delta, 250ms by default
+            let mut virtual_time = Time::<Virtual>::default();
+            virtual_time.set_max_delta(dt.max(virtual_time.max_delta()));
+            app.insert_resource(virtual_time);
+            app.insert_resource(Time::<Fixed>::from_duration(dt));
+            // Ambiguously ordered systems must run in the same order every frame.
+            app.edit_schedule(Update, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            });
+        }
+
+        // Positions given explicitly take precedence over the ones in the map.
+        let map = &self.map;
         app.insert_resource(CTFConfig {
-            red_team_agent_positions: self.red_team_agent_positions.clone(),
-            blue_team_agent_positions: self.blue_team_agent_positions.clone(),
-            red_team_flag_positions: self.red_team_flag_positions.clone(),
-            blue_team_flag_positions: self.blue_team_flag_positions.clone(),
-            red_team_capture_point_positions: self.red_team_capture_point_positions.clone(),
-            blue_team_capture_point_positions: self.blue_team_capture_point_positions.clone(),
+            red_team_agent_positions: or_map(
+                &self.red_team_agent_positions,
+                &map.red_team_agent_positions,
+            ),
+            blue_team_agent_positions: or_map(
+                &self.blue_team_agent_positions,
+                &map.blue_team_agent_positions,
+            ),
+            red_team_flag_positions: or_map(
+                &self.red_team_flag_positions,
+                &map.red_team_flag_positions,
+            ),
+            blue_team_flag_positions: or_map(
+                &self.blue_team_flag_positions,
+                &map.blue_team_flag_positions,
+            ),
+            red_team_capture_point_positions: or_map(
+                &self.red_team_capture_point_positions,
+                &map.red_team_capture_point_positions,
+            ),
+            blue_team_capture_point_positions: or_map(
+                &self.blue_team_capture_point_positions,
+                &map.blue_team_capture_point_positions,
+            ),
+            map: self.map.clone(),
             headless: self.headless,
+            replay: self.replay,
         });
 
         app.add_systems(Startup, setup_scene.run_if(|c: Res<CTFConfig>| !c.headless));
@@ -60,9 +135,22 @@ impl Plugin for CTFPlugin {
             Startup,
             setup_scene_headless.run_if(|c: Res<CTFConfig>| c.headless),
         );
+        app.add_systems(Startup, pause_physics.run_if(|c: Res<CTFConfig>| c.replay));
     }
 }
 
+fn or_map(positions: &[(f32, f32)], map_positions: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    if positions.is_empty() {
+        map_positions.to_vec()
+    } else {
+        positions.to_vec()
+    }
+}
+
+fn pause_physics(mut physics_time: ResMut<Time<Physics>>) {
+    physics_time.pause();
+}
+
 fn setup_scene_headless(mut commands: Commands) {
     commands.spawn((
         Name::new("Ground Plane"),
@@ -1,10 +1,19 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::flag::FLAG_INTERACTION_RADIUS;
 use crate::interaction_range::{InteractionRadius, VisibleRange};
 use crate::team::TeamId;
 
+/// The public ID of a flag, assigned from its team and config slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct FlagId(pub u32);
+
+/// The public ID of a capture point, assigned from its team and config slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct CapturePointId(pub u32);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 pub enum FlagStatus {
     Dropped,
@@ -29,14 +38,14 @@ pub struct FlagBundle {
 }
 
 impl FlagBundle {
-    pub fn new(name: &str, team: TeamId, position: Vec3) -> Self {
+    pub fn new(name: &str, team: TeamId, position: Vec3, interaction_radius: f32) -> Self {
         Self {
             name: Name::new(name.to_string()),
             flag: Flag {
                 team,
                 status: FlagStatus::Dropped,
             },
-            interaction_radius: InteractionRadius(FLAG_INTERACTION_RADIUS),
+            interaction_radius: InteractionRadius(interaction_radius),
             transform: Transform::from_translation(position),
             visibile_range: VisibleRange,
         }
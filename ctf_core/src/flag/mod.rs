@@ -21,6 +21,8 @@ pub struct FlagPlugin;
 impl Plugin for FlagPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<components::FlagCaptureCounts>();
+        app.register_type::<components::FlagId>();
+        app.register_type::<components::CapturePointId>();
         app.init_resource::<components::FlagCaptureCounts>();
         app.add_systems(
             PreStartup,
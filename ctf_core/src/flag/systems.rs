@@ -4,14 +4,15 @@ use bevy::prelude::*;
 use crate::core::CTFConfig;
 use crate::flag::{
     COLLISION_LAYER_CAMP_BLOCK_BLUE, COLLISION_LAYER_CAMP_BLOCK_RED, COLLISION_LAYER_FLAG_OR_CP,
-    CapturePointBundle, KEEP_AWAY_RADIUS,
+    CapturePointBundle, CapturePointId, FlagId,
 };
+use crate::rules::GameRules;
 use crate::team::{COLLISION_LAYER_BLUE, COLLISION_LAYER_RED, TeamId};
 
 use super::components::FlagBundle;
 use super::visual::{CapturePointGraphicsAssets, FlagGraphicsAssets};
 
-pub fn spawn_flags_headless(mut commands: Commands, config: Res<CTFConfig>) {
+pub fn spawn_flags_headless(mut commands: Commands, config: Res<CTFConfig>, rules: Res<GameRules>) {
     for (team, positions) in [
         (TeamId::Red, &config.red_team_flag_positions),
         (TeamId::Blue, &config.blue_team_flag_positions),
@@ -33,8 +34,14 @@ pub fn spawn_flags_headless(mut commands: Commands, config: Res<CTFConfig>) {
             };
 
             commands.spawn((
-                FlagBundle::new(&flag_name, team, Vec3::new(position.0, 0.0, position.1)),
-                Collider::cylinder(KEEP_AWAY_RADIUS, 1.0),
+                FlagId(team.slot_id(i)),
+                FlagBundle::new(
+                    &flag_name,
+                    team,
+                    Vec3::new(position.0, 0.0, position.1),
+                    rules.flag_interaction_radius,
+                ),
+                Collider::cylinder(rules.keep_away_radius, 1.0),
                 RigidBody::Static,
                 collision_layer,
             ));
@@ -46,6 +53,7 @@ pub fn spawn_flags(
     mut commands: Commands,
     flag_graphics: Res<FlagGraphicsAssets>,
     config: Res<CTFConfig>,
+    rules: Res<GameRules>,
 ) {
     for (team, positions) in [
         (TeamId::Red, &config.red_team_flag_positions),
@@ -68,13 +76,19 @@ pub fn spawn_flags(
             };
 
             commands.spawn((
-                FlagBundle::new(&flag_name, team, Vec3::new(position.0, 0.0, position.1)),
+                FlagId(team.slot_id(i)),
+                FlagBundle::new(
+                    &flag_name,
+                    team,
+                    Vec3::new(position.0, 0.0, position.1),
+                    rules.flag_interaction_radius,
+                ),
                 Mesh3d(flag_graphics.mesh.clone()),
                 MeshMaterial3d(match team {
                     TeamId::Blue => flag_graphics.blue_material.clone(),
                     TeamId::Red => flag_graphics.red_material.clone(),
                 }),
-                Collider::cylinder(KEEP_AWAY_RADIUS, 2.0),
+                Collider::cylinder(rules.keep_away_radius, 2.0),
                 RigidBody::Static,
                 collision_layer,
             ));
@@ -82,7 +96,11 @@ pub fn spawn_flags(
     }
 }
 
-pub fn spawn_capture_points_headless(mut commands: Commands, config: Res<CTFConfig>) {
+pub fn spawn_capture_points_headless(
+    mut commands: Commands,
+    config: Res<CTFConfig>,
+    rules: Res<GameRules>,
+) {
     for (team, positions) in [
         (TeamId::Red, &config.red_team_capture_point_positions),
         (TeamId::Blue, &config.blue_team_capture_point_positions),
@@ -104,8 +122,9 @@ pub fn spawn_capture_points_headless(mut commands: Commands, config: Res<CTFConf
             };
 
             commands.spawn((
+                CapturePointId(team.slot_id(i)),
                 CapturePointBundle::new(&name, team, Vec3::new(position.0, 0.0, position.1)),
-                Collider::cylinder(KEEP_AWAY_RADIUS, 2.0),
+                Collider::cylinder(rules.keep_away_radius, 2.0),
                 RigidBody::Static,
                 collision_layer,
             ));
@@ -117,6 +136,7 @@ pub fn spawn_capture_points(
     mut commands: Commands,
     capture_point_graphics: Res<CapturePointGraphicsAssets>,
     config: Res<CTFConfig>,
+    rules: Res<GameRules>,
 ) {
     for (team, positions) in [
         (TeamId::Red, &config.red_team_capture_point_positions),
@@ -139,13 +159,14 @@ pub fn spawn_capture_points(
             };
 
             commands.spawn((
+                CapturePointId(team.slot_id(i)),
                 CapturePointBundle::new(&name, team, Vec3::new(position.0, 0.0, position.1)),
                 Mesh3d(capture_point_graphics.mesh.clone()),
                 MeshMaterial3d(match team {
                     TeamId::Red => capture_point_graphics.blue_material.clone(),
                     TeamId::Blue => capture_point_graphics.red_material.clone(),
                 }),
-                Collider::cylinder(KEEP_AWAY_RADIUS, 1.0),
+                Collider::cylinder(rules.keep_away_radius, 1.0),
                 RigidBody::Static,
                 collision_layer,
             ));
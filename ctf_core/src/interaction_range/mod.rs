@@ -7,6 +7,9 @@ use bevy::prelude::*;
 
 use crate::core::CTFConfig;
 pub use crate::interaction_range::components::*;
+pub use crate::interaction_range::events::*;
+use crate::rules::GameRules;
+use crate::territory::TaggingMode;
 
 #[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PickupSet {
@@ -17,10 +20,15 @@ pub enum PickupSet {
 pub struct InteractionRangePlugin;
 impl Plugin for InteractionRangePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<events::FlagPickupEvent>();
-        app.add_event::<events::FlagDropEvent>();
-        app.add_event::<events::FlagScoreEvent>();
-        app.configure_sets(Update, (PickupSet::Detect, PickupSet::Apply).chain());
+        app.add_event::<FlagPickupEvent>();
+        app.add_event::<FlagDropEvent>();
+        app.add_event::<FlagScoreEvent>();
+        app.configure_sets(
+            Update,
+            (PickupSet::Detect, PickupSet::Apply)
+                .chain()
+                .run_if(|c: Res<CTFConfig>| !c.replay),
+        );
 
         app.add_systems(
             PreStartup,
@@ -36,14 +44,18 @@ impl Plugin for InteractionRangePlugin {
                 .run_if(|c: Res<CTFConfig>| !c.headless),
         );
 
-        app.add_systems(Update, systems::tick_recently_dropped);
+        app.add_systems(
+            Update,
+            systems::tick_recently_dropped.run_if(|c: Res<CTFConfig>| !c.replay),
+        );
 
         app.add_systems(
             Update,
             (
                 systems::detect_flag_pickups,
                 systems::detect_flag_capture,
-                systems::detect_flag_drop,
+                systems::detect_flag_drop
+                    .run_if(|r: Res<GameRules>| r.tagging == TaggingMode::Legacy),
             )
                 .in_set(PickupSet::Detect),
         );
@@ -1,15 +1,13 @@
 use avian3d::prelude::*;
 use bevy::prelude::*;
+use rand::Rng;
 
-use crate::agent::{
-    AGENT_COOLDOWN_TIME, AGENT_DEFAULT_SPEED, AGENT_FLAG_SPEED, AGENT_TAG_RADIUS, Agent,
-    AgentGraphicsAssets,
-};
-use crate::flag::{
-    CapturePoint, FLAG_COOLDOWN_TIME, FLAG_SPAWN_RADIUS, Flag, FlagCaptureCounts, FlagStatus,
-};
+use crate::agent::{Agent, AgentGraphicsAssets};
+use crate::core::{CTFConfig, SimRng};
+use crate::flag::{CapturePoint, Flag, FlagCaptureCounts, FlagStatus};
 use crate::interaction_range::RecentlyDropped;
 use crate::interaction_range::events::{FlagDropEvent, FlagScoreEvent};
+use crate::rules::GameRules;
 use crate::team::{Team, TeamId};
 use crate::{Segment2D, segment_hits_wall_flag_or_capture_point};
 
@@ -113,6 +111,7 @@ pub fn handle_flag_pickups(
     mut agents: Query<&mut Agent>,
     mut flags: Query<(&mut Flag, &mut Visibility, &mut Transform), Without<RecentlyDropped>>,
     agent_graphics: Option<Res<AgentGraphicsAssets>>,
+    rules: Res<GameRules>,
 ) {
     for FlagPickupEvent {
         agent: agent_entity,
@@ -142,11 +141,11 @@ pub fn handle_flag_pickups(
                 .entity(agent_entity)
                 .insert(MeshMaterial3d(pickup_material));
         }
-        agent.speed = AGENT_FLAG_SPEED;
+        agent.speed = rules.agent_flag_speed;
 
         commands
             .entity(agent_entity)
-            .insert(InteractionRadius(AGENT_TAG_RADIUS));
+            .insert(InteractionRadius(rules.agent_tag_radius));
 
         flag.status = FlagStatus::PickedUp;
         commands.entity(flag_entity).insert(ChildOf(agent_entity));
@@ -191,6 +190,7 @@ pub fn detect_flag_capture(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_flag_capture(
     mut commands: Commands,
     mut reader: EventReader<FlagScoreEvent>,
@@ -199,6 +199,7 @@ pub fn handle_flag_capture(
     mut capture_points: Query<&mut CapturePoint>,
     mut capture_counts: ResMut<FlagCaptureCounts>,
     agent_graphics: Option<Res<AgentGraphicsAssets>>,
+    rules: Res<GameRules>,
 ) {
     for FlagScoreEvent {
         agent: agent_entity,
@@ -239,7 +240,7 @@ pub fn handle_flag_capture(
                 .entity(agent_entity)
                 .insert(MeshMaterial3d(default_material));
         }
-        agent.speed = AGENT_DEFAULT_SPEED;
+        agent.speed = rules.agent_default_speed;
 
         flag.status = FlagStatus::Captured;
         capture_point.flag = Some(flag_entity);
@@ -301,6 +302,7 @@ pub fn detect_flag_drop(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_flag_drop(
     mut reader: EventReader<FlagDropEvent>,
     mut commands: Commands,
@@ -308,7 +310,13 @@ pub fn handle_flag_drop(
     mut flags: Query<(&mut Flag, &mut Visibility, &GlobalTransform)>,
     spatial_query: SpatialQuery,
     agent_graphics: Option<Res<AgentGraphicsAssets>>,
+    config: Res<CTFConfig>,
+    rules: Res<GameRules>,
+    mut rng: ResMut<SimRng>,
 ) {
+    // keep dropped flags one unit inside the outermost walls
+    let bounds = config.map.bounds().inflate(-1.0);
+
     for FlagDropEvent {
         agent: agent_entity,
         flag: flag_entity,
@@ -339,7 +347,7 @@ pub fn handle_flag_drop(
         commands
             .entity(agent_entity)
             .insert(RecentlyDropped(Timer::from_seconds(
-                AGENT_COOLDOWN_TIME,
+                rules.agent_cooldown_time,
                 TimerMode::Once,
             )));
 
@@ -348,22 +356,22 @@ pub fn handle_flag_drop(
         commands
             .entity(flag_entity)
             .insert(RecentlyDropped(Timer::from_seconds(
-                FLAG_COOLDOWN_TIME,
+                rules.flag_cooldown_time,
                 TimerMode::Once,
             )));
 
         let mut drop_world = Vec3::ZERO;
         let mut found = false;
         for _ in 0..100 {
-            let angle = rand::random::<f32>() * std::f32::consts::TAU;
+            let angle = rng.0.random::<f32>() * std::f32::consts::TAU;
             let offset = Vec3::new(
-                FLAG_SPAWN_RADIUS * angle.cos(),
+                rules.flag_spawn_radius * angle.cos(),
                 0.0,
-                FLAG_SPAWN_RADIUS * angle.sin(),
+                rules.flag_spawn_radius * angle.sin(),
             );
             drop_world = global_tf.translation() + offset;
-            drop_world.x = drop_world.x.clamp(-49.0, 49.0);
-            drop_world.z = drop_world.z.clamp(-49.0, 49.0);
+            drop_world.x = drop_world.x.clamp(bounds.min.x, bounds.max.x);
+            drop_world.z = drop_world.z.clamp(bounds.min.y, bounds.max.y);
 
             if !segment_hits_wall_flag_or_capture_point(
                 &spatial_query,
@@ -8,7 +8,13 @@ pub mod core;
 pub mod debug;
 pub mod flag;
 pub mod interaction_range;
+pub mod map;
+pub mod match_state;
+pub mod navigation;
+pub mod policy;
+pub mod rules;
 pub mod team;
+pub mod territory;
 pub mod wall;
 
 #[derive(Debug, Clone, Copy)]
@@ -17,19 +23,49 @@ pub struct Segment2D {
     pub end: Vec2,
 }
 
+/// Walls plus the keep-away zones that block `team_id`.
+pub(crate) fn obstacle_filter(team_id: team::TeamId) -> SpatialQueryFilter {
+    let block_mask = match team_id {
+        team::TeamId::Red => wall::COLLISION_LAYER_WALL | flag::COLLISION_LAYER_CAMP_BLOCK_RED,
+        team::TeamId::Blue => wall::COLLISION_LAYER_WALL | flag::COLLISION_LAYER_CAMP_BLOCK_BLUE,
+    };
+    SpatialQueryFilter::from_mask(LayerMask(block_mask))
+}
+
 pub fn segment_hits_wall_flag_or_capture_point(
     spatial: &SpatialQuery,
     seg: Segment2D,
     team_id: team::TeamId,
 ) -> bool {
-    let shape = Collider::cuboid(1.0, 1.0, 1.0);
+    segment_hits(spatial, seg, &obstacle_filter(team_id))
+}
 
-    // 1) Walls: simple mask
-    let block_mask = match team_id {
-        team::TeamId::Red => wall::COLLISION_LAYER_WALL | flag::COLLISION_LAYER_CAMP_BLOCK_RED,
-        team::TeamId::Blue => wall::COLLISION_LAYER_WALL | flag::COLLISION_LAYER_CAMP_BLOCK_BLUE,
+/// Whether nothing but open floor lies between `from` and `to`. Only walls
+/// block sight.
+pub fn line_of_sight(spatial: &SpatialQuery, from: Vec2, to: Vec2) -> bool {
+    let filter = SpatialQueryFilter::from_mask(LayerMask(wall::COLLISION_LAYER_WALL));
+    let delta = to - from;
+    // a thin ray, so that standing next to a wall does not block sight
+    let Ok(direction) = Dir3::new(Vec3::new(delta.x, 0.0, delta.y)) else {
+        return true;
     };
-    let filter = SpatialQueryFilter::from_mask(LayerMask(block_mask));
+    spatial
+        .cast_ray(
+            Vec3::new(from.x, 0.5, from.y),
+            direction,
+            delta.length(),
+            true,
+            &filter,
+        )
+        .is_none()
+}
+
+pub(crate) fn segment_hits(
+    spatial: &SpatialQuery,
+    seg: Segment2D,
+    filter: &SpatialQueryFilter,
+) -> bool {
+    let shape = Collider::cuboid(1.0, 1.0, 1.0);
 
     let start = Vec3::new(seg.start.x, 0.5, seg.start.y);
     let end = Vec3::new(seg.end.x, 0.5, seg.end.y);
@@ -39,7 +75,7 @@ pub fn segment_hits_wall_flag_or_capture_point(
 
     if !dist.is_finite() || dist <= 1e-4 {
         return !spatial
-            .shape_intersections(&shape, start, rot, &filter)
+            .shape_intersections(&shape, start, rot, filter)
             .is_empty();
     }
 
@@ -50,7 +86,50 @@ pub fn segment_hits_wall_flag_or_capture_point(
             rot,
             Dir3::new(delta).unwrap(),
             &ShapeCastConfig::from_max_distance(dist),
-            &filter,
+            filter,
         )
         .is_some()
 }
+
+/// Distance from `origin` along `direction` to the first obstacle for
+/// `team_id`, if there is one within `max_distance`.
+pub fn raycast_obstacle(
+    spatial: &SpatialQuery,
+    origin: Vec2,
+    direction: Vec2,
+    max_distance: f32,
+    team_id: team::TeamId,
+) -> Option<f32> {
+    let direction = Dir3::new(Vec3::new(direction.x, 0.0, direction.y)).ok()?;
+    spatial
+        .cast_ray(
+            Vec3::new(origin.x, 0.5, origin.y),
+            direction,
+            max_distance,
+            true,
+            &obstacle_filter(team_id),
+        )
+        .map(|hit| hit.distance)
+}
+
+/// Horizontal distance from `point` to the closest obstacle for `team_id`,
+/// or zero if the point is inside one. `None` if there are no obstacles.
+pub fn nearest_obstacle_distance(
+    spatial: &SpatialQuery,
+    point: Vec2,
+    team_id: team::TeamId,
+) -> Option<f32> {
+    spatial
+        .project_point(
+            Vec3::new(point.x, 0.5, point.y),
+            true,
+            &obstacle_filter(team_id),
+        )
+        .map(|projection| {
+            if projection.is_inside {
+                0.0
+            } else {
+                projection.point.xz().distance(point)
+            }
+        })
+}
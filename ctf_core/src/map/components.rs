@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::territory::Territory;
+use crate::wall::WALL_THICKNESS;
+
+/// A single wall, described as a polyline. Every pair of consecutive points
+/// becomes one wall segment of the given thickness.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct WallSpec {
+    pub points: Vec<(f32, f32)>,
+    #[serde(default = "default_wall_thickness")]
+    pub thickness: f32,
+}
+
+fn default_wall_thickness() -> f32 {
+    WALL_THICKNESS
+}
+
+impl WallSpec {
+    pub fn segments(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        self.points
+            .windows(2)
+            .map(|w| (Vec2::from(w[0]), Vec2::from(w[1])))
+    }
+}
+
+/// An arena layout: the walls plus optional flag, capture point and spawn
+/// positions. Positions set in the config take precedence over the ones
+/// here, which are used where the config leaves them empty.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct ArenaMap {
+    #[serde(default)]
+    pub name: String,
+    pub walls: Vec<WallSpec>,
+    #[serde(default)]
+    pub red_team_agent_positions: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub blue_team_agent_positions: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub red_team_flag_positions: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub blue_team_flag_positions: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub red_team_capture_point_positions: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub blue_team_capture_point_positions: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub territory: Territory,
+    /// Where tagged red agents are sent under the jail penalty.
+    #[serde(default)]
+    pub red_team_jail_position: Option<(f32, f32)>,
+    /// Where tagged blue agents are sent under the jail penalty.
+    #[serde(default)]
+    pub blue_team_jail_position: Option<(f32, f32)>,
+}
+
+impl ArenaMap {
+    /// Axis-aligned bounds of all wall points.
+    pub fn bounds(&self) -> Rect {
+        self.walls
+            .iter()
+            .flat_map(|w| w.points.iter())
+            .fold(Rect::EMPTY, |r, &p| r.union_point(Vec2::from(p)))
+    }
+}
+
+impl Default for ArenaMap {
+    /// The original arena: outer box, side bars, middle bars and the two
+    /// center diamonds.
+    fn default() -> Self {
+        let wall = |points: &[(f32, f32)]| WallSpec {
+            points: points.to_vec(),
+            thickness: WALL_THICKNESS,
+        };
+
+        Self {
+            name: "default".to_string(),
+            walls: vec![
+                // Outer box
+                wall(&[(-50.0, 50.0), (50.0, 50.0)]),
+                wall(&[(50.0, 50.0), (50.0, -50.0)]),
+                wall(&[(50.0, -50.0), (-50.0, -50.0)]),
+                wall(&[(-50.0, -50.0), (-50.0, 50.0)]),
+                // Side bars
+                wall(&[(-45.0, 45.0), (-45.0, 5.0)]),
+                wall(&[(-45.0, -5.0), (-45.0, -45.0)]),
+                wall(&[(45.0, 45.0), (45.0, 5.0)]),
+                wall(&[(45.0, -5.0), (45.0, -45.0)]),
+                // Middle horizontal bars
+                wall(&[(-10.0, 5.0), (10.0, 5.0)]),
+                wall(&[(-10.0, -5.0), (10.0, -5.0)]),
+                // Center diamonds
+                wall(&[(-5.0, 0.0), (-35.0, 30.0)]),
+                wall(&[(-35.0, -30.0), (-5.0, 0.0)]),
+                wall(&[(-5.0, 0.0), (25.0, -30.0)]),
+                wall(&[(25.0, 20.0), (5.0, 0.0)]),
+                wall(&[(5.0, 0.0), (35.0, 30.0)]),
+                wall(&[(35.0, -30.0), (5.0, 0.0)]),
+                wall(&[(5.0, 0.0), (-25.0, 30.0)]),
+                wall(&[(-25.0, -20.0), (-5.0, 0.0)]),
+            ],
+            red_team_agent_positions: vec![],
+            blue_team_agent_positions: vec![],
+            red_team_flag_positions: vec![],
+            blue_team_flag_positions: vec![],
+            red_team_capture_point_positions: vec![],
+            blue_team_capture_point_positions: vec![],
+            territory: Territory::default(),
+            red_team_jail_position: None,
+            blue_team_jail_position: None,
+        }
+    }
+}
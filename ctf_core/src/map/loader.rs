@@ -0,0 +1,111 @@
+use std::fmt;
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::territory::Territory;
+
+use super::components::ArenaMap;
+
+#[derive(Debug)]
+pub enum MapError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(e) => write!(f, "failed to read map: {e}"),
+            MapError::Parse(e) => write!(f, "failed to parse map: {e}"),
+            MapError::Invalid(msg) => write!(f, "invalid map: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl ArenaMap {
+    /// Loads and validates a map from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        let json = std::fs::read_to_string(path).map_err(MapError::Io)?;
+        Self::from_json(&json)
+    }
+
+    /// Parses and validates a map from a JSON string.
+    pub fn from_json(json: &str) -> Result<Self, MapError> {
+        let map: ArenaMap = serde_json::from_str(json).map_err(MapError::Parse)?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    pub fn validate(&self) -> Result<(), MapError> {
+        if self.walls.is_empty() {
+            return Err(MapError::Invalid("map has no walls".to_string()));
+        }
+
+        for (i, wall) in self.walls.iter().enumerate() {
+            if wall.points.len() < 2 {
+                return Err(MapError::Invalid(format!(
+                    "wall {i} needs at least two points"
+                )));
+            }
+            if !wall.thickness.is_finite() || wall.thickness <= 0.0 {
+                return Err(MapError::Invalid(format!(
+                    "wall {i} has non-positive thickness {}",
+                    wall.thickness
+                )));
+            }
+            if wall
+                .points
+                .iter()
+                .any(|(x, y)| !x.is_finite() || !y.is_finite())
+            {
+                return Err(MapError::Invalid(format!(
+                    "wall {i} has a non-finite point"
+                )));
+            }
+        }
+
+        let bounds = self.bounds();
+        for (label, positions) in [
+            ("red agent", self.red_team_agent_positions.as_slice()),
+            ("blue agent", self.blue_team_agent_positions.as_slice()),
+            ("red flag", self.red_team_flag_positions.as_slice()),
+            ("blue flag", self.blue_team_flag_positions.as_slice()),
+            (
+                "red capture point",
+                self.red_team_capture_point_positions.as_slice(),
+            ),
+            (
+                "blue capture point",
+                self.blue_team_capture_point_positions.as_slice(),
+            ),
+            ("red jail", self.red_team_jail_position.as_slice()),
+            ("blue jail", self.blue_team_jail_position.as_slice()),
+        ] {
+            for &(x, y) in positions {
+                if !bounds.contains(Vec2::new(x, y)) {
+                    return Err(MapError::Invalid(format!(
+                        "{label} position ({x}, {y}) is outside the map bounds"
+                    )));
+                }
+            }
+        }
+
+        if let Territory::Polygons { red, blue } = &self.territory {
+            for (team, polygons) in [("red", red), ("blue", blue)] {
+                for (i, polygon) in polygons.iter().enumerate() {
+                    if polygon.len() < 3 {
+                        return Err(MapError::Invalid(format!(
+                            "{team} territory polygon {i} needs at least three points"
+                        )));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod components;
+mod loader;
+
+pub use components::*;
+pub use loader::*;
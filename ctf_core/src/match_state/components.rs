@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::team::TeamId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum MatchEndReason {
+    TimeLimit,
+    ScoreLimit,
+    AllFlagsCaptured,
+    /// The loser gave up or was disqualified.
+    Forfeit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub struct MatchOutcome {
+    /// `None` for a draw.
+    pub winner: Option<TeamId>,
+    pub reason: MatchEndReason,
+}
+
+#[derive(Debug, Clone, Default, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct MatchState {
+    /// Seconds of game time since the match started.
+    pub elapsed: f32,
+    pub time_limit: Option<f32>,
+    pub score_to_win: Option<u32>,
+    pub outcome: Option<MatchOutcome>,
+}
+
+impl MatchState {
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn remaining(&self) -> Option<f32> {
+        self.time_limit.map(|limit| (limit - self.elapsed).max(0.0))
+    }
+}
@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+
+use crate::match_state::MatchEndReason;
+use crate::team::TeamId;
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MatchEnded {
+    pub winner: Option<TeamId>,
+    pub reason: MatchEndReason,
+}
+
+/// Ends the match in the other team's favour.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MatchForfeited {
+    pub team: TeamId,
+}
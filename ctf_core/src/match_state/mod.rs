@@ -0,0 +1,50 @@
+mod components;
+mod events;
+mod systems;
+
+use bevy::prelude::*;
+
+pub use components::*;
+pub use events::*;
+
+use crate::core::CTFConfig;
+use crate::interaction_range::PickupSet;
+use crate::rules::GameRules;
+
+/// Runs after flag interactions have been applied, so anything ordered after
+/// it sees the final outcome of the frame.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MatchSet;
+
+pub struct MatchPlugin;
+impl Plugin for MatchPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<MatchState>();
+        app.init_resource::<MatchState>();
+        app.add_event::<MatchEnded>();
+        app.add_event::<MatchForfeited>();
+        app.configure_sets(Update, MatchSet.after(PickupSet::Apply));
+
+        app.add_systems(Startup, init_match_state);
+        app.add_systems(
+            Update,
+            (
+                systems::tick_match_clock,
+                systems::check_match_end,
+                systems::freeze_on_match_end,
+            )
+                .chain()
+                .in_set(MatchSet)
+                .run_if(|c: Res<CTFConfig>| !c.replay),
+        );
+        app.add_systems(
+            Last,
+            systems::exit_on_match_end.run_if(|c: Res<CTFConfig>| c.headless),
+        );
+    }
+}
+
+fn init_match_state(mut state: ResMut<MatchState>, rules: Res<GameRules>) {
+    state.time_limit = rules.time_limit;
+    state.score_to_win = rules.score_to_win;
+}
@@ -0,0 +1,120 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::flag::{Flag, FlagCaptureCounts, FlagStatus};
+use crate::team::TeamId;
+
+use super::components::{MatchEndReason, MatchOutcome, MatchState};
+use super::events::{MatchEnded, MatchForfeited};
+
+pub fn tick_match_clock(time: Res<Time>, mut state: ResMut<MatchState>) {
+    if !state.is_over() {
+        state.elapsed += time.delta_secs();
+    }
+}
+
+fn leader(scores: &FlagCaptureCounts) -> Option<TeamId> {
+    match scores.red.cmp(&scores.blue) {
+        std::cmp::Ordering::Greater => Some(TeamId::Red),
+        std::cmp::Ordering::Less => Some(TeamId::Blue),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// The outcome when red, blue or both lost for `reason`: the other team wins,
+/// or `tie_winner` if both did.
+fn lost(
+    red: bool,
+    blue: bool,
+    tie_winner: Option<TeamId>,
+    reason: MatchEndReason,
+) -> Option<MatchOutcome> {
+    let winner = match (red, blue) {
+        (true, true) => tie_winner,
+        (true, false) => Some(TeamId::Blue),
+        (false, true) => Some(TeamId::Red),
+        (false, false) => return None,
+    };
+    Some(MatchOutcome { winner, reason })
+}
+
+pub fn check_match_end(
+    mut state: ResMut<MatchState>,
+    scores: Res<FlagCaptureCounts>,
+    flags: Query<&Flag>,
+    mut forfeits: EventReader<MatchForfeited>,
+    mut writer: EventWriter<MatchEnded>,
+) {
+    if state.is_over() {
+        forfeits.clear();
+        return;
+    }
+
+    let (mut red_forfeits, mut blue_forfeits) = (false, false);
+    for forfeit in forfeits.read() {
+        match forfeit.team {
+            TeamId::Red => red_forfeits = true,
+            TeamId::Blue => blue_forfeits = true,
+        }
+    }
+
+    let all_captured = |team: TeamId| {
+        let mut team_flags = flags.iter().filter(|f| f.team == team).peekable();
+        team_flags.peek().is_some() && team_flags.all(|f| f.status == FlagStatus::Captured)
+    };
+
+    let outcome = lost(red_forfeits, blue_forfeits, None, MatchEndReason::Forfeit);
+    let outcome = outcome.or_else(|| {
+        lost(
+            all_captured(TeamId::Red),
+            all_captured(TeamId::Blue),
+            leader(&scores),
+            MatchEndReason::AllFlagsCaptured,
+        )
+    });
+
+    let outcome = outcome.or_else(|| {
+        let target = state.score_to_win?;
+        (scores.red >= target || scores.blue >= target).then(|| MatchOutcome {
+            winner: leader(&scores),
+            reason: MatchEndReason::ScoreLimit,
+        })
+    });
+
+    let outcome = outcome.or_else(|| {
+        let limit = state.time_limit?;
+        (state.elapsed >= limit).then(|| MatchOutcome {
+            winner: leader(&scores),
+            reason: MatchEndReason::TimeLimit,
+        })
+    });
+
+    if let Some(outcome) = outcome {
+        info!("Match over: {outcome:?}");
+        state.outcome = Some(outcome);
+        writer.write(MatchEnded {
+            winner: outcome.winner,
+            reason: outcome.reason,
+        });
+    }
+}
+
+pub fn freeze_on_match_end(
+    mut reader: EventReader<MatchEnded>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    if reader.read().next().is_some() {
+        physics_time.pause();
+    }
+}
+
+/// Exits once the match is over, with an error if a team forfeited.
+pub fn exit_on_match_end(mut reader: EventReader<MatchEnded>, mut exit: EventWriter<AppExit>) {
+    if let Some(ended) = reader.read().next() {
+        exit.write(if ended.reason == MatchEndReason::Forfeit {
+            AppExit::error()
+        } else {
+            AppExit::Success
+        });
+    }
+}
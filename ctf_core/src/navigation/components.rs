@@ -0,0 +1,305 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::team::TeamId;
+use crate::{Segment2D, obstacle_filter, segment_hits};
+
+use super::{NAV_CELL_SIZE, NAV_SNAP_RADIUS};
+
+/// An occupancy grid over the arena for one team. A cell is blocked if an
+/// agent standing at its center would overlap a wall or one of the team's
+/// keep-away zones, other than the `moving` ones it was built without.
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    origin: Vec2,
+    cell_size: f32,
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    /// Samples the physics world over `bounds`, leaving out the colliders in
+    /// `moving`.
+    pub fn build(
+        spatial: &SpatialQuery,
+        bounds: Rect,
+        cell_size: f32,
+        team: TeamId,
+        moving: &[Entity],
+    ) -> Self {
+        let filter = obstacle_filter(team).with_excluded_entities(moving.iter().copied());
+        let width = (bounds.width() / cell_size).ceil().max(1.0) as usize;
+        let height = (bounds.height() / cell_size).ceil().max(1.0) as usize;
+        let mut grid = Self {
+            origin: bounds.min,
+            cell_size,
+            width,
+            height,
+            blocked: vec![false; width * height],
+        };
+        for cell in 0..grid.blocked.len() {
+            let center = grid.center(cell);
+            let seg = Segment2D {
+                start: center,
+                end: center,
+            };
+            grid.blocked[cell] = segment_hits(spatial, seg, &filter);
+        }
+        grid
+    }
+
+    fn cell(&self, point: Vec2) -> Option<usize> {
+        let local = (point - self.origin) / self.cell_size;
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+        let (x, y) = (local.x as usize, local.y as usize);
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+
+    fn center(&self, cell: usize) -> Vec2 {
+        let (x, y) = (cell % self.width, cell / self.width);
+        self.origin + (Vec2::new(x as f32, y as f32) + 0.5) * self.cell_size
+    }
+
+    fn is_free(&self, x: isize, y: isize) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && !self.blocked[y as usize * self.width + x as usize]
+    }
+
+    /// The free cell closest to `point`, searching up to [`NAV_SNAP_RADIUS`]
+    /// cells away. Lets agents pressed against a wall still find a path.
+    fn nearest_free_cell(&self, point: Vec2) -> Option<usize> {
+        let start = self.cell(point)?;
+        let mut seen = vec![false; self.blocked.len()];
+        let mut queue = VecDeque::from([(start, 0)]);
+        seen[start] = true;
+
+        while let Some((cell, depth)) = queue.pop_front() {
+            if !self.blocked[cell] {
+                return Some(cell);
+            }
+            if depth == NAV_SNAP_RADIUS {
+                continue;
+            }
+            for (next, _) in self.neighbours(cell, true) {
+                if !seen[next] {
+                    seen[next] = true;
+                    queue.push_back((next, depth + 1));
+                }
+            }
+        }
+        None
+    }
+
+    /// 8-connected neighbours with their step cost. Diagonal steps may not
+    /// cut a blocked corner.
+    fn neighbours(&self, cell: usize, include_blocked: bool) -> impl Iterator<Item = (usize, f32)> {
+        let (x, y) = ((cell % self.width) as isize, (cell / self.width) as isize);
+        let free = move |dx: isize, dy: isize| include_blocked || self.is_free(x + dx, y + dy);
+        let in_bounds = move |dx: isize, dy: isize| {
+            let (nx, ny) = (x + dx, y + dy);
+            nx >= 0 && ny >= 0 && (nx as usize) < self.width && (ny as usize) < self.height
+        };
+
+        [
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+            (-1, -1),
+            (-1, 1),
+            (1, -1),
+            (1, 1),
+        ]
+        .into_iter()
+        .filter(move |&(dx, dy)| {
+            in_bounds(dx, dy)
+                && free(dx, dy)
+                && (dx == 0 || dy == 0 || (free(dx, 0) && free(0, dy)))
+        })
+        .map(move |(dx, dy)| {
+            let next = (y + dy) as usize * self.width + (x + dx) as usize;
+            let cost = if dx != 0 && dy != 0 {
+                std::f32::consts::SQRT_2
+            } else {
+                1.0
+            };
+            (next, cost * self.cell_size)
+        })
+    }
+
+    /// Whether every cell under the line from `a` to `b` is free.
+    fn line_is_free(&self, a: Vec2, b: Vec2) -> bool {
+        let steps = ((b - a).length() / (self.cell_size * 0.25)).ceil().max(1.0) as usize;
+        (0..=steps).all(|i| {
+            let p = a.lerp(b, i as f32 / steps as f32);
+            self.cell(p).is_some_and(|cell| !self.blocked[cell])
+        })
+    }
+
+    /// A* from `start` to `end`. Returns the waypoints including both
+    /// endpoints, with intermediate cells dropped wherever there is a
+    /// straight line past them.
+    pub fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        let from = self.nearest_free_cell(start)?;
+        let to = self.nearest_free_cell(end)?;
+        let goal = self.center(to);
+
+        let mut cost = vec![f32::INFINITY; self.blocked.len()];
+        let mut came_from = vec![usize::MAX; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        cost[from] = 0.0;
+        open.push(OpenCell {
+            estimate: self.center(from).distance(goal),
+            cell: from,
+        });
+
+        while let Some(OpenCell { cell, estimate }) = open.pop() {
+            if cell == to {
+                break;
+            }
+            if estimate - self.center(cell).distance(goal) > cost[cell] + 1e-3 {
+                // stale entry
+                continue;
+            }
+            for (next, step) in self.neighbours(cell, false) {
+                let next_cost = cost[cell] + step;
+                if next_cost < cost[next] {
+                    cost[next] = next_cost;
+                    came_from[next] = cell;
+                    open.push(OpenCell {
+                        estimate: next_cost + self.center(next).distance(goal),
+                        cell: next,
+                    });
+                }
+            }
+        }
+
+        if !cost[to].is_finite() {
+            return None;
+        }
+
+        let mut cells = vec![to];
+        while let Some(&cell) = cells.last() {
+            if cell == from {
+                break;
+            }
+            cells.push(came_from[cell]);
+        }
+        cells.reverse();
+
+        let mut points = Vec::with_capacity(cells.len() + 2);
+        points.push(start);
+        points.extend(cells.into_iter().map(|cell| self.center(cell)));
+        points.push(end);
+        Some(self.smooth(points))
+    }
+
+    fn smooth(&self, points: Vec<Vec2>) -> Vec<Vec2> {
+        let mut smoothed = vec![points[0]];
+        let mut anchor = 0;
+        while anchor < points.len() - 1 {
+            // furthest point visible from the anchor, falling back to the next one
+            let next = (anchor + 2..points.len())
+                .rev()
+                .find(|&i| self.line_is_free(points[anchor], points[i]))
+                .unwrap_or(anchor + 1);
+            smoothed.push(points[next]);
+            anchor = next;
+        }
+        smoothed
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OpenCell {
+    estimate: f32,
+    cell: usize,
+}
+
+impl Eq for OpenCell {}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // min-heap on the estimate, ties broken by cell for determinism
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| other.cell.cmp(&self.cell))
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Per-team navigation grids, built on first use and rebuilt after a
+/// keep-away zone at rest moves. Zones that move every frame, such as carried
+/// flags, are left out.
+#[derive(Debug, Clone, Resource)]
+pub struct NavGrids {
+    pub bounds: Rect,
+    /// Keep-away zones left out of the grids, sorted.
+    moving: Vec<Entity>,
+    red: Option<NavGrid>,
+    blue: Option<NavGrid>,
+}
+
+impl NavGrids {
+    pub fn new(bounds: Rect) -> Self {
+        Self {
+            bounds,
+            moving: Vec::new(),
+            red: None,
+            blue: None,
+        }
+    }
+
+    /// Leaves the keep-away zones of `moving` out of the grids, rebuilding
+    /// them if that changes which ones are.
+    pub fn set_moving(&mut self, mut moving: Vec<Entity>) {
+        moving.sort();
+        if moving != self.moving {
+            self.moving = moving;
+            self.invalidate();
+        }
+    }
+
+    pub fn invalidate(&mut self) {
+        self.red = None;
+        self.blue = None;
+    }
+
+    pub fn grid(&mut self, spatial: &SpatialQuery, team: TeamId) -> &NavGrid {
+        let bounds = self.bounds;
+        let slot = match team {
+            TeamId::Red => &mut self.red,
+            TeamId::Blue => &mut self.blue,
+        };
+        slot.get_or_insert_with(|| {
+            NavGrid::build(spatial, bounds, NAV_CELL_SIZE, team, &self.moving)
+        })
+    }
+
+    /// Waypoints from `start` to `end` for an agent of `team`, or `None` if
+    /// `end` cannot be reached.
+    pub fn shortest_path(
+        &mut self,
+        spatial: &SpatialQuery,
+        start: Vec2,
+        end: Vec2,
+        team: TeamId,
+    ) -> Option<Vec<Vec2>> {
+        self.grid(spatial, team).find_path(start, end)
+    }
+}
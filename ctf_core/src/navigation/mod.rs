@@ -0,0 +1,19 @@
+mod components;
+mod systems;
+
+use bevy::prelude::*;
+
+pub use components::*;
+
+pub const NAV_CELL_SIZE: f32 = 1.0;
+/// How many cells away from a blocked start or end point to look for a free
+/// cell.
+pub const NAV_SNAP_RADIUS: usize = 3;
+
+pub struct NavigationPlugin;
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, systems::init_nav_grids);
+        app.add_systems(PostUpdate, systems::invalidate_nav_grids);
+    }
+}
@@ -0,0 +1,43 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::core::CTFConfig;
+use crate::flag::{CapturePointId, Flag, FlagStatus};
+
+use super::NAV_CELL_SIZE;
+use super::components::NavGrids;
+
+pub fn init_nav_grids(mut commands: Commands, config: Res<CTFConfig>) {
+    commands.insert_resource(NavGrids::new(config.map.bounds().inflate(NAV_CELL_SIZE)));
+}
+
+type MovedCapturePoint = (
+    With<Collider>,
+    With<CapturePointId>,
+    Changed<GlobalTransform>,
+);
+
+/// Carried flags take their keep-away zone along every frame, so they are
+/// left out of the grids. The grids only go stale when a flag is picked up,
+/// dropped or moved while at rest, or a capture point moves.
+pub fn invalidate_nav_grids(
+    mut grids: ResMut<NavGrids>,
+    flags: Query<(Entity, &Flag, Ref<GlobalTransform>), With<Collider>>,
+    capture_points: Query<(), MovedCapturePoint>,
+) {
+    let carried = |flag: &Flag| flag.status == FlagStatus::PickedUp;
+    grids.set_moving(
+        flags
+            .iter()
+            .filter(|(_, flag, _)| carried(flag))
+            .map(|(entity, _, _)| entity)
+            .collect(),
+    );
+
+    let moved = flags
+        .iter()
+        .any(|(_, flag, transform)| !carried(flag) && transform.is_changed());
+    if moved || !capture_points.is_empty() {
+        grids.invalidate();
+    }
+}
@@ -0,0 +1,143 @@
+//! Policies written in Rust, run inside the simulator instead of in a
+//! separate process.
+
+use bevy::math::Vec2;
+
+use crate::agent::Action;
+use crate::flag::FlagStatus;
+use crate::match_state::MatchOutcome;
+use crate::team::TeamId;
+
+/// What a team's policy sees of the match when it is asked to act.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Observation {
+    /// Increases by one with every state sent to the policies.
+    pub tick: u64,
+    pub red_score: u32,
+    pub blue_score: u32,
+    /// Sorted by ID, like every list below.
+    pub red_team: Vec<AgentObservation>,
+    pub blue_team: Vec<AgentObservation>,
+    pub red_flags: Vec<FlagObservation>,
+    pub blue_flags: Vec<FlagObservation>,
+    pub red_capture_points: Vec<CapturePointObservation>,
+    pub blue_capture_points: Vec<CapturePointObservation>,
+    /// Seconds of game time since the match started.
+    pub elapsed: f32,
+    pub time_remaining: Option<f32>,
+    pub outcome: Option<MatchOutcome>,
+}
+
+impl Observation {
+    pub fn score(&self, team: TeamId) -> u32 {
+        match team {
+            TeamId::Red => self.red_score,
+            TeamId::Blue => self.blue_score,
+        }
+    }
+
+    pub fn agents(&self, team: TeamId) -> &[AgentObservation] {
+        match team {
+            TeamId::Red => &self.red_team,
+            TeamId::Blue => &self.blue_team,
+        }
+    }
+
+    pub fn flags(&self, team: TeamId) -> &[FlagObservation] {
+        match team {
+            TeamId::Red => &self.red_flags,
+            TeamId::Blue => &self.blue_flags,
+        }
+    }
+
+    pub fn capture_points(&self, team: TeamId) -> &[CapturePointObservation] {
+        match team {
+            TeamId::Red => &self.red_capture_points,
+            TeamId::Blue => &self.blue_capture_points,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentObservation {
+    pub id: u32,
+    pub team: TeamId,
+    pub position: Vec2,
+    pub max_speed: f32,
+    /// ID of the flag the agent is carrying, if any.
+    pub carried_flag: Option<u32>,
+    pub in_home_territory: bool,
+    /// Seconds since an opponent hidden by fog of war was last seen. `None`
+    /// while it is in view.
+    pub last_seen_age: Option<f32>,
+    /// Teammates' messages delivered to this agent, oldest first.
+    pub messages: Vec<TeamMessage>,
+}
+
+impl AgentObservation {
+    pub fn has_flag(&self) -> bool {
+        self.carried_flag.is_some()
+    }
+}
+
+/// A message from a teammate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeamMessage {
+    pub sender: u32,
+    pub content: String,
+    /// Match time it was sent at, in seconds.
+    pub sent_at: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlagObservation {
+    pub id: u32,
+    pub team: TeamId,
+    pub position: Vec2,
+    pub status: FlagStatus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturePointObservation {
+    pub id: u32,
+    pub team: TeamId,
+    pub position: Vec2,
+    pub has_flag: bool,
+}
+
+/// A policy that controls a team from inside the simulator.
+pub trait Policy: Send + Sync + 'static {
+    /// Called once with the first observation, before any `get_actions`.
+    #[allow(unused_variables)]
+    fn startup(&mut self, initial: &Observation) {}
+
+    /// The actions for the team's agents in `observation`.
+    fn get_actions(&mut self, observation: &Observation) -> Vec<Action>;
+
+    /// Like `get_actions`, with `nav` for paths around walls. Policies that
+    /// navigate override this.
+    #[allow(unused_variables)]
+    fn get_actions_with_nav(
+        &mut self,
+        observation: &Observation,
+        nav: &mut dyn Navigator,
+    ) -> Vec<Action> {
+        self.get_actions(observation)
+    }
+}
+
+/// Finds paths around walls for policies.
+pub trait Navigator {
+    /// Waypoints from `start` to `end` for an agent of `team`, or `None` if
+    /// there is no path or it could not be found.
+    fn shortest_path(&mut self, start: Vec2, end: Vec2, team: TeamId) -> Option<Vec<Vec2>>;
+}
+
+/// Finds no paths, so navigating policies head straight for their targets.
+pub struct NoNavigation;
+
+impl Navigator for NoNavigation {
+    fn shortest_path(&mut self, _start: Vec2, _end: Vec2, _team: TeamId) -> Option<Vec<Vec2>> {
+        None
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{AGENT_COOLDOWN_TIME, AGENT_DEFAULT_SPEED, AGENT_FLAG_SPEED, AGENT_TAG_RADIUS};
+use crate::flag::{
+    FLAG_COOLDOWN_TIME, FLAG_INTERACTION_RADIUS, FLAG_SPAWN_RADIUS, KEEP_AWAY_RADIUS,
+};
+use crate::territory::{TAG_PENALTY_TIME, TagPenalty, TaggingMode};
+
+/// Tunable gameplay constants. Defaults match the compile-time constants in
+/// the `agent` and `flag` modules.
+#[derive(Debug, Clone, PartialEq, Resource, Reflect, Serialize, Deserialize)]
+#[reflect(Resource, Default)]
+#[serde(default)]
+pub struct GameRules {
+    pub agent_default_speed: f32,
+    pub agent_flag_speed: f32,
+    pub agent_cooldown_time: f32,
+    pub agent_tag_radius: f32,
+    pub flag_cooldown_time: f32,
+    pub flag_interaction_radius: f32,
+    pub flag_spawn_radius: f32,
+    pub keep_away_radius: f32,
+    /// Seconds of game time after which the match ends.
+    pub time_limit: Option<f32>,
+    /// Captures needed to win the match.
+    pub score_to_win: Option<u32>,
+    pub tagging: TaggingMode,
+    pub tag_penalty: TagPenalty,
+    /// Seconds a tagged agent stays frozen or jailed.
+    pub tag_penalty_time: f32,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            agent_default_speed: AGENT_DEFAULT_SPEED,
+            agent_flag_speed: AGENT_FLAG_SPEED,
+            agent_cooldown_time: AGENT_COOLDOWN_TIME,
+            agent_tag_radius: AGENT_TAG_RADIUS,
+            flag_cooldown_time: FLAG_COOLDOWN_TIME,
+            flag_interaction_radius: FLAG_INTERACTION_RADIUS,
+            flag_spawn_radius: FLAG_SPAWN_RADIUS,
+            keep_away_radius: KEEP_AWAY_RADIUS,
+            time_limit: None,
+            score_to_win: None,
+            tagging: TaggingMode::default(),
+            tag_penalty: TagPenalty::default(),
+            tag_penalty_time: TAG_PENALTY_TIME,
+        }
+    }
+}
+
+/// Why a set of rules can't be played.
+#[derive(Debug)]
+pub struct RulesError(pub String);
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid rules: {}", self.0)
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+impl GameRules {
+    /// Checks that speeds and radii are positive, and that times are not
+    /// negative.
+    pub fn validate(&self) -> Result<(), RulesError> {
+        for (name, value) in [
+            ("agent_default_speed", self.agent_default_speed),
+            ("agent_flag_speed", self.agent_flag_speed),
+            ("agent_tag_radius", self.agent_tag_radius),
+            ("flag_interaction_radius", self.flag_interaction_radius),
+            ("keep_away_radius", self.keep_away_radius),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(RulesError(format!("{name} must be positive, got {value}")));
+            }
+        }
+        for (name, value) in [
+            ("agent_cooldown_time", self.agent_cooldown_time),
+            ("flag_cooldown_time", self.flag_cooldown_time),
+            ("flag_spawn_radius", self.flag_spawn_radius),
+            ("tag_penalty_time", self.tag_penalty_time),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(RulesError(format!(
+                    "{name} must not be negative, got {value}"
+                )));
+            }
+        }
+        if let Some(limit) = self.time_limit.filter(|t| !t.is_finite() || *t <= 0.0) {
+            return Err(RulesError(format!(
+                "time_limit must be positive, got {limit}"
+            )));
+        }
+        if self.score_to_win == Some(0) {
+            return Err(RulesError("score_to_win must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,31 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub const BLUE_ID_OFFSET: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
 pub enum TeamId {
     Red,
     Blue,
 }
 
+impl TeamId {
+    /// A stable public ID for the `slot`-th entity of this team: red IDs
+    /// start at 0 and blue IDs at [`BLUE_ID_OFFSET`].
+    pub fn slot_id(self, slot: usize) -> u32 {
+        match self {
+            TeamId::Red => slot as u32,
+            TeamId::Blue => BLUE_ID_OFFSET + slot as u32,
+        }
+    }
+
+    pub fn other(self) -> TeamId {
+        match self {
+            TeamId::Red => TeamId::Blue,
+            TeamId::Blue => TeamId::Red,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Serialize, Deserialize)]
 pub struct Team(pub TeamId);
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::CTFConfig;
+use crate::team::TeamId;
+
+/// How a map is divided into home territories.
+#[derive(Debug, Clone, PartialEq, Default, Reflect, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Territory {
+    /// Split halfway between the two teams' flags.
+    #[default]
+    Auto,
+    /// Split along the line through `start` and `end`. Each team owns the
+    /// side its flags are on.
+    Split { start: (f32, f32), end: (f32, f32) },
+    /// Explicit areas per team. Points in neither are neutral.
+    Polygons {
+        red: Vec<Vec<(f32, f32)>>,
+        blue: Vec<Vec<(f32, f32)>>,
+    },
+}
+
+/// Who can be tagged, and where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
+pub enum TaggingMode {
+    /// Flag carriers are tagged by any opponent, anywhere on the map.
+    #[default]
+    Legacy,
+    /// Any intruder is tagged by a defender on the defender's home side.
+    Territory,
+}
+
+/// What happens to an agent tagged in [`TaggingMode::Territory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect, Serialize, Deserialize)]
+pub enum TagPenalty {
+    /// Stop in place for the penalty time.
+    #[default]
+    Freeze,
+    /// Move to the team's jail and stay there for the penalty time.
+    Jail,
+    /// Move back to the agent's spawn position.
+    Respawn,
+}
+
+/// Where an agent was spawned, for [`TagPenalty::Respawn`].
+#[derive(Debug, Clone, Copy, Component)]
+pub struct SpawnPosition(pub Vec2);
+
+/// The home territories of a map, resolved against the actual flag positions.
+#[derive(Debug, Clone, Resource)]
+pub enum HomeTerritories {
+    /// Red owns the side `red_normal` points to.
+    HalfPlane { origin: Vec2, red_normal: Vec2 },
+    Polygons {
+        red: Vec<Vec<Vec2>>,
+        blue: Vec<Vec<Vec2>>,
+    },
+}
+
+impl HomeTerritories {
+    pub fn resolve(territory: &Territory, config: &CTFConfig) -> Self {
+        let red = team_anchor(config, TeamId::Red);
+        let blue = team_anchor(config, TeamId::Blue);
+
+        match territory {
+            Territory::Auto => match (red, blue) {
+                (Some(red), Some(blue)) if red.distance_squared(blue) > f32::EPSILON => {
+                    HomeTerritories::HalfPlane {
+                        origin: (red + blue) * 0.5,
+                        red_normal: (red - blue).normalize(),
+                    }
+                }
+                // nothing to tell the sides apart by; fall back to red on the left
+                _ => HomeTerritories::HalfPlane {
+                    origin: Vec2::ZERO,
+                    red_normal: Vec2::NEG_X,
+                },
+            },
+            Territory::Split { start, end } => {
+                let origin = Vec2::from(*start);
+                let normal = (Vec2::from(*end) - origin).perp().normalize_or(Vec2::NEG_X);
+                let red_normal = match (red, blue) {
+                    (Some(red), _) if normal.dot(red - origin) < 0.0 => -normal,
+                    (None, Some(blue)) if normal.dot(blue - origin) > 0.0 => -normal,
+                    _ => normal,
+                };
+                HomeTerritories::HalfPlane { origin, red_normal }
+            }
+            Territory::Polygons { red, blue } => {
+                let convert = |polygons: &Vec<Vec<(f32, f32)>>| {
+                    polygons
+                        .iter()
+                        .map(|p| p.iter().copied().map(Vec2::from).collect())
+                        .collect()
+                };
+                HomeTerritories::Polygons {
+                    red: convert(red),
+                    blue: convert(blue),
+                }
+            }
+        }
+    }
+
+    /// The team whose home territory contains `point`, if any.
+    pub fn owner(&self, point: Vec2) -> Option<TeamId> {
+        match self {
+            HomeTerritories::HalfPlane { origin, red_normal } => {
+                if red_normal.dot(point - *origin) >= 0.0 {
+                    Some(TeamId::Red)
+                } else {
+                    Some(TeamId::Blue)
+                }
+            }
+            HomeTerritories::Polygons { red, blue } => {
+                if red.iter().any(|p| polygon_contains(p, point)) {
+                    Some(TeamId::Red)
+                } else if blue.iter().any(|p| polygon_contains(p, point)) {
+                    Some(TeamId::Blue)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// The centroid of a team's flags, falling back to its capture points and
+/// then its agents.
+fn team_anchor(config: &CTFConfig, team: TeamId) -> Option<Vec2> {
+    let candidates = match team {
+        TeamId::Red => [
+            &config.red_team_flag_positions,
+            &config.red_team_capture_point_positions,
+            &config.red_team_agent_positions,
+        ],
+        TeamId::Blue => [
+            &config.blue_team_flag_positions,
+            &config.blue_team_capture_point_positions,
+            &config.blue_team_agent_positions,
+        ],
+    };
+    let positions = candidates.into_iter().find(|p| !p.is_empty())?;
+    let sum = positions
+        .iter()
+        .fold(Vec2::ZERO, |acc, &p| acc + Vec2::from(p));
+    Some(sum / positions.len() as f32)
+}
+
+/// Even-odd rule point in polygon test.
+fn polygon_contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+#[derive(Event, Clone, Copy)]
+pub struct AgentTagEvent {
+    /// The intruder that was tagged.
+    pub agent: Entity,
+    /// The defender that tagged it.
+    pub by: Entity,
+}
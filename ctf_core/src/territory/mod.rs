@@ -0,0 +1,45 @@
+mod components;
+mod events;
+mod systems;
+
+use bevy::prelude::*;
+
+pub use components::*;
+pub use events::*;
+
+use crate::core::CTFConfig;
+use crate::interaction_range::PickupSet;
+use crate::rules::GameRules;
+
+pub const TAG_PENALTY_TIME: f32 = 5.0;
+
+pub struct TerritoryPlugin;
+impl Plugin for TerritoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AgentTagEvent>();
+
+        app.add_systems(Startup, systems::init_home_territories);
+        app.add_systems(
+            Update,
+            (
+                systems::record_spawn_positions,
+                systems::update_home_territory,
+            )
+                .before(PickupSet::Detect)
+                .run_if(|c: Res<CTFConfig>| !c.replay),
+        );
+        app.add_systems(
+            Update,
+            systems::detect_tags
+                .in_set(PickupSet::Detect)
+                .run_if(|r: Res<GameRules>| r.tagging == TaggingMode::Territory),
+        );
+        app.add_systems(
+            Update,
+            systems::handle_tags
+                // after the flag drop, so the penalty replaces its cooldown
+                .after(PickupSet::Apply)
+                .run_if(|c: Res<CTFConfig>| !c.replay),
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::agent::Agent;
+use crate::core::CTFConfig;
+use crate::interaction_range::{FlagDropEvent, RecentlyDropped};
+use crate::rules::GameRules;
+use crate::team::{Team, TeamId};
+
+use super::components::{HomeTerritories, SpawnPosition, TagPenalty};
+use super::events::AgentTagEvent;
+
+pub fn init_home_territories(mut commands: Commands, config: Res<CTFConfig>) {
+    commands.insert_resource(HomeTerritories::resolve(&config.map.territory, &config));
+}
+
+pub fn record_spawn_positions(
+    mut commands: Commands,
+    agents: Query<(Entity, &Transform), Added<Agent>>,
+) {
+    for (entity, transform) in &agents {
+        commands
+            .entity(entity)
+            .insert(SpawnPosition(transform.translation.xz()));
+    }
+}
+
+pub fn update_home_territory(
+    territories: Res<HomeTerritories>,
+    mut agents: Query<(&Transform, &Team, &mut Agent)>,
+) {
+    for (transform, Team(team), mut agent) in &mut agents {
+        let home = territories.owner(transform.translation.xz()) == Some(*team);
+        if agent.in_home_territory != home {
+            agent.in_home_territory = home;
+        }
+    }
+}
+
+/// Tags every intruder in the other team's territory within tag range of a
+/// defender standing on its own side. Carriers drop their flag as well.
+#[allow(clippy::type_complexity)]
+pub fn detect_tags(
+    mut tags: EventWriter<AgentTagEvent>,
+    mut drops: EventWriter<FlagDropEvent>,
+    agents: Query<(Entity, &Transform, &Team, &Agent), Without<RecentlyDropped>>,
+    territories: Res<HomeTerritories>,
+    rules: Res<GameRules>,
+) {
+    let radius_sq = rules.agent_tag_radius * rules.agent_tag_radius;
+
+    for (intruder, intruder_transform, Team(intruder_team), intruder_agent) in &agents {
+        if intruder_agent.in_home_territory {
+            continue;
+        }
+        let intruder_pos = intruder_transform.translation.xz();
+        // neutral ground is safe
+        let Some(owner) = territories.owner(intruder_pos) else {
+            continue;
+        };
+
+        let defender = agents.iter().find(|(_, transform, Team(team), agent)| {
+            team != intruder_team
+                && *team == owner
+                && agent.in_home_territory
+                && transform.translation.xz().distance_squared(intruder_pos) <= radius_sq
+        });
+        let Some((defender, ..)) = defender else {
+            continue;
+        };
+
+        tags.write(AgentTagEvent {
+            agent: intruder,
+            by: defender,
+        });
+        if let Some(flag) = intruder_agent.flag {
+            drops.write(FlagDropEvent {
+                agent: intruder,
+                flag,
+            });
+        }
+    }
+}
+
+pub fn handle_tags(
+    mut commands: Commands,
+    mut reader: EventReader<AgentTagEvent>,
+    mut agents: Query<(
+        &Team,
+        &mut Transform,
+        &mut LinearVelocity,
+        Option<&SpawnPosition>,
+    )>,
+    config: Res<CTFConfig>,
+    rules: Res<GameRules>,
+) {
+    for AgentTagEvent { agent, .. } in reader.read().copied() {
+        let Ok((Team(team), mut transform, mut velocity, spawn)) = agents.get_mut(agent) else {
+            continue;
+        };
+        velocity.0 = Vec3::ZERO;
+
+        let spawn = spawn.map(|s| s.0);
+        let (destination, frozen) = match rules.tag_penalty {
+            TagPenalty::Freeze => (None, true),
+            TagPenalty::Jail => {
+                let jail = match team {
+                    TeamId::Red => config.map.red_team_jail_position,
+                    TeamId::Blue => config.map.blue_team_jail_position,
+                };
+                (jail.map(Vec2::from).or(spawn), true)
+            }
+            TagPenalty::Respawn => (spawn, false),
+        };
+
+        if let Some(destination) = destination {
+            transform.translation.x = destination.x;
+            transform.translation.z = destination.y;
+        }
+        if frozen {
+            // movement ignores agents that are still cooling down
+            commands
+                .entity(agent)
+                .insert(RecentlyDropped(Timer::from_seconds(
+                    rules.tag_penalty_time,
+                    TimerMode::Once,
+                )));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-from ._core import run, run_headless, segment_is_free, Action, AgentState, FlagState, FlagStatus, GameState, Team, Config, DefenseBot
+from ._core import nearest_obstacle_distance, point_is_free, raycast, read_message, replay, run, run_headless, run_tournament, segment_is_free, segments_are_free, shortest_path, PROTOCOL_VERSION, Action, AgentState, DefenseRole, DefenseTask, FlagState, FlagStatus, FogOfWar, GameRules, GameState, Hello, LaunchSpec, MatchEndReason, MatchOutcome, Message, OverBudget, PolicyLimits, PolicyStats, TagPenalty, TaggingMode, Team, UnseenOpponents, WireFormat, AttackerBot, Comms, Config, DefenseBot, Env, InterceptorBot, MixedBot, RandomBot
 from typing import Optional, Protocol, runtime_checkable
 import sys
 import matplotlib.pyplot as plt
@@ -17,53 +17,22 @@ class AgentProtocol(Protocol):
 
     def get_action(self, game_state: GameState, agent_state: AgentState) -> Action: ...
 
-def point_is_free(point: Position, side: Team, timeout_ms: Optional[int] = None) -> bool:
-    return segment_is_free(point, point, side, timeout_ms=timeout_ms)
+def plot(nodes: list, goal_positions: Optional[list[Position]] = None, walls: Optional[list[list[Position]]] = None):
+    if walls is None:
+        walls = Config().walls()
 
-def plot(nodes: list, goal_positions: Optional[list[Position]] = None):
     fig, ax = plt.subplots()
     ax.set_aspect('equal', 'box')
-    ax.set_xlim(-60, 60)
-    ax.set_ylim(-60, 60)
+    xs = [x for wall in walls for x, _ in wall]
+    ys = [y for wall in walls for _, y in wall]
+    if xs:
+        ax.set_xlim(min(xs) - 10, max(xs) + 10)
+        ax.set_ylim(min(ys) - 10, max(ys) + 10)
     ax.grid(True, which='both', linestyle='--', linewidth=0.5)
     ax.invert_yaxis()
 
-    outer = [
-        ((-50.0, 50.0), (50.0, 50.0)),
-        ((50.0, 50.0), (50.0, -50.0)),
-        ((50.0, -50.0), (-50.0, -50.0)),
-        ((-50.0, -50.0), (-50.0, 50.0)),
-    ];
-
-    side_bars = [
-        ((-45.0, 45.0), (-45.0, 5.0)),
-        ((-45.0, -5.0), (-45.0, -45.0)),
-        ((45.0, 45.0), (45.0, 5.0)),
-        ((45.0, -5.0), (45.0, -45.0)),
-    ];
-
-    middle = [
-        ((-10.0, 5.0), (10.0, 5.0)),
-        ((-10.0, -5.0), (10.0, -5.0)),
-    ];
-
-    diamond_left_edges = [
-        ((-5.0, 30.0), (-35.0, 0.0)),
-        ((-35.0, 0.0), (-5.0, -30.0)),
-        ((-5.0, -30.0), (25.0, 0.0)),
-        ((25.0, 0.0), (5.0, 20.0)),
-    ];
-
-    diamond_right_edges = [
-        ((5.0, 30.0), (35.0, 0.0)),
-        ((35.0, 0.0), (5.0, -30.0)),
-        ((5.0, 30.0), (-25.0, 0.0)),
-        ((-25.0, 0.0), (-5.0, -20.0)),
-    ];
-
-    for wall in outer + side_bars + middle + diamond_left_edges + diamond_right_edges:
-        (x1, y1), (x2, y2) = wall
-        ax.plot([x1, x2], [y1, y2], color='black', linewidth=1.0)
+    for wall in walls:
+        ax.plot([x for x, _ in wall], [y for _, y in wall], color='black', linewidth=1.0)
 
     for node in nodes:
         if node.parent is not None:
@@ -78,23 +47,54 @@ def plot(nodes: list, goal_positions: Optional[list[Position]] = None):
 
     plt.show()
 
-def visualize_planner(planner, goal_positions: Optional[list[Position]] = None):
+def visualize_planner(planner, goal_positions: Optional[list[Position]] = None, config: Optional[Config] = None):
     nodes = planner.nodes
-    process = mp.Process(target=plot, args=(nodes, goal_positions))
+    walls = (config or Config()).walls()
+    process = mp.Process(target=plot, args=(nodes, goal_positions, walls))
     process.start()
 
 __all__ = [
     "Action",
     "AgentProtocol",
     "AgentState",
+    "AttackerBot",
     "DefenseBot",
+    "DefenseRole",
+    "DefenseTask",
+    "InterceptorBot",
+    "MixedBot",
+    "RandomBot",
+    "Comms",
     "Config",
+    "Env",
     "GameState",
     "FlagState",
     "FlagStatus",
+    "FogOfWar",
+    "GameRules",
+    "Hello",
+    "LaunchSpec",
+    "MatchEndReason",
+    "MatchOutcome",
+    "Message",
+    "nearest_obstacle_distance",
+    "OverBudget",
     "point_is_free",
+    "PolicyLimits",
+    "PolicyStats",
+    "PROTOCOL_VERSION",
+    "raycast",
+    "read_message",
+    "replay",
     "run",
     "run_headless",
+    "run_tournament",
     "segment_is_free",
-    "Team"
+    "segments_are_free",
+    "shortest_path",
+    "TagPenalty",
+    "TaggingMode",
+    "Team",
+    "UnseenOpponents",
+    "WireFormat"
 ]
\ No newline at end of file
@@ -5,8 +5,14 @@ import builtins
 import typing
 from enum import Enum
 
+PROTOCOL_VERSION: builtins.int
+
 class Action:
-    def __new__(cls, id:builtins.int, velocity:tuple[builtins.float, builtins.float]) -> Action: ...
+    def __new__(cls, id:builtins.int, velocity:tuple[builtins.float, builtins.float], message:typing.Optional[builtins.str]=None) -> Action:
+        r"""
+        Moves agent `id` at `velocity`, optionally broadcasting `message` to
+        its teammates when `Config.comms` is set.
+        """
     def to_json(self) -> builtins.str: ...
     def __repr__(self) -> builtins.str: ...
     def __str__(self) -> builtins.str: ...
@@ -23,7 +29,8 @@ class AgentState:
     @property
     def id(self) -> builtins.int:
         r"""
-        The unique identifier of the agent.
+        The stable identifier of the agent: its slot in the team, offset by
+        1000 for blue.
         """
     @property
     def team(self) -> Team:
@@ -45,6 +52,39 @@ class AgentState:
         r"""
         If this agent is currently carrying a flag.
         """
+    @property
+    def carried_flag(self) -> typing.Optional[builtins.int]:
+        r"""
+        The ID of the flag this agent is carrying, if any.
+        """
+    @property
+    def in_home_territory(self) -> builtins.bool:
+        r"""
+        If this agent is currently inside its own team's territory.
+        """
+    @property
+    def last_seen_age(self) -> typing.Optional[builtins.float]:
+        r"""
+        For an opponent out of sight under fog of war, seconds since it was
+        last seen; its other fields are from that moment. `None` while it is
+        in view.
+        """
+    @property
+    def messages(self) -> builtins.list[Message]:
+        r"""
+        Teammates' messages delivered to this agent with this state, oldest
+        first. Always empty for opponents.
+        """
+
+class AttackerBot:
+    r"""
+    Runs for the nearest loose enemy flag and carries it back to the nearest
+    free capture point of its own, finding paths around walls through the
+    physics server.
+    """
+    def __new__(cls, side:Team) -> AttackerBot: ...
+    def startup(self, initial_state:GameState) -> None: ...
+    def get_action(self, game_state:GameState, agent_state:AgentState) -> Action: ...
 
 class CapturePointState:
     r"""
@@ -55,7 +95,8 @@ class CapturePointState:
     @property
     def id(self) -> builtins.int:
         r"""
-        The unique identifier of the capture point.
+        The stable identifier of the capture point: its slot in the team,
+        offset by 1000 for blue.
         """
     @property
     def team(self) -> Team:
@@ -69,6 +110,50 @@ class CapturePointState:
         """
     def has_flag(self) -> builtins.bool: ...
 
+class Comms:
+    r"""
+    Lets agents broadcast short messages to their teammates through
+    `Action.message`.
+    """
+    @property
+    def max_message_bytes(self) -> builtins.int:
+        r"""
+        Longest message allowed, in bytes. Longer ones are dropped.
+        """
+    @max_message_bytes.setter
+    def max_message_bytes(self, value: builtins.int) -> None:
+        r"""
+        Longest message allowed, in bytes. Longer ones are dropped.
+        """
+    @property
+    def range(self) -> typing.Optional[builtins.float]:
+        r"""
+        Messages only reach teammates within this distance of the sender
+        when it was sent. Unlimited if unset.
+        """
+    @range.setter
+    def range(self, value: typing.Optional[builtins.float]) -> None:
+        r"""
+        Messages only reach teammates within this distance of the sender
+        when it was sent. Unlimited if unset.
+        """
+    @property
+    def delay(self) -> builtins.float:
+        r"""
+        Seconds of match time before a message is delivered. Messages are
+        delivered with the next state at the earliest, and dropped if none
+        carries them within 5 seconds after that.
+        """
+    @delay.setter
+    def delay(self, value: builtins.float) -> None:
+        r"""
+        Seconds of match time before a message is delivered. Messages are
+        delivered with the next state at the earliest, and dropped if none
+        carries them within 5 seconds after that.
+        """
+    def __new__(cls) -> Comms: ...
+    def __repr__(self) -> builtins.str: ...
+
 class Config:
     @property
     def red_team_agent_ids(self) -> builtins.list[builtins.str]: ...
@@ -114,15 +199,299 @@ class Config:
     def rate_hz(self) -> typing.Optional[builtins.float]: ...
     @rate_hz.setter
     def rate_hz(self, value: typing.Optional[builtins.float]) -> None: ...
+    @property
+    def map_path(self) -> typing.Optional[builtins.str]:
+        r"""
+        Path to a JSON map file. Ignored if `map` is set.
+        """
+    @map_path.setter
+    def map_path(self, value: typing.Optional[builtins.str]) -> None:
+        r"""
+        Path to a JSON map file. Ignored if `map` is set.
+        """
+    @property
+    def map(self) -> typing.Optional[builtins.str]:
+        r"""
+        An inline JSON map. Takes precedence over `map_path`.
+        """
+    @map.setter
+    def map(self, value: typing.Optional[builtins.str]) -> None:
+        r"""
+        An inline JSON map. Takes precedence over `map_path`.
+        """
+    @property
+    def rules(self) -> GameRules:
+        r"""
+        Gameplay constants such as speeds, radii and cooldowns.
+        """
+    @rules.setter
+    def rules(self, value: GameRules) -> None:
+        r"""
+        Gameplay constants such as speeds, radii and cooldowns.
+        """
+    @property
+    def seed(self) -> typing.Optional[builtins.int]:
+        r"""
+        Seed for all in-game randomness. Random if unset.
+        """
+    @seed.setter
+    def seed(self, value: typing.Optional[builtins.int]) -> None:
+        r"""
+        Seed for all in-game randomness. Random if unset.
+        """
+    @property
+    def fixed_timestep(self) -> builtins.bool:
+        r"""
+        Advance the simulation by exactly `1 / rate_hz` seconds per frame and
+        wait for both policies to answer every state, so that runs with the
+        same seed are reproducible.
+        """
+    @fixed_timestep.setter
+    def fixed_timestep(self, value: builtins.bool) -> None:
+        r"""
+        Advance the simulation by exactly `1 / rate_hz` seconds per frame and
+        wait for both policies to answer every state, so that runs with the
+        same seed are reproducible.
+        """
+    @property
+    def record_path(self) -> typing.Optional[builtins.str]:
+        r"""
+        Write a gzipped replay of the match to this path. Play it back with
+        `replay`.
+        """
+    @record_path.setter
+    def record_path(self, value: typing.Optional[builtins.str]) -> None:
+        r"""
+        Write a gzipped replay of the match to this path. Play it back with
+        `replay`.
+        """
+    @property
+    def fast_forward(self) -> builtins.bool:
+        r"""
+        In `run_headless`, run frames back to back instead of in real time.
+        Like `fixed_timestep`, each frame advances `1 / rate_hz` seconds and
+        waits for both policies.
+        """
+    @fast_forward.setter
+    def fast_forward(self, value: builtins.bool) -> None:
+        r"""
+        In `run_headless`, run frames back to back instead of in real time.
+        Like `fixed_timestep`, each frame advances `1 / rate_hz` seconds and
+        waits for both policies.
+        """
+    @property
+    def time_scale(self) -> typing.Optional[builtins.float]:
+        r"""
+        With `fast_forward`, cap the speed at this multiple of real time.
+        Unlimited if unset.
+        """
+    @time_scale.setter
+    def time_scale(self, value: typing.Optional[builtins.float]) -> None:
+        r"""
+        With `fast_forward`, cap the speed at this multiple of real time.
+        Unlimited if unset.
+        """
+    @property
+    def red_team_launch(self) -> typing.Optional[LaunchSpec]:
+        r"""
+        How to start the red policy. Defaults to
+        `python_exe run launch-team {config} --side {side}`.
+        """
+    @red_team_launch.setter
+    def red_team_launch(self, value: typing.Optional[LaunchSpec]) -> None:
+        r"""
+        How to start the red policy. Defaults to
+        `python_exe run launch-team {config} --side {side}`.
+        """
+    @property
+    def blue_team_launch(self) -> typing.Optional[LaunchSpec]:
+        r"""
+        Like `red_team_launch`, for the blue policy.
+        """
+    @blue_team_launch.setter
+    def blue_team_launch(self, value: typing.Optional[LaunchSpec]) -> None:
+        r"""
+        Like `red_team_launch`, for the blue policy.
+        """
+    @property
+    def red_team_policy(self) -> typing.Optional[builtins.str]:
+        r"""
+        A built-in policy to play red inside the simulator instead of
+        launching a process: `builtin:defense_bot`, `builtin:attacker_bot`,
+        `builtin:random_bot`, `builtin:mixed_bot` or `builtin:interceptor_bot`.
+        `human` lets a player drive red's agents in the window opened by `run`.
+        """
+    @red_team_policy.setter
+    def red_team_policy(self, value: typing.Optional[builtins.str]) -> None:
+        r"""
+        A built-in policy to play red inside the simulator instead of
+        launching a process: `builtin:defense_bot`, `builtin:attacker_bot`,
+        `builtin:random_bot`, `builtin:mixed_bot` or `builtin:interceptor_bot`.
+        `human` lets a player drive red's agents in the window opened by `run`.
+        """
+    @property
+    def blue_team_policy(self) -> typing.Optional[builtins.str]:
+        r"""
+        Like `red_team_policy`, for blue.
+        """
+    @blue_team_policy.setter
+    def blue_team_policy(self, value: typing.Optional[builtins.str]) -> None:
+        r"""
+        Like `red_team_policy`, for blue.
+        """
+    @property
+    def red_team_limits(self) -> PolicyLimits:
+        r"""
+        Resource limits and response budget for the red policy.
+        """
+    @red_team_limits.setter
+    def red_team_limits(self, value: PolicyLimits) -> None:
+        r"""
+        Resource limits and response budget for the red policy.
+        """
+    @property
+    def blue_team_limits(self) -> PolicyLimits:
+        r"""
+        Resource limits and response budget for the blue policy.
+        """
+    @blue_team_limits.setter
+    def blue_team_limits(self, value: PolicyLimits) -> None:
+        r"""
+        Resource limits and response budget for the blue policy.
+        """
+    @property
+    def wire_format(self) -> WireFormat:
+        r"""
+        How states and actions are encoded on the policy pipes. Policies may
+        decline a binary format and stay on JSON.
+        """
+    @wire_format.setter
+    def wire_format(self, value: WireFormat) -> None:
+        r"""
+        How states and actions are encoded on the policy pipes. Policies may
+        decline a binary format and stay on JSON.
+        """
+    @property
+    def fog_of_war(self) -> typing.Optional[FogOfWar]:
+        r"""
+        Limits what each policy observes to what its agents can see. Both
+        policies see everything if unset.
+        """
+    @fog_of_war.setter
+    def fog_of_war(self, value: typing.Optional[FogOfWar]) -> None:
+        r"""
+        Limits what each policy observes to what its agents can see. Both
+        policies see everything if unset.
+        """
+    @property
+    def comms(self) -> typing.Optional[Comms]:
+        r"""
+        Lets agents broadcast messages to their teammates. Messages in
+        actions are ignored if unset.
+        """
+    @comms.setter
+    def comms(self, value: typing.Optional[Comms]) -> None:
+        r"""
+        Lets agents broadcast messages to their teammates. Messages in
+        actions are ignored if unset.
+        """
+    @property
+    def per_agent_policies(self) -> builtins.bool:
+        r"""
+        Launch one policy process per agent instead of one per team. Each
+        process observes the game on its own and may only act for its agent.
+        An agent whose process is given up on after crashing stops.
+        """
+    @per_agent_policies.setter
+    def per_agent_policies(self, value: builtins.bool) -> None:
+        r"""
+        Launch one policy process per agent instead of one per team. Each
+        process observes the game on its own and may only act for its agent.
+        An agent whose process is given up on after crashing stops.
+        """
     def __new__(cls) -> Config: ...
     @staticmethod
     def from_json(json_str:builtins.str) -> Config: ...
+    def walls(self) -> builtins.list[builtins.list[tuple[builtins.float, builtins.float]]]:
+        r"""
+        The walls of the configured map, each a list of points joined by
+        wall segments.
+        """
     def __str__(self) -> builtins.str: ...
 
 class DefenseBot:
+    r"""
+    Defends as a team: one chaser and one interceptor per opponent carrying
+    a flag, with everyone else spread over the flags on the ground as guards,
+    or sent after the opponents when there are none.
+    """
     def __new__(cls, side:Team) -> DefenseBot: ...
     def startup(self, initial_state:GameState) -> None: ...
     def get_action(self, game_state:GameState, agent_state:AgentState) -> Action: ...
+    def get_actions(self, game_state:GameState) -> builtins.list[Action]:
+        r"""
+        The actions for the whole team, decided together.
+        """
+    def assignment(self, game_state:GameState) -> builtins.list[DefenseTask]:
+        r"""
+        The task of each agent of the team in `game_state`.
+        """
+
+class DefenseTask:
+    r"""
+    A defender's role and what it is aimed at.
+    """
+    @property
+    def agent(self) -> builtins.int:
+        r"""
+        ID of the defender.
+        """
+    @property
+    def role(self) -> DefenseRole: ...
+    @property
+    def target(self) -> builtins.int:
+        r"""
+        ID of the guarded flag, or of the opponent chased or intercepted.
+        """
+    def __repr__(self) -> builtins.str: ...
+
+class Env:
+    r"""
+    A synchronous, gym-style environment. The world only advances when
+    `step` is called, and both teams' actions are supplied by the caller
+    instead of policy subprocesses.
+    """
+    def __new__(cls, config:Config, frames_per_step:builtins.int=1, max_steps:typing.Optional[builtins.int]=None) -> Env:
+        r"""
+        Creates an environment. Each `step` advances the world by
+        `frames_per_step` frames of `1 / config.rate_hz` seconds, and the
+        episode is done after `max_steps` steps if given.
+        """
+    def reset(self, seed:typing.Optional[builtins.int]=None) -> GameState:
+        r"""
+        Rebuilds the world from the config and returns the initial state.
+        `seed` overrides `config.seed` for this episode.
+        """
+    def step(self, actions:typing.Sequence[Action]) -> tuple[GameState, tuple[builtins.float, builtins.float], builtins.bool, builtins.dict]:
+        r"""
+        Applies `actions` and advances the world by one step.
+        
+        Returns `(state, (red_reward, blue_reward), done, info)`, where each
+        reward is the number of flags that team captured during the step.
+        """
+    def state(self) -> GameState:
+        r"""
+        The current state, without advancing the world.
+        """
+    def segment_is_free(self, start:tuple[builtins.float, builtins.float], end:tuple[builtins.float, builtins.float], side:Team) -> builtins.bool:
+        r"""
+        Like `segment_is_free`, but queries this environment's world directly.
+        """
+
+    def shortest_path(self, start:tuple[builtins.float, builtins.float], end:tuple[builtins.float, builtins.float], side:Team) -> typing.Optional[builtins.list[tuple[builtins.float, builtins.float]]]:
+        r"""
+        Like `shortest_path`, but queries this environment's world directly.
+        """
 
 class FlagState:
     r"""
@@ -136,7 +505,8 @@ class FlagState:
     @property
     def id(self) -> builtins.int:
         r"""
-        The unique identifier of the flag.
+        The stable identifier of the flag: its slot in the team, offset by
+        1000 for blue.
         """
     @property
     def team(self) -> Team:
@@ -151,11 +521,187 @@ class FlagState:
     @property
     def status(self) -> FlagStatus: ...
 
+class FogOfWar:
+    r"""
+    Limits what each team's policy observes to what its agents can see.
+    Flags carried by opponents out of sight are reported where they were
+    last seen.
+    """
+    @property
+    def vision_radius(self) -> builtins.float:
+        r"""
+        How far each agent sees.
+        """
+    @vision_radius.setter
+    def vision_radius(self, value: builtins.float) -> None:
+        r"""
+        How far each agent sees.
+        """
+    @property
+    def line_of_sight(self) -> builtins.bool:
+        r"""
+        Whether walls block sight.
+        """
+    @line_of_sight.setter
+    def line_of_sight(self, value: builtins.bool) -> None:
+        r"""
+        Whether walls block sight.
+        """
+    @property
+    def unseen_opponents(self) -> UnseenOpponents: ...
+    @unseen_opponents.setter
+    def unseen_opponents(self, value: UnseenOpponents) -> None: ...
+    def __new__(cls) -> FogOfWar: ...
+    def __repr__(self) -> builtins.str: ...
+
+class GameRules:
+    r"""
+    Tunable gameplay constants. Defaults match the built-in game. Speeds and
+    tag and flag radii must be positive, and times must not be negative.
+    """
+    @property
+    def agent_default_speed(self) -> builtins.float:
+        r"""
+        Speed of an agent that is not carrying a flag.
+        """
+    @agent_default_speed.setter
+    def agent_default_speed(self, value: builtins.float) -> None:
+        r"""
+        Speed of an agent that is not carrying a flag.
+        """
+    @property
+    def agent_flag_speed(self) -> builtins.float:
+        r"""
+        Speed of an agent carrying a flag.
+        """
+    @agent_flag_speed.setter
+    def agent_flag_speed(self, value: builtins.float) -> None:
+        r"""
+        Speed of an agent carrying a flag.
+        """
+    @property
+    def agent_cooldown_time(self) -> builtins.float:
+        r"""
+        Seconds an agent is stunned after being tagged.
+        """
+    @agent_cooldown_time.setter
+    def agent_cooldown_time(self, value: builtins.float) -> None:
+        r"""
+        Seconds an agent is stunned after being tagged.
+        """
+    @property
+    def agent_tag_radius(self) -> builtins.float:
+        r"""
+        Distance at which an opponent tags a flag carrier.
+        """
+    @agent_tag_radius.setter
+    def agent_tag_radius(self, value: builtins.float) -> None:
+        r"""
+        Distance at which an opponent tags a flag carrier.
+        """
+    @property
+    def flag_cooldown_time(self) -> builtins.float:
+        r"""
+        Seconds before a dropped flag can be picked up again.
+        """
+    @flag_cooldown_time.setter
+    def flag_cooldown_time(self, value: builtins.float) -> None:
+        r"""
+        Seconds before a dropped flag can be picked up again.
+        """
+    @property
+    def flag_interaction_radius(self) -> builtins.float:
+        r"""
+        Distance at which an agent picks up a flag.
+        """
+    @flag_interaction_radius.setter
+    def flag_interaction_radius(self, value: builtins.float) -> None:
+        r"""
+        Distance at which an agent picks up a flag.
+        """
+    @property
+    def flag_spawn_radius(self) -> builtins.float:
+        r"""
+        Distance a dropped flag is thrown from its carrier.
+        """
+    @flag_spawn_radius.setter
+    def flag_spawn_radius(self, value: builtins.float) -> None:
+        r"""
+        Distance a dropped flag is thrown from its carrier.
+        """
+    @property
+    def keep_away_radius(self) -> builtins.float:
+        r"""
+        Radius around flags and capture points that blocks their own team.
+        """
+    @keep_away_radius.setter
+    def keep_away_radius(self, value: builtins.float) -> None:
+        r"""
+        Radius around flags and capture points that blocks their own team.
+        """
+    @property
+    def time_limit(self) -> typing.Optional[builtins.float]:
+        r"""
+        Seconds of game time after which the match ends. Unlimited if unset.
+        """
+    @time_limit.setter
+    def time_limit(self, value: typing.Optional[builtins.float]) -> None:
+        r"""
+        Seconds of game time after which the match ends. Unlimited if unset.
+        """
+    @property
+    def score_to_win(self) -> typing.Optional[builtins.int]:
+        r"""
+        Captures needed to win the match. Unlimited if unset.
+        """
+    @score_to_win.setter
+    def score_to_win(self, value: typing.Optional[builtins.int]) -> None:
+        r"""
+        Captures needed to win the match. Unlimited if unset.
+        """
+    @property
+    def tagging(self) -> TaggingMode:
+        r"""
+        Who can be tagged, and where.
+        """
+    @tagging.setter
+    def tagging(self, value: TaggingMode) -> None:
+        r"""
+        Who can be tagged, and where.
+        """
+    @property
+    def tag_penalty(self) -> TagPenalty:
+        r"""
+        What happens to an agent tagged in `TaggingMode.Territory`.
+        """
+    @tag_penalty.setter
+    def tag_penalty(self, value: TagPenalty) -> None:
+        r"""
+        What happens to an agent tagged in `TaggingMode.Territory`.
+        """
+    @property
+    def tag_penalty_time(self) -> builtins.float:
+        r"""
+        Seconds a tagged agent stays frozen or jailed.
+        """
+    @tag_penalty_time.setter
+    def tag_penalty_time(self, value: builtins.float) -> None:
+        r"""
+        Seconds a tagged agent stays frozen or jailed.
+        """
+    def __new__(cls) -> GameRules: ...
+    def __repr__(self) -> builtins.str: ...
+
 class GameState:
     r"""
     A snapshot of the current game state, including scores and agent states for both teams.
     """
     @property
+    def tick(self) -> builtins.int:
+        r"""
+        The ID of this state. Answers to it must carry the same tick.
+        """
+    @property
     def red_score(self) -> builtins.int:
         r"""
         The number of flags captured by the red team.
@@ -200,10 +746,40 @@ class GameState:
         r"""
         The list of capture points belonging to the blue team, sorted by their IDs.
         """
-    def get_team_score(self, team:Team) -> builtins.int:
+    @property
+    def elapsed(self) -> builtins.float:
         r"""
-        Gets the score for the specified team.
-        
+        Seconds of game time since the match started.
+        """
+    @property
+    def time_remaining(self) -> typing.Optional[builtins.float]:
+        r"""
+        Seconds left before the time limit, or `None` if there is no limit.
+        """
+    @property
+    def outcome(self) -> typing.Optional[MatchOutcome]:
+        r"""
+        How the match ended, or `None` while it is still running.
+        """
+    @property
+    def is_over(self) -> builtins.bool:
+        r"""
+        Whether the match has ended.
+        """
+    @property
+    def red_policy_stats(self) -> typing.Optional[PolicyStats]:
+        r"""
+        How well the red policy has kept up, or `None` outside of a policy match.
+        """
+    @property
+    def blue_policy_stats(self) -> typing.Optional[PolicyStats]:
+        r"""
+        How well the blue policy has kept up, or `None` outside of a policy match.
+        """
+    def get_team_score(self, team:Team) -> builtins.int:
+        r"""
+        Gets the score for the specified team.
+        
         Parameters
            `team`: The team whose score to retrieve (either `Team.RED` or `Team.BLUE`).
         """
@@ -228,9 +804,328 @@ class GameState:
         Parameters
          `team`: The team whose capture points to retrieve (either `Team.RED` or `Team.BLUE`).
         """
+    def reply(self, actions:typing.Sequence[Action]) -> builtins.str:
+        r"""
+        The line to answer this state with.
+        
+        Parameters
+          `actions`: The actions chosen for this state.
+        """
+    def encode_reply(self, actions:typing.Sequence[Action], wire_format:WireFormat=WireFormat.Json) -> builtins.bytes:
+        r"""
+        Like `reply`, but encoded in `wire_format` with framing included,
+        ready to write to `sys.stdout.buffer`.
+        """
     @staticmethod
     def from_json(json_str:builtins.str) -> GameState: ...
 
+class Hello:
+    r"""
+    The first message a policy receives.
+    """
+    @property
+    def protocol_version(self) -> builtins.int:
+        r"""
+        The protocol version the simulator speaks.
+        """
+    @property
+    def capabilities(self) -> builtins.list[builtins.str]:
+        r"""
+        Optional features the simulator offers.
+        """
+    @property
+    def side(self) -> Team:
+        r"""
+        The side the policy plays.
+        """
+    @property
+    def wire_format(self) -> WireFormat:
+        r"""
+        The wire format the simulator would like to switch to. Pass it to
+        `reply` to accept it.
+        """
+    @property
+    def agent(self) -> typing.Optional[builtins.int]:
+        r"""
+        The ID of the only agent the policy controls, or `None` if it
+        controls the whole team. Actions for other agents are rejected.
+        """
+    @staticmethod
+    def from_json(json_str:builtins.str) -> Hello:
+        r"""
+        Parses the first line sent by the simulator.
+        """
+    def reply(self, capabilities:typing.Sequence[builtins.str]=[], name:typing.Optional[builtins.str]=None, wire_format:WireFormat=WireFormat.Json) -> builtins.str:
+        r"""
+        The line to answer this hello with.
+        
+        Raises `ValueError` if the simulator speaks a different protocol
+        version than this package.
+        
+        Parameters
+          `wire_format`: The format for all later messages. Either JSON or
+          the one the simulator offered.
+        """
+    def __repr__(self) -> builtins.str: ...
+
+class InterceptorBot:
+    r"""
+    Cuts off opponents on their way in: a flag carrier on its way to its
+    capture points, or else the opponent closest to one of its own flags.
+    Waits by the nearest own flag when no opponent is in view.
+    """
+    def __new__(cls, side:Team) -> InterceptorBot: ...
+    def startup(self, initial_state:GameState) -> None: ...
+    def get_action(self, game_state:GameState, agent_state:AgentState) -> Action: ...
+
+class LaunchSpec:
+    r"""
+    How to start a team's policy process. Any executable that speaks the
+    JSON-lines protocol on stdin/stdout works.
+    
+    `args`, `env` values and `cwd` may contain these placeholders:
+      - `{config}`: the config as JSON.
+      - `{side}`: the side as JSON, as read by `Team.from_str`.
+      - `{side_name}`: `red` or `blue`.
+      - `{physics_addr}`: the physics RPC address, or an empty string.
+    """
+    @property
+    def program(self) -> builtins.str: ...
+    @program.setter
+    def program(self, value: builtins.str) -> None: ...
+    @property
+    def args(self) -> builtins.list[builtins.str]: ...
+    @args.setter
+    def args(self, value: builtins.list[builtins.str]) -> None: ...
+    @property
+    def env(self) -> builtins.dict[builtins.str, builtins.str]:
+        r"""
+        Extra environment variables, on top of the simulator's own.
+        """
+    @env.setter
+    def env(self, value: builtins.dict[builtins.str, builtins.str]) -> None:
+        r"""
+        Extra environment variables, on top of the simulator's own.
+        """
+    @property
+    def cwd(self) -> typing.Optional[builtins.str]:
+        r"""
+        Working directory. Defaults to the simulator's.
+        """
+    @cwd.setter
+    def cwd(self, value: typing.Optional[builtins.str]) -> None:
+        r"""
+        Working directory. Defaults to the simulator's.
+        """
+    def __new__(cls, program:builtins.str, args:typing.Sequence[builtins.str]=[], env:typing.Mapping[builtins.str, builtins.str]={}, cwd:typing.Optional[builtins.str]=None) -> LaunchSpec: ...
+    def __repr__(self) -> builtins.str: ...
+
+class MatchOutcome:
+    r"""
+    How a finished match ended.
+    """
+    @property
+    def winner(self) -> typing.Optional[Team]:
+        r"""
+        The winning team, or `None` for a draw.
+        """
+    @property
+    def reason(self) -> MatchEndReason:
+        r"""
+        Why the match ended.
+        """
+    def __repr__(self) -> builtins.str: ...
+
+class Message:
+    r"""
+    A message from a teammate.
+    """
+    @property
+    def sender(self) -> builtins.int:
+        r"""
+        ID of the agent that sent it.
+        """
+    @property
+    def content(self) -> builtins.str: ...
+    @property
+    def sent_at(self) -> builtins.float:
+        r"""
+        Match time it was sent at, in seconds.
+        """
+    def __repr__(self) -> builtins.str: ...
+
+class MixedBot:
+    r"""
+    Splits the team into roles every state: flag carriers and the half of
+    the team closest to the enemy flags attack like `AttackerBot`, the
+    teammate closest to an opponent carrying a flag intercepts it like
+    `InterceptorBot`, and the rest defend together like `DefenseBot`.
+    """
+    def __new__(cls, side:Team) -> MixedBot: ...
+    def startup(self, initial_state:GameState) -> None: ...
+    def get_action(self, game_state:GameState, agent_state:AgentState) -> Action: ...
+    def roles(self, game_state:GameState) -> builtins.dict[builtins.int, builtins.str]:
+        r"""
+        The role of each agent of the team in `game_state`, by agent ID:
+        `attack`, `defend` or `intercept`.
+        """
+
+class PolicyLimits:
+    r"""
+    Resource limits and time budget for one team's policy process.
+    """
+    @property
+    def memory_mb(self) -> typing.Optional[builtins.int]:
+        r"""
+        Address space cap for the process, in MiB. Unix only.
+        """
+    @memory_mb.setter
+    def memory_mb(self, value: typing.Optional[builtins.int]) -> None:
+        r"""
+        Address space cap for the process, in MiB. Unix only.
+        """
+    @property
+    def cpu_seconds(self) -> typing.Optional[builtins.int]:
+        r"""
+        CPU time cap for the process, in seconds. The process is killed once
+        it is used up. Unix only.
+        """
+    @cpu_seconds.setter
+    def cpu_seconds(self, value: typing.Optional[builtins.int]) -> None:
+        r"""
+        CPU time cap for the process, in seconds. The process is killed once
+        it is used up. Unix only.
+        """
+    @property
+    def tick_deadline_ms(self) -> typing.Optional[builtins.int]:
+        r"""
+        Milliseconds a policy has to answer a state. Later answers are
+        dropped and counted as late.
+        """
+    @tick_deadline_ms.setter
+    def tick_deadline_ms(self, value: typing.Optional[builtins.int]) -> None:
+        r"""
+        Milliseconds a policy has to answer a state. Later answers are
+        dropped and counted as late.
+        """
+    @property
+    def max_late(self) -> typing.Optional[builtins.int]:
+        r"""
+        Late answers allowed from any one policy process before the team is
+        over budget.
+        """
+    @max_late.setter
+    def max_late(self, value: typing.Optional[builtins.int]) -> None:
+        r"""
+        Late answers allowed from any one policy process before the team is
+        over budget.
+        """
+    @property
+    def max_missed(self) -> typing.Optional[builtins.int]:
+        r"""
+        Missed states allowed from any one policy process before the team is
+        over budget. A state is missed if the policy is still busy with an
+        earlier one, does not answer in time in lockstep mode, or leaves it
+        unanswered past the deadline or behind 64 newer states. States sent
+        before the policy answers the hello do not count.
+        """
+    @max_missed.setter
+    def max_missed(self, value: typing.Optional[builtins.int]) -> None:
+        r"""
+        Missed states allowed from any one policy process before the team is
+        over budget. A state is missed if the policy is still busy with an
+        earlier one, does not answer in time in lockstep mode, or leaves it
+        unanswered past the deadline or behind 64 newer states. States sent
+        before the policy answers the hello do not count.
+        """
+    @property
+    def on_over_budget(self) -> OverBudget:
+        r"""
+        What happens once the team is over budget.
+        """
+    @on_over_budget.setter
+    def on_over_budget(self, value: OverBudget) -> None:
+        r"""
+        What happens once the team is over budget.
+        """
+    @property
+    def max_restarts(self) -> builtins.int:
+        r"""
+        How many times a crashed policy process is restarted before it is
+        given up on. The team forfeits once none of its processes is left.
+        """
+    @max_restarts.setter
+    def max_restarts(self, value: builtins.int) -> None:
+        r"""
+        How many times a crashed policy process is restarted before it is
+        given up on. The team forfeits once none of its processes is left.
+        """
+    def __new__(cls) -> PolicyLimits: ...
+    def __repr__(self) -> builtins.str: ...
+
+class PolicyStats:
+    r"""
+    How well a team's policy has kept up so far. With per-agent policies,
+    the counts are summed over the processes.
+    """
+    @property
+    def answered(self) -> builtins.int:
+        r"""
+        States answered in time.
+        """
+    @property
+    def late(self) -> builtins.int:
+        r"""
+        Answers that came after the deadline and were dropped.
+        """
+    @property
+    def missed(self) -> builtins.int:
+        r"""
+        States the policy never answered.
+        """
+    @property
+    def over_budget(self) -> builtins.bool:
+        r"""
+        Whether the team has gone over its budget.
+        """
+    @property
+    def crashes(self) -> builtins.int:
+        r"""
+        How many times the policy process crashed.
+        """
+    @property
+    def mean_latency_ms(self) -> builtins.float:
+        r"""
+        Mean time from sending a state to receiving its answer, in
+        milliseconds. Late answers are included.
+        """
+    @property
+    def max_latency_ms(self) -> builtins.float:
+        r"""
+        Slowest answer so far, in milliseconds.
+        """
+    @property
+    def last_latency_ms(self) -> builtins.float:
+        r"""
+        Latency of the most recent answer, in milliseconds. With per-agent
+        policies, the slowest of each process's most recent answers.
+        """
+    def __repr__(self) -> builtins.str: ...
+
+class RandomBot:
+    r"""
+    A baseline that walks each agent at full speed in a random direction,
+    picking a new one every `turn_interval` seconds of match time.
+    """
+    def __new__(cls, side:Team, seed:typing.Optional[builtins.int]=None, turn_interval:builtins.float=1.0) -> RandomBot:
+        r"""
+        Parameters
+          `seed`: Seed for the walk. Random if unset.
+          `turn_interval`: Seconds between turns.
+        """
+    def startup(self, initial_state:GameState) -> None: ...
+    def get_action(self, game_state:GameState, agent_state:AgentState) -> Action: ...
+
 class StateQueue:
     @property
     def rate_hz(self) -> builtins.float: ...
@@ -260,22 +1155,152 @@ class Team:
     def from_str(s:builtins.str) -> Team: ...
     def __str__(self) -> builtins.str: ...
 
+class DefenseRole(Enum):
+    r"""
+    What a defender was assigned to do.
+    """
+    Guard = ...
+    r"""
+    Orbit the flag `target`, facing the opponent closest to it.
+    """
+    Chase = ...
+    r"""
+    Run at the opponent `target`: a flag carrier, or the nearest opponent
+    once there are no flags left to guard.
+    """
+    Intercept = ...
+    r"""
+    Cut off the flag carrier `target` on its way to its capture points.
+    """
+
 class FlagStatus(Enum):
     Captured = ...
     PickedUp = ...
     Dropped = ...
 
+class MatchEndReason(Enum):
+    TimeLimit = ...
+    ScoreLimit = ...
+    AllFlagsCaptured = ...
+    Forfeit = ...
+
+class OverBudget(Enum):
+    r"""
+    What happens to a team whose policy goes over its response budget.
+    """
+    Ignore = ...
+    r"""
+    Only log it.
+    """
+    Freeze = ...
+    r"""
+    Stop the team's agents and ignore the policy for the rest of the match.
+    """
+    Forfeit = ...
+    r"""
+    End the match in the other team's favour.
+    """
+
+class TagPenalty(Enum):
+    r"""
+    What happens to an agent tagged in `TaggingMode.Territory`.
+    """
+    Freeze = ...
+    r"""
+    Stop in place for the penalty time.
+    """
+    Jail = ...
+    r"""
+    Move to the team's jail and stay there for the penalty time.
+    """
+    Respawn = ...
+    r"""
+    Move back to the agent's spawn position.
+    """
+
+class TaggingMode(Enum):
+    r"""
+    Who can be tagged, and where.
+    """
+    Legacy = ...
+    r"""
+    Flag carriers are tagged by any opponent, anywhere on the map.
+    """
+    Territory = ...
+    r"""
+    Any intruder is tagged by a defender on the defender's home side.
+    """
+
+class UnseenOpponents(Enum):
+    r"""
+    What a team is told about opponents none of its agents can see.
+    """
+    Omit = ...
+    r"""
+    Leave them out of the state.
+    """
+    LastKnown = ...
+    r"""
+    Report them as last seen, with `last_seen_age` set. Opponents never
+    seen are left out.
+    """
+
+class WireFormat(Enum):
+    r"""
+    How messages to and from policies are encoded after the handshake, which
+    is always JSON.
+    """
+    Json = ...
+    r"""
+    One JSON object per line.
+    """
+    MsgPack = ...
+    r"""
+    Length-prefixed MessagePack, much cheaper to encode and parse.
+    """
+
 def run(config:Config) -> None:
     r"""
     Runs the Capture the Flag simulation with the given policies for each team.
+    
+    A team whose policy is `human` is driven with WASD or a gamepad's left
+    stick. Tab or the right bumper switches to the next agent.
+    
+    Raises `RuntimeError` if the match ended in a forfeit.
+    """
+
+def replay(path:builtins.str) -> None:
+    r"""
+    Plays back a match recorded with `Config.record_path`.
+    
+    Space pauses, Left/Right seek by five seconds and Up/Down change the
+    playback speed.
     """
 
 def run_headless(config:Config) -> StateQueue: ...
 
+def run_tournament(entrants:typing.Sequence[tuple[builtins.str, LaunchSpec]], config:Config, rounds:builtins.int=1, workers:typing.Optional[builtins.int]=None, output:typing.Optional[builtins.str]=None, max_ticks:typing.Optional[builtins.int]=None) -> typing.Any:
+    r"""
+    Plays every pair of `entrants` against each other `rounds` times on each
+    side, in parallel headless worlds.
+    
+    Parameters:
+      - `entrants`: List of (name, launch spec) pairs.
+      - `config`: Settings shared by every match. It must set
+        `rules.time_limit` unless `max_ticks` is given.
+      - `workers`: Matches run in parallel. Defaults to the number of CPUs.
+      - `output`: Also write the results to this JSON file.
+      - `max_ticks`: Abort a match after this many frames.
+    
+    Returns:
+     - A dict with the `standings`, sorted by Elo rating, and every `matches` result.
+    """
+
 def segment_is_free(start:tuple[builtins.float, builtins.float], end:tuple[builtins.float, builtins.float], side:Team, timeout_ms:typing.Optional[builtins.int]=None) -> builtins.bool:
     r"""
     Checks if the line segment from `start` to `end` is free of obstacles by
-    making a blocking RPC to the Bevy app's physics server.
+    making a blocking RPC to the Bevy app's physics server. The connection is
+    kept open and reused by later calls from the same process.
     
     Parameters:
       - `start`: Tuple of (x, y) coordinates for the start of the segment.
@@ -287,3 +1312,68 @@ def segment_is_free(start:tuple[builtins.float, builtins.float], end:tuple[built
      - `True` if the segment is free of obstacles, `False` otherwise. This does not include other agents
     """
 
+def segments_are_free(segments:typing.Sequence[tuple[tuple[builtins.float, builtins.float], tuple[builtins.float, builtins.float]]], side:Team, timeout_ms:typing.Optional[builtins.int]=None) -> builtins.list[builtins.bool]:
+    r"""
+    Like `segment_is_free`, but checks many segments in a single RPC.
+    
+    Parameters:
+      - `segments`: List of ((x, y), (x, y)) start and end points.
+      - `side`: Team ID (Team.RED or Team.BLUE)
+      - `timeout_ms`: Timeout in milliseconds for the whole batch.
+    
+    Returns:
+     - One bool per segment, in the same order.
+    """
+
+def point_is_free(point:tuple[builtins.float, builtins.float], side:Team, timeout_ms:typing.Optional[builtins.int]=None) -> builtins.bool:
+    r"""
+    Checks if an agent standing at `point` would overlap an obstacle.
+    
+    Returns:
+     - `True` if the point is free of obstacles. This does not include other agents
+    """
+
+def raycast(origin:tuple[builtins.float, builtins.float], direction:tuple[builtins.float, builtins.float], max_distance:builtins.float, side:Team, timeout_ms:typing.Optional[builtins.int]=None) -> typing.Optional[builtins.float]:
+    r"""
+    Casts a ray from `origin` along `direction`.
+    
+    Returns:
+     - The distance to the first obstacle, or `None` if nothing is hit within
+       `max_distance`. This does not include other agents
+    """
+
+def nearest_obstacle_distance(point:tuple[builtins.float, builtins.float], side:Team, timeout_ms:typing.Optional[builtins.int]=None) -> typing.Optional[builtins.float]:
+    r"""
+    Distance from `point` to the closest obstacle.
+    
+    Returns:
+     - The distance, `0.0` if the point is inside an obstacle, or `None` if the
+       map has no obstacles. This does not include other agents
+    """
+
+def shortest_path(start:tuple[builtins.float, builtins.float], end:tuple[builtins.float, builtins.float], side:Team, timeout_ms:typing.Optional[builtins.int]=None) -> typing.Optional[builtins.list[tuple[builtins.float, builtins.float]]]:
+    r"""
+    Finds the shortest path from `start` to `end` around walls and the
+    keep-away zones that block `side`. Zones around carried flags are left
+    out, since they move with the carrier.
+    
+    Returns:
+     - The waypoints, starting at `start` and ending at `end`, or `None` if
+       `end` cannot be reached. This does not include other agents
+    
+    Raises `RuntimeError` if the simulator does not answer in time.
+    """
+
+def read_message(stream:typing.Any, wire_format:WireFormat=WireFormat.Json) -> typing.Optional[typing.Union[Hello, GameState]]:
+    r"""
+    Reads the next message from the simulator off a binary stream such as
+    `sys.stdin.buffer`.
+    
+    Parameters:
+      - `stream`: Any object with binary `read` and `readline` methods.
+      - `wire_format`: The agreed format. The hello is always JSON.
+    
+    Returns:
+     - A `Hello` or a `GameState`, or `None` at the end of the stream.
+    """
+
@@ -1,12 +1,15 @@
 use bevy::prelude::*;
 use ctf_core::{
-    agent::{Action, Agent},
+    agent::{Action, Agent, AgentId},
+    flag::FlagId,
+    policy::AgentObservation,
     team::{Team, TeamId},
 };
 use pyo3::prelude::*;
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
 use serde::{Deserialize, Serialize};
 
+use crate::comms::PyMessage;
 use crate::team::PyTeamId;
 
 /// A snapshot of an agent's state in the game.
@@ -18,7 +21,17 @@ pub struct AgentState {
     pub id: u32,
     pub team: TeamId,
     pub position: (f32, f32),
-    pub agent: Agent,
+    pub max_speed: f32,
+    /// ID of the flag the agent is carrying, if any.
+    pub carried_flag: Option<u32>,
+    pub in_home_territory: bool,
+    /// Seconds since an opponent hidden by fog of war was last seen. `None`
+    /// while it is in view.
+    #[serde(default)]
+    pub last_seen_age: Option<f32>,
+    /// Teammates' messages delivered to this agent with this state.
+    #[serde(default)]
+    pub messages: Vec<PyMessage>,
 }
 
 #[gen_stub_pymethods]
@@ -30,7 +43,8 @@ impl AgentState {
         &self.name
     }
 
-    /// The unique identifier of the agent.
+    /// The stable identifier of the agent: its slot in the team, offset by
+    /// 1000 for blue.
     #[getter]
     pub fn id(&self) -> u32 {
         self.id
@@ -45,7 +59,7 @@ impl AgentState {
     #[getter]
     /// The maximum speed of the agent.
     pub fn max_speed(&self) -> f32 {
-        self.agent.speed
+        self.max_speed
     }
 
     /// The position of the agent in the game world as an (x, y) tuple.
@@ -57,7 +71,49 @@ impl AgentState {
     /// If this agent is currently carrying a flag.
     #[getter]
     pub fn has_flag(&self) -> bool {
-        self.agent.flag.is_some()
+        self.carried_flag.is_some()
+    }
+
+    /// The ID of the flag this agent is carrying, if any.
+    #[getter]
+    pub fn carried_flag(&self) -> Option<u32> {
+        self.carried_flag
+    }
+
+    /// If this agent is currently inside its own team's territory.
+    #[getter]
+    pub fn in_home_territory(&self) -> bool {
+        self.in_home_territory
+    }
+
+    /// For an opponent out of sight under fog of war, seconds since it was
+    /// last seen; its other fields are from that moment. `None` while it is
+    /// in view.
+    #[getter]
+    pub fn last_seen_age(&self) -> Option<f32> {
+        self.last_seen_age
+    }
+
+    /// Teammates' messages delivered to this agent with this state, oldest
+    /// first. Always empty for opponents.
+    #[getter]
+    pub fn messages(&self) -> Vec<PyMessage> {
+        self.messages.clone()
+    }
+}
+
+impl From<&AgentState> for AgentObservation {
+    fn from(state: &AgentState) -> Self {
+        AgentObservation {
+            id: state.id,
+            team: state.team,
+            position: state.position.into(),
+            max_speed: state.max_speed,
+            carried_flag: state.carried_flag,
+            in_home_territory: state.in_home_territory,
+            last_seen_age: state.last_seen_age,
+            messages: state.messages.iter().map(Into::into).collect(),
+        }
     }
 }
 
@@ -67,14 +123,22 @@ impl AgentState {
 pub struct PyAction {
     id: u32,
     velocity: (f32, f32),
+    message: Option<String>,
 }
 
 #[gen_stub_pymethods]
 #[pymethods]
 impl PyAction {
+    /// Moves agent `id` at `velocity`, optionally broadcasting `message` to
+    /// its teammates when `Config.comms` is set.
     #[new]
-    pub fn new(id: u32, velocity: (f32, f32)) -> Self {
-        PyAction { id, velocity }
+    #[pyo3(signature = (id, velocity, message=None))]
+    pub fn new(id: u32, velocity: (f32, f32), message: Option<String>) -> Self {
+        PyAction {
+            id,
+            velocity,
+            message,
+        }
     }
 
     fn to_json(&self) -> String {
@@ -82,10 +146,16 @@ impl PyAction {
     }
 
     fn __repr__(&self) -> String {
-        format!(
-            "Action(id={}, velocity=({}, {}))",
-            self.id, self.velocity.0, self.velocity.1
-        )
+        match &self.message {
+            Some(message) => format!(
+                "Action(id={}, velocity=({}, {}), message={:?})",
+                self.id, self.velocity.0, self.velocity.1, message
+            ),
+            None => format!(
+                "Action(id={}, velocity=({}, {}))",
+                self.id, self.velocity.0, self.velocity.1
+            ),
+        }
     }
 
     fn __str__(&self) -> String {
@@ -98,23 +168,44 @@ impl From<PyAction> for Action {
         Action::Move {
             id: val.id,
             velocity: val.velocity.into(),
+            message: val.message,
+        }
+    }
+}
+
+impl From<Action> for PyAction {
+    fn from(val: Action) -> Self {
+        let Action::Move {
+            id,
+            velocity,
+            message,
+        } = val;
+        PyAction {
+            id,
+            velocity: velocity.into(),
+            message,
         }
     }
 }
 
 pub fn collect_agent_states(
-    agents: Query<(Entity, &Name, &Transform, &Agent, &Team)>,
+    agents: Query<(&AgentId, &Name, &Transform, &Agent, &Team)>,
+    flag_ids: impl Fn(Entity) -> Option<FlagId>,
 ) -> (Vec<AgentState>, Vec<AgentState>) {
     let mut red_team = vec![];
     let mut blue_team = vec![];
 
-    for (entity, name, transform, agent, team) in &agents {
+    for (id, name, transform, agent, team) in &agents {
         let agent_state = AgentState {
             name: name.as_str().to_string(),
-            id: entity.index(),
+            id: id.0,
             team: team.0,
             position: (transform.translation.x, transform.translation.z),
-            agent: *agent,
+            max_speed: agent.speed,
+            carried_flag: agent.flag.and_then(&flag_ids).map(|id| id.0),
+            in_home_territory: agent.in_home_territory,
+            last_seen_age: None,
+            messages: Vec::new(),
         };
 
         match team.0 {
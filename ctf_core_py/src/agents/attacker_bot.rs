@@ -0,0 +1,150 @@
+use bevy::math::Vec2;
+use ctf_core::{
+    agent::Action,
+    flag::FlagStatus,
+    policy::{AgentObservation, Navigator, NoNavigation, Observation, Policy},
+    team::TeamId,
+};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use super::{navigate, nearest, team_actions};
+use crate::{
+    agent::{AgentState, PyAction},
+    game::GameState,
+    policy::RpcNavigator,
+    team::PyTeamId,
+};
+
+/// Runs for the nearest loose enemy flag and carries it back to the nearest
+/// free capture point of its own, finding paths around walls through the
+/// physics server.
+#[gen_stub_pyclass]
+#[pyclass]
+pub struct AttackerBot {
+    side: TeamId,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl AttackerBot {
+    #[new]
+    pub fn new(side: PyTeamId) -> Self {
+        AttackerBot { side: side.inner }
+    }
+
+    #[allow(unused_variables)]
+    fn startup(&self, initial_state: GameState) {}
+
+    fn get_action(&self, game_state: GameState, agent_state: AgentState) -> PyAction {
+        let velocity = self.velocity(
+            &Observation::from(&game_state),
+            &AgentObservation::from(&agent_state),
+            &mut RpcNavigator,
+        );
+        PyAction::new(agent_state.id, (velocity.x, velocity.y), None)
+    }
+}
+
+impl Policy for AttackerBot {
+    fn get_actions(&mut self, observation: &Observation) -> Vec<Action> {
+        self.get_actions_with_nav(observation, &mut NoNavigation)
+    }
+
+    fn get_actions_with_nav(
+        &mut self,
+        observation: &Observation,
+        nav: &mut dyn Navigator,
+    ) -> Vec<Action> {
+        team_actions(observation, self.side, |agent| {
+            self.velocity(observation, agent, nav)
+        })
+    }
+}
+
+impl AttackerBot {
+    pub(crate) fn velocity(
+        &self,
+        observation: &Observation,
+        agent: &AgentObservation,
+        nav: &mut dyn Navigator,
+    ) -> Vec2 {
+        let self_pos = agent.position;
+
+        let target = if agent.has_flag() {
+            let points = observation.capture_points(self.side);
+            nearest(points.iter().filter(|p| !p.has_flag), self_pos, |p| {
+                p.position
+            })
+            .map(|p| p.position)
+        } else {
+            let flags = observation.flags(self.side.other());
+            nearest(
+                flags.iter().filter(|f| f.status == FlagStatus::Dropped),
+                self_pos,
+                |f| f.position,
+            )
+            .map(|f| f.position)
+        };
+
+        match target {
+            Some(target) => navigate(nav, self.side, self_pos, target, agent.max_speed),
+            None => Vec2::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::testing::{agent, capture_point, flag};
+
+    fn velocity(observation: &Observation) -> Vec2 {
+        let mut bot = AttackerBot { side: TeamId::Red };
+        match bot.get_actions(observation)[..] {
+            [Action::Move { velocity, .. }] => velocity,
+            ref actions => panic!("expected one move, got {actions:?}"),
+        }
+    }
+
+    #[test]
+    fn runs_for_the_nearest_flag_on_the_ground() {
+        let observation = Observation {
+            red_team: vec![agent(0, TeamId::Red, 0.0, 0.0)],
+            blue_flags: vec![
+                flag(1000, TeamId::Blue, -5.0, 0.0, FlagStatus::Captured),
+                flag(1001, TeamId::Blue, 0.0, 20.0, FlagStatus::Dropped),
+                flag(1002, TeamId::Blue, 30.0, 0.0, FlagStatus::Dropped),
+            ],
+            ..Observation::default()
+        };
+
+        assert_eq!(velocity(&observation), Vec2::new(0.0, 5.0));
+    }
+
+    #[test]
+    fn carries_a_flag_to_the_nearest_free_capture_point() {
+        let mut carrier = agent(0, TeamId::Red, 0.0, 0.0);
+        carrier.carried_flag = Some(1000);
+        let mut taken = capture_point(0, TeamId::Red, -5.0, 0.0);
+        taken.has_flag = true;
+        let observation = Observation {
+            red_team: vec![carrier],
+            red_capture_points: vec![taken, capture_point(1, TeamId::Red, 0.0, -20.0)],
+            ..Observation::default()
+        };
+
+        assert_eq!(velocity(&observation), Vec2::new(0.0, -5.0));
+    }
+
+    #[test]
+    fn waits_with_nothing_to_take() {
+        let observation = Observation {
+            red_team: vec![agent(0, TeamId::Red, 0.0, 0.0)],
+            blue_flags: vec![flag(1000, TeamId::Blue, 10.0, 0.0, FlagStatus::PickedUp)],
+            ..Observation::default()
+        };
+
+        assert_eq!(velocity(&observation), Vec2::ZERO);
+    }
+}
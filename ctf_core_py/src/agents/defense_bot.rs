@@ -1,127 +1,440 @@
+use std::collections::HashMap;
+
 use bevy::math::Vec2;
+use ctf_core::{
+    agent::Action,
+    flag::FlagStatus,
+    policy::{AgentObservation, Observation, Policy},
+    team::TeamId,
+};
 use pyo3::prelude::*;
-use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pymethods};
 
+use super::{clamp_len, interceptor_bot::intercept_point, nearest, steer_toward, team_actions};
 use crate::{
     agent::{AgentState, PyAction},
     game::GameState,
     team::PyTeamId,
 };
 
+/// What a defender was assigned to do.
+#[gen_stub_pyclass_enum]
+#[pyclass(name = "DefenseRole", eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PyDefenseRole {
+    /// Orbit the flag `target`, facing the opponent closest to it.
+    Guard,
+    /// Run at the opponent `target`: a flag carrier, or the nearest opponent
+    /// once there are no flags left to guard.
+    Chase,
+    /// Cut off the flag carrier `target` on its way to its capture points.
+    Intercept,
+}
+
+/// A defender's role and what it is aimed at.
+#[gen_stub_pyclass]
+#[pyclass(name = "DefenseTask", frozen)]
+#[derive(Debug, Clone)]
+pub struct DefenseTask {
+    /// ID of the defender.
+    #[pyo3(get)]
+    pub agent: u32,
+
+    #[pyo3(get)]
+    pub role: PyDefenseRole,
+
+    /// ID of the guarded flag, or of the opponent chased or intercepted.
+    #[pyo3(get)]
+    pub target: u32,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl DefenseTask {
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Defends as a team: one chaser and one interceptor per opponent carrying
+/// a flag, with everyone else spread over the flags on the ground as guards,
+/// or sent after the opponents when there are none.
 #[gen_stub_pyclass]
 #[pyclass]
 pub struct DefenseBot {
-    side: PyTeamId,
+    side: TeamId,
 }
 
 #[gen_stub_pymethods]
 #[pymethods]
 impl DefenseBot {
     #[new]
-    fn new(side: PyTeamId) -> Self {
-        DefenseBot { side }
+    pub fn new(side: PyTeamId) -> Self {
+        DefenseBot { side: side.inner }
     }
 
     #[allow(unused_variables)]
     fn startup(&self, initial_state: GameState) {}
 
     fn get_action(&self, game_state: GameState, agent_state: AgentState) -> PyAction {
-        // Basic params
-        let r_target: f32 = 5.0;
-        let k_p: f32 = 2.0; // position gain toward target point
-        let k_rad: f32 = 1.5; // radial correction gain
-        let tangential_speed_frac: f32 = 0.35; // fraction of max speed for orbit
-
-        let self_pos = Vec2::from(agent_state.position);
-        let max_speed = agent_state.max_speed();
-
-        // --- find closest own flag (defense anchor) ---
-        let own_flags = game_state.get_team_flags(&self.side);
-        let closest_flag = own_flags.iter().min_by(|a, b| {
-            let da = (Vec2::from(a.position) - self_pos).length_squared();
-            let db = (Vec2::from(b.position) - self_pos).length_squared();
-            da.partial_cmp(&db).unwrap()
-        });
+        let observation = Observation::from(&game_state);
+        let tasks = self.assign(&observation, observation.agents(self.side));
+        let velocity = self.velocity(&observation, &AgentObservation::from(&agent_state), &tasks);
+        PyAction::new(agent_state.id, (velocity.x, velocity.y), None)
+    }
+
+    /// The actions for the whole team, decided together.
+    #[pyo3(name = "get_actions")]
+    fn py_get_actions(&self, game_state: GameState) -> Vec<PyAction> {
+        self.team_actions(&Observation::from(&game_state))
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    /// The task of each agent of the team in `game_state`.
+    fn assignment(&self, game_state: GameState) -> Vec<DefenseTask> {
+        let observation = Observation::from(&game_state);
+        let mut tasks: Vec<DefenseTask> = self
+            .assign(&observation, observation.agents(self.side))
+            .into_values()
+            .collect();
+        tasks.sort_by_key(|task| task.agent);
+        tasks
+    }
+}
 
-        // --- opponents + potential carrier ---
-        let opponents = game_state.get_team_agents(&self.side.other());
-        let closest_opp = opponents.iter().min_by(|a, b| {
-            let da = (Vec2::from(a.position) - self_pos).length_squared();
-            let db = (Vec2::from(b.position) - self_pos).length_squared();
-            da.partial_cmp(&db).unwrap()
+impl Policy for DefenseBot {
+    fn get_actions(&mut self, observation: &Observation) -> Vec<Action> {
+        self.team_actions(observation)
+    }
+}
+
+impl DefenseBot {
+    fn team_actions(&self, observation: &Observation) -> Vec<Action> {
+        let tasks = self.assign(observation, observation.agents(self.side));
+        team_actions(observation, self.side, |agent| {
+            self.velocity(observation, agent, &tasks)
+        })
+    }
+
+    /// Splits `defenders` between the opponents carrying flags and the
+    /// team's flags, by agent ID.
+    pub(crate) fn assign(
+        &self,
+        observation: &Observation,
+        defenders: &[AgentObservation],
+    ) -> HashMap<u32, DefenseTask> {
+        let mut free: Vec<&AgentObservation> = defenders.iter().collect();
+        let mut tasks = HashMap::new();
+        let mut take = |free: &mut Vec<&AgentObservation>, at: Vec2, role, target| {
+            let (i, _) = nearest(free.iter().enumerate(), at, |(_, a)| a.position)?;
+            let agent = free.swap_remove(i);
+            tasks.insert(
+                agent.id,
+                DefenseTask {
+                    agent: agent.id,
+                    role,
+                    target,
+                },
+            );
+            Some(())
+        };
+
+        // carriers closest to scoring first
+        let goals = observation.capture_points(self.side.other());
+        let goal_of = |carrier: &AgentObservation| {
+            let at = carrier.position;
+            nearest(goals.iter(), at, |p| p.position).map_or(at, |p| p.position)
+        };
+        let mut carriers: Vec<&AgentObservation> = observation
+            .agents(self.side.other())
+            .iter()
+            .filter(|o| o.has_flag())
+            .collect();
+        carriers.sort_by(|a, b| {
+            let da = a.position.distance(goal_of(a));
+            let db = b.position.distance(goal_of(b));
+            da.total_cmp(&db)
         });
+        for carrier in &carriers {
+            take(
+                &mut free,
+                carrier.position,
+                PyDefenseRole::Chase,
+                carrier.id,
+            );
+        }
+        // meet a carrier halfway to its goal
+        let cut_off = |carrier: &AgentObservation| carrier.position.lerp(goal_of(carrier), 0.5);
+        for carrier in &carriers {
+            take(
+                &mut free,
+                cut_off(carrier),
+                PyDefenseRole::Intercept,
+                carrier.id,
+            );
+        }
 
-        // Heuristic: if any opponent is carrying *a* flag, treat them as carrier to chase.
-        // (In CTF, opponents can only carry our flag, so this is usually correct.)
-        let carrier = opponents.iter().find(|a| a.has_flag());
-
-        let desired_vel: Vec2 = if let Some(carrier) = carrier {
-            // --- CHASE: go straight at the flag carrier ---
-            let target = Vec2::from(carrier.position);
-            steer_toward(self_pos, target, max_speed)
-        } else if let Some(flag) = closest_flag {
-            // --- DEFENSE: hold a ring around the flag, aligned to closest opponent ---
-            let flag_pos = Vec2::from(flag.position);
-
-            // radial vector from flag to us
-            let r = self_pos - flag_pos;
-            let r_len = r.length();
-            let r_dir = if r_len > 1e-4 { r / r_len } else { Vec2::X };
-
-            // direction from flag to closest opponent (if none, use current radial dir)
-            let opp_dir = closest_opp
-                .map(|o| {
-                    let d = Vec2::from(o.position) - flag_pos;
-                    let dl = d.length();
-                    if dl > 1e-4 {
-                        d / dl
-                    } else {
-                        r_dir
-                    }
+        // captured flags sit in enemy territory, beyond saving
+        let flags: Vec<_> = observation
+            .flags(self.side)
+            .iter()
+            .filter(|f| f.status == FlagStatus::Dropped)
+            .collect();
+        let mut guards = vec![0; flags.len()];
+        while !free.is_empty() && !flags.is_empty() {
+            // the least guarded flag, first in order on ties
+            let (i, flag) = flags
+                .iter()
+                .enumerate()
+                .min_by_key(|(i, _)| guards[*i])
+                .unwrap();
+            take(&mut free, flag.position, PyDefenseRole::Guard, flag.id);
+            guards[i] += 1;
+        }
+
+        // nothing to guard: help cut off the carriers, or else go after the
+        // nearest opponent
+        let opponents = observation.agents(self.side.other());
+        while let Some(&defender) = free.first() {
+            let assigned = if carriers.is_empty() {
+                nearest(opponents.iter(), defender.position, |o| o.position)
+                    .and_then(|o| take(&mut free, o.position, PyDefenseRole::Chase, o.id))
+            } else {
+                carriers.iter().try_for_each(|carrier| {
+                    take(
+                        &mut free,
+                        cut_off(carrier),
+                        PyDefenseRole::Intercept,
+                        carrier.id,
+                    )
                 })
-                .unwrap_or(r_dir);
+            };
+            if assigned.is_none() {
+                break;
+            }
+        }
+        tasks
+    }
+
+    pub(crate) fn velocity(
+        &self,
+        observation: &Observation,
+        agent: &AgentObservation,
+        tasks: &HashMap<u32, DefenseTask>,
+    ) -> Vec2 {
+        let self_pos = agent.position;
+        let max_speed = agent.max_speed;
+        let Some(task) = tasks.get(&agent.id) else {
+            // no opponents either? idle.
+            return Vec2::ZERO;
+        };
+
+        let opponents = observation.agents(self.side.other());
+        match task.role {
+            PyDefenseRole::Guard => {
+                let flags = observation.flags(self.side);
+                let Some(flag) = flags.iter().find(|f| f.id == task.target) else {
+                    return Vec2::ZERO;
+                };
+                let flag_pos = flag.position;
+                let closest_opp = nearest(opponents.iter(), flag_pos, |o| o.position);
+                orbit(
+                    self_pos,
+                    flag_pos,
+                    closest_opp.map(|o| o.position),
+                    max_speed,
+                )
+            }
+            PyDefenseRole::Chase | PyDefenseRole::Intercept => {
+                let Some(carrier) = opponents.iter().find(|o| o.id == task.target) else {
+                    return Vec2::ZERO;
+                };
+                let mut target = carrier.position;
+                if task.role == PyDefenseRole::Intercept {
+                    let goals = observation.capture_points(self.side.other());
+                    if let Some(goal) = nearest(goals.iter(), target, |p| p.position) {
+                        target = intercept_point(self_pos, target, goal.position);
+                    }
+                }
+                steer_toward(self_pos, target, max_speed)
+            }
+        }
+    }
+}
+
+/// Holds a ring around `flag_pos`, on the side facing `threat` if given.
+pub(crate) fn orbit(self_pos: Vec2, flag_pos: Vec2, threat: Option<Vec2>, max_speed: f32) -> Vec2 {
+    // Basic params
+    let r_target: f32 = 5.0;
+    let k_p: f32 = 2.0; // position gain toward target point
+    let k_rad: f32 = 1.5; // radial correction gain
+    let tangential_speed_frac: f32 = 0.35; // fraction of max speed for orbit
+
+    // radial vector from flag to us
+    let r = self_pos - flag_pos;
+    let r_len = r.length();
+    let r_dir = if r_len > 1e-4 { r / r_len } else { Vec2::X };
+
+    // direction from flag to the threat (if none, use current radial dir)
+    let opp_dir = threat
+        .map(|o| {
+            let d = o - flag_pos;
+            let dl = d.length();
+            if dl > 1e-4 {
+                d / dl
+            } else {
+                r_dir
+            }
+        })
+        .unwrap_or(r_dir);
+
+    // target point on the circle of radius r_target toward opponent
+    let circle_target = flag_pos + opp_dir * r_target;
+
+    // proportional move toward that target point
+    let p_term = (circle_target - self_pos) * k_p;
+
+    // radial correction to stay near r_target
+    let radial_error = r_len - r_target;
+    let rad_term = -r_dir * (radial_error * k_rad);
 
-            // target point on the circle of radius r_target toward opponent
-            let circle_target = flag_pos + opp_dir * r_target;
+    // tangential (perpendicular) to keep orbiting
+    let tang_dir = Vec2::new(-r_dir.y, r_dir.x); // 90° CCW
+    let tang_term = tang_dir * (tangential_speed_frac * max_speed);
 
-            // proportional move toward that target point
-            let p_term = (circle_target - self_pos) * k_p;
+    let v = p_term + rad_term + tang_term;
+    clamp_len(v, max_speed - f32::EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::testing::{agent, capture_point, flag};
 
-            // radial correction to stay near r_target
-            let radial_error = r_len - r_target;
-            let rad_term = -r_dir * (radial_error * k_rad);
+    /// A blue carrier with red flag 1 at x = 40, heading for the blue
+    /// capture point at x = 80.
+    fn carrying() -> Observation {
+        let mut carrier = agent(1000, TeamId::Blue, 40.0, 0.0);
+        carrier.carried_flag = Some(1);
+        Observation {
+            blue_team: vec![carrier],
+            red_flags: vec![flag(1, TeamId::Red, 40.0, 0.0, FlagStatus::PickedUp)],
+            blue_capture_points: vec![capture_point(1000, TeamId::Blue, 80.0, 0.0)],
+            ..Observation::default()
+        }
+    }
 
-            // tangential (perpendicular) to keep orbiting
-            let tang_dir = Vec2::new(-r_dir.y, r_dir.x); // 90° CCW
-            let tang_term = tang_dir * (tangential_speed_frac * max_speed);
+    fn assign(
+        observation: &Observation,
+        defenders: &[AgentObservation],
+    ) -> Vec<(u32, PyDefenseRole, u32)> {
+        let bot = DefenseBot { side: TeamId::Red };
+        let mut tasks: Vec<_> = bot
+            .assign(observation, defenders)
+            .into_values()
+            .map(|task| (task.agent, task.role, task.target))
+            .collect();
+        tasks.sort_by_key(|task| task.0);
+        tasks
+    }
 
-            let v = p_term + rad_term + tang_term;
-            clamp_len(v, max_speed - f32::EPSILON)
-        } else {
-            // No flags? idle.
-            Vec2::ZERO
+    #[test]
+    fn spreads_guards_over_the_flags_on_the_ground() {
+        let observation = Observation {
+            red_flags: vec![
+                flag(0, TeamId::Red, 0.0, 0.0, FlagStatus::Dropped),
+                flag(1, TeamId::Red, 50.0, 0.0, FlagStatus::Dropped),
+                flag(2, TeamId::Red, 100.0, 0.0, FlagStatus::Captured),
+            ],
+            ..Observation::default()
         };
+        let defenders = [
+            agent(0, TeamId::Red, 1.0, 0.0),
+            agent(1, TeamId::Red, 49.0, 0.0),
+            agent(2, TeamId::Red, 99.0, 0.0),
+        ];
 
-        PyAction::new(agent_state.id, (desired_vel.x, desired_vel.y))
+        assert_eq!(
+            assign(&observation, &defenders),
+            [
+                (0, PyDefenseRole::Guard, 0),
+                (1, PyDefenseRole::Guard, 1),
+                (2, PyDefenseRole::Guard, 0),
+            ]
+        );
     }
-}
 
-// --- helpers ---
-fn clamp_len(v: Vec2, max: f32) -> Vec2 {
-    let len = v.length();
-    if len > max && max > 0.0 {
-        v * (max / len)
-    } else {
-        v
+    #[test]
+    fn sends_a_chaser_and_an_interceptor_after_each_carrier() {
+        let mut observation = carrying();
+        observation
+            .red_flags
+            .push(flag(0, TeamId::Red, 0.0, 0.0, FlagStatus::Dropped));
+        let defenders = [
+            agent(0, TeamId::Red, 38.0, 0.0),
+            agent(1, TeamId::Red, 62.0, 0.0),
+            agent(2, TeamId::Red, 1.0, 0.0),
+        ];
+
+        assert_eq!(
+            assign(&observation, &defenders),
+            [
+                (0, PyDefenseRole::Chase, 1000),
+                (1, PyDefenseRole::Intercept, 1000),
+                (2, PyDefenseRole::Guard, 0),
+            ]
+        );
     }
-}
 
-fn steer_toward(from: Vec2, to: Vec2, max_speed: f32) -> Vec2 {
-    let d = to - from;
-    let l = d.length();
-    if l > 1e-4 {
-        d * (max_speed / l)
-    } else {
-        Vec2::ZERO
+    #[test]
+    fn spare_defenders_cut_off_carriers_when_there_is_nothing_to_guard() {
+        let defenders = [
+            agent(0, TeamId::Red, 38.0, 0.0),
+            agent(1, TeamId::Red, 62.0, 0.0),
+            agent(2, TeamId::Red, 0.0, 0.0),
+            agent(3, TeamId::Red, 100.0, 0.0),
+        ];
+
+        assert_eq!(
+            assign(&carrying(), &defenders),
+            [
+                (0, PyDefenseRole::Chase, 1000),
+                (1, PyDefenseRole::Intercept, 1000),
+                (2, PyDefenseRole::Intercept, 1000),
+                (3, PyDefenseRole::Intercept, 1000),
+            ]
+        );
+    }
+
+    #[test]
+    fn chases_the_nearest_opponents_when_there_is_nothing_to_defend() {
+        let observation = Observation {
+            blue_team: vec![
+                agent(1000, TeamId::Blue, 10.0, 0.0),
+                agent(1001, TeamId::Blue, -10.0, 0.0),
+            ],
+            ..Observation::default()
+        };
+        let defenders = [
+            agent(0, TeamId::Red, 8.0, 0.0),
+            agent(1, TeamId::Red, -3.0, 0.0),
+        ];
+
+        assert_eq!(
+            assign(&observation, &defenders),
+            [
+                (0, PyDefenseRole::Chase, 1000),
+                (1, PyDefenseRole::Chase, 1001),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_defenders_idle_with_no_opponents() {
+        let defenders = [agent(0, TeamId::Red, 0.0, 0.0)];
+
+        assert!(assign(&Observation::default(), &defenders).is_empty());
     }
 }
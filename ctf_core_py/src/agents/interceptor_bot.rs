@@ -0,0 +1,189 @@
+use bevy::math::Vec2;
+use ctf_core::{
+    agent::Action,
+    flag::FlagStatus,
+    policy::{AgentObservation, Navigator, NoNavigation, Observation, Policy},
+    team::TeamId,
+};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use super::{navigate, nearest, team_actions};
+use crate::{
+    agent::{AgentState, PyAction},
+    game::GameState,
+    policy::RpcNavigator,
+    team::PyTeamId,
+};
+
+/// How close to its own flag an interceptor waits when there is no one to
+/// cut off.
+const GUARD_RADIUS: f32 = 3.0;
+
+/// Cuts off opponents on their way in: a flag carrier on its way to its
+/// capture points, or else the opponent closest to one of its own flags.
+/// Waits by the nearest own flag when no opponent is in view.
+#[gen_stub_pyclass]
+#[pyclass]
+pub struct InterceptorBot {
+    side: TeamId,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl InterceptorBot {
+    #[new]
+    pub fn new(side: PyTeamId) -> Self {
+        InterceptorBot { side: side.inner }
+    }
+
+    #[allow(unused_variables)]
+    fn startup(&self, initial_state: GameState) {}
+
+    fn get_action(&self, game_state: GameState, agent_state: AgentState) -> PyAction {
+        let velocity = self.velocity(
+            &Observation::from(&game_state),
+            &AgentObservation::from(&agent_state),
+            &mut RpcNavigator,
+        );
+        PyAction::new(agent_state.id, (velocity.x, velocity.y), None)
+    }
+}
+
+impl Policy for InterceptorBot {
+    fn get_actions(&mut self, observation: &Observation) -> Vec<Action> {
+        self.get_actions_with_nav(observation, &mut NoNavigation)
+    }
+
+    fn get_actions_with_nav(
+        &mut self,
+        observation: &Observation,
+        nav: &mut dyn Navigator,
+    ) -> Vec<Action> {
+        team_actions(observation, self.side, |agent| {
+            self.velocity(observation, agent, nav)
+        })
+    }
+}
+
+impl InterceptorBot {
+    pub(crate) fn velocity(
+        &self,
+        observation: &Observation,
+        agent: &AgentObservation,
+        nav: &mut dyn Navigator,
+    ) -> Vec2 {
+        let self_pos = agent.position;
+        let max_speed = agent.max_speed;
+        let opponents = observation.agents(self.side.other());
+        // only flags on the ground can still be taken
+        let own_flags: Vec<Vec2> = observation
+            .flags(self.side)
+            .iter()
+            .filter(|f| f.status == FlagStatus::Dropped)
+            .map(|f| f.position)
+            .collect();
+
+        // the opponent to cut off, and where it is heading
+        let carriers = opponents.iter().filter(|o| o.has_flag());
+        let threat = match nearest(carriers, self_pos, |o| o.position) {
+            Some(carrier) => {
+                let from = carrier.position;
+                let goals = observation.capture_points(self.side.other());
+                nearest(goals.iter(), from, |p| p.position).map(|p| (from, p.position))
+            }
+            None => opponents
+                .iter()
+                .filter_map(|o| {
+                    let from = o.position;
+                    nearest(own_flags.iter().copied(), from, |f| *f).map(|goal| (from, goal))
+                })
+                .min_by(|(a, a_goal), (b, b_goal)| {
+                    a.distance_squared(*a_goal)
+                        .total_cmp(&b.distance_squared(*b_goal))
+                }),
+        };
+
+        if let Some((from, goal)) = threat {
+            let target = intercept_point(self_pos, from, goal);
+            return navigate(nav, self.side, self_pos, target, max_speed);
+        }
+
+        match nearest(own_flags.iter().copied(), self_pos, |f| *f) {
+            Some(flag) if flag.distance(self_pos) > GUARD_RADIUS => {
+                navigate(nav, self.side, self_pos, flag, max_speed)
+            }
+            _ => Vec2::ZERO,
+        }
+    }
+}
+
+/// Where to meet an opponent running from `from` to `goal` at the same
+/// speed: as far along its way as it gets while we cover the distance to it.
+pub(crate) fn intercept_point(self_pos: Vec2, from: Vec2, goal: Vec2) -> Vec2 {
+    let way = goal - from;
+    let lead = self_pos.distance(from).min(way.length());
+    from + way.normalize_or_zero() * lead
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::testing::{agent, capture_point, flag};
+
+    fn velocity(observation: &Observation, at: Vec2) -> Vec2 {
+        let bot = InterceptorBot { side: TeamId::Red };
+        bot.velocity(
+            observation,
+            &agent(0, TeamId::Red, at.x, at.y),
+            &mut NoNavigation,
+        )
+    }
+
+    #[test]
+    fn cuts_off_a_carrier_on_its_way_to_a_capture_point() {
+        let mut carrier = agent(1000, TeamId::Blue, 20.0, 0.0);
+        carrier.carried_flag = Some(0);
+        let observation = Observation {
+            blue_team: vec![carrier],
+            blue_capture_points: vec![capture_point(1000, TeamId::Blue, 40.0, 0.0)],
+            ..Observation::default()
+        };
+
+        // it meets the carrier 10 along its way, at (30, 0)
+        let expected = Vec2::new(10.0, -10.0).normalize() * 5.0;
+        assert!(velocity(&observation, Vec2::new(20.0, 10.0)).abs_diff_eq(expected, 1e-4));
+    }
+
+    #[test]
+    fn guards_only_the_flags_still_on_the_ground() {
+        let observation = Observation {
+            blue_team: vec![agent(1000, TeamId::Blue, 20.0, 0.0)],
+            red_flags: vec![
+                flag(0, TeamId::Red, 0.0, 0.0, FlagStatus::Dropped),
+                flag(1, TeamId::Red, 19.0, 0.0, FlagStatus::Captured),
+            ],
+            ..Observation::default()
+        };
+
+        // the opponent goes for flag 0, and is met there
+        assert_eq!(
+            velocity(&observation, Vec2::new(0.0, 10.0)),
+            Vec2::new(0.0, -5.0)
+        );
+    }
+
+    #[test]
+    fn waits_by_its_flag_with_no_opponent_in_view() {
+        let observation = Observation {
+            red_flags: vec![flag(0, TeamId::Red, 0.0, 0.0, FlagStatus::Dropped)],
+            ..Observation::default()
+        };
+
+        assert_eq!(
+            velocity(&observation, Vec2::new(10.0, 0.0)),
+            Vec2::new(-5.0, 0.0)
+        );
+        assert_eq!(velocity(&observation, Vec2::new(1.0, 0.0)), Vec2::ZERO);
+    }
+}
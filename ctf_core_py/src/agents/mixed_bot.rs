@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use bevy::math::Vec2;
+use ctf_core::{
+    agent::Action,
+    policy::{AgentObservation, Navigator, NoNavigation, Observation, Policy},
+    team::TeamId,
+};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use super::{
+    attacker_bot::AttackerBot,
+    defense_bot::{DefenseBot, DefenseTask},
+    interceptor_bot::InterceptorBot,
+    nearest, team_actions,
+};
+use crate::{
+    agent::{AgentState, PyAction},
+    game::GameState,
+    policy::RpcNavigator,
+    team::PyTeamId,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Attack,
+    Defend,
+    Intercept,
+}
+
+impl Role {
+    fn name(self) -> &'static str {
+        match self {
+            Role::Attack => "attack",
+            Role::Defend => "defend",
+            Role::Intercept => "intercept",
+        }
+    }
+}
+
+/// Splits the team into roles every state: flag carriers and the half of
+/// the team closest to the enemy flags attack like `AttackerBot`, the
+/// teammate closest to an opponent carrying a flag intercepts it like
+/// `InterceptorBot`, and the rest defend like `DefenseBot`.
+#[gen_stub_pyclass]
+#[pyclass]
+pub struct MixedBot {
+    side: TeamId,
+    attacker: AttackerBot,
+    defender: DefenseBot,
+    interceptor: InterceptorBot,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl MixedBot {
+    #[new]
+    pub fn new(side: PyTeamId) -> Self {
+        MixedBot {
+            attacker: AttackerBot::new(side.clone()),
+            defender: DefenseBot::new(side.clone()),
+            interceptor: InterceptorBot::new(side.clone()),
+            side: side.inner,
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn startup(&self, initial_state: GameState) {}
+
+    fn get_action(&self, game_state: GameState, agent_state: AgentState) -> PyAction {
+        let observation = Observation::from(&game_state);
+        let roles = self.assign_roles(&observation);
+        let defense = self.assign_defense(&observation, &roles);
+        let velocity = self.velocity(
+            &observation,
+            &AgentObservation::from(&agent_state),
+            &roles,
+            &defense,
+            &mut RpcNavigator,
+        );
+        PyAction::new(agent_state.id, (velocity.x, velocity.y), None)
+    }
+
+    /// The role of each agent of the team in `game_state`, by agent ID:
+    /// `attack`, `defend` or `intercept`.
+    fn roles(&self, game_state: GameState) -> HashMap<u32, String> {
+        self.assign_roles(&Observation::from(&game_state))
+            .into_iter()
+            .map(|(id, role)| (id, role.name().to_string()))
+            .collect()
+    }
+}
+
+impl Policy for MixedBot {
+    fn get_actions(&mut self, observation: &Observation) -> Vec<Action> {
+        self.get_actions_with_nav(observation, &mut NoNavigation)
+    }
+
+    fn get_actions_with_nav(
+        &mut self,
+        observation: &Observation,
+        nav: &mut dyn Navigator,
+    ) -> Vec<Action> {
+        let roles = self.assign_roles(observation);
+        let defense = self.assign_defense(observation, &roles);
+        team_actions(observation, self.side, |agent| {
+            self.velocity(observation, agent, &roles, &defense, nav)
+        })
+    }
+}
+
+impl MixedBot {
+    fn assign_roles(&self, observation: &Observation) -> HashMap<u32, Role> {
+        let own = observation.agents(self.side);
+        let mut roles: HashMap<u32, Role> = own
+            .iter()
+            .filter(|a| a.has_flag())
+            .map(|a| (a.id, Role::Attack))
+            .collect();
+        let free = |roles: &HashMap<u32, Role>| {
+            own.iter()
+                .filter(|a| !roles.contains_key(&a.id))
+                .collect::<Vec<_>>()
+        };
+
+        let opponents = observation.agents(self.side.other());
+        for carrier in opponents.iter().filter(|o| o.has_flag()) {
+            let at = carrier.position;
+            if let Some(chaser) = nearest(free(&roles), at, |a| a.position) {
+                roles.insert(chaser.id, Role::Intercept);
+            }
+        }
+
+        let enemy_flags: Vec<Vec2> = observation
+            .flags(self.side.other())
+            .iter()
+            .map(|f| f.position)
+            .collect();
+        let distance_to_flags = |a: &AgentObservation| {
+            let at = a.position;
+            nearest(enemy_flags.iter().copied(), at, |f| *f)
+                .map_or(f32::INFINITY, |f| f.distance(at))
+        };
+        let mut candidates = free(&roles);
+        candidates.sort_by(|a, b| distance_to_flags(a).total_cmp(&distance_to_flags(b)));
+        let attackers = own.len().div_ceil(2);
+        let attacking = roles.values().filter(|r| **r == Role::Attack).count();
+        for (i, agent) in candidates.into_iter().enumerate() {
+            let role = if attacking + i < attackers {
+                Role::Attack
+            } else {
+                Role::Defend
+            };
+            roles.insert(agent.id, role);
+        }
+        roles
+    }
+
+    /// Splits the defenders' work between them like `DefenseBot` does.
+    fn assign_defense(
+        &self,
+        observation: &Observation,
+        roles: &HashMap<u32, Role>,
+    ) -> HashMap<u32, DefenseTask> {
+        let defenders: Vec<AgentObservation> = observation
+            .agents(self.side)
+            .iter()
+            .filter(|a| roles.get(&a.id).is_none_or(|r| *r == Role::Defend))
+            .cloned()
+            .collect();
+        self.defender.assign(observation, &defenders)
+    }
+
+    fn velocity(
+        &self,
+        observation: &Observation,
+        agent: &AgentObservation,
+        roles: &HashMap<u32, Role>,
+        defense: &HashMap<u32, DefenseTask>,
+        nav: &mut dyn Navigator,
+    ) -> Vec2 {
+        match roles.get(&agent.id) {
+            Some(Role::Attack) => self.attacker.velocity(observation, agent, nav),
+            Some(Role::Intercept) => self.interceptor.velocity(observation, agent, nav),
+            Some(Role::Defend) | None => self.defender.velocity(observation, agent, defense),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ctf_core::flag::FlagStatus;
+
+    use super::*;
+    use crate::agents::testing::{agent, flag};
+
+    fn roles(observation: &Observation) -> Vec<(u32, Role)> {
+        let bot = MixedBot::new(PyTeamId { inner: TeamId::Red });
+        let mut roles: Vec<_> = bot.assign_roles(observation).into_iter().collect();
+        roles.sort_by_key(|(id, _)| *id);
+        roles
+    }
+
+    #[test]
+    fn sends_the_half_closest_to_the_enemy_flags_to_attack() {
+        let observation = Observation {
+            red_team: vec![
+                agent(0, TeamId::Red, 0.0, 0.0),
+                agent(1, TeamId::Red, 10.0, 0.0),
+                agent(2, TeamId::Red, 35.0, 0.0),
+                agent(3, TeamId::Red, 38.0, 0.0),
+            ],
+            blue_flags: vec![flag(1000, TeamId::Blue, 40.0, 0.0, FlagStatus::Dropped)],
+            ..Observation::default()
+        };
+
+        assert_eq!(
+            roles(&observation),
+            [
+                (0, Role::Defend),
+                (1, Role::Defend),
+                (2, Role::Attack),
+                (3, Role::Attack),
+            ]
+        );
+    }
+
+    #[test]
+    fn carriers_attack_and_the_closest_teammate_intercepts() {
+        let mut carrier = agent(1, TeamId::Red, 10.0, 0.0);
+        carrier.carried_flag = Some(1000);
+        let mut opponent = agent(1000, TeamId::Blue, -5.0, 0.0);
+        opponent.carried_flag = Some(0);
+        let observation = Observation {
+            red_team: vec![
+                agent(0, TeamId::Red, 0.0, 0.0),
+                carrier,
+                agent(2, TeamId::Red, 30.0, 0.0),
+            ],
+            blue_team: vec![opponent],
+            blue_flags: vec![flag(1001, TeamId::Blue, 40.0, 0.0, FlagStatus::Dropped)],
+            ..Observation::default()
+        };
+
+        assert_eq!(
+            roles(&observation),
+            [(0, Role::Intercept), (1, Role::Attack), (2, Role::Attack)]
+        );
+    }
+}
@@ -1 +1,127 @@
+pub mod attacker_bot;
 pub mod defense_bot;
+pub mod interceptor_bot;
+pub mod mixed_bot;
+pub mod random_bot;
+
+use bevy::math::Vec2;
+use ctf_core::{
+    agent::Action,
+    policy::{AgentObservation, Navigator, Observation},
+    team::TeamId,
+};
+
+/// How close an agent gets to a waypoint before heading for the next one.
+const WAYPOINT_RADIUS: f32 = 0.75;
+
+/// A move for each of `side`'s agents in `observation`, at the velocity
+/// `velocity` picks for it.
+pub(crate) fn team_actions(
+    observation: &Observation,
+    side: TeamId,
+    mut velocity: impl FnMut(&AgentObservation) -> Vec2,
+) -> Vec<Action> {
+    observation
+        .agents(side)
+        .iter()
+        .map(|agent| Action::Move {
+            id: agent.id,
+            velocity: velocity(agent),
+            message: None,
+        })
+        .collect()
+}
+
+/// The one of `items` closest to `from`.
+pub(crate) fn nearest<T>(
+    items: impl IntoIterator<Item = T>,
+    from: Vec2,
+    position: impl Fn(&T) -> Vec2,
+) -> Option<T> {
+    items.into_iter().min_by(|a, b| {
+        let da = (position(a) - from).length_squared();
+        let db = (position(b) - from).length_squared();
+        da.total_cmp(&db)
+    })
+}
+
+/// Full speed toward `to`, along a path around walls when `nav` finds one.
+pub(crate) fn navigate(
+    nav: &mut dyn Navigator,
+    side: TeamId,
+    from: Vec2,
+    to: Vec2,
+    max_speed: f32,
+) -> Vec2 {
+    let waypoint = nav
+        .shortest_path(from, to, side)
+        .and_then(|path| {
+            path.into_iter()
+                .find(|p| p.distance(from) > WAYPOINT_RADIUS)
+        })
+        .unwrap_or(to);
+    steer_toward(from, waypoint, max_speed)
+}
+
+pub(crate) fn clamp_len(v: Vec2, max: f32) -> Vec2 {
+    let len = v.length();
+    if len > max && max > 0.0 {
+        v * (max / len)
+    } else {
+        v
+    }
+}
+
+pub(crate) fn steer_toward(from: Vec2, to: Vec2, max_speed: f32) -> Vec2 {
+    let d = to - from;
+    let l = d.length();
+    if l > 1e-4 {
+        d * (max_speed / l)
+    } else {
+        Vec2::ZERO
+    }
+}
+
+/// Observations to test the built-in policies against.
+#[cfg(test)]
+pub(crate) mod testing {
+    use bevy::math::Vec2;
+    use ctf_core::{
+        flag::FlagStatus,
+        policy::{AgentObservation, CapturePointObservation, FlagObservation},
+        team::TeamId,
+    };
+
+    /// An agent of `team` at (`x`, `y`), not carrying a flag.
+    pub fn agent(id: u32, team: TeamId, x: f32, y: f32) -> AgentObservation {
+        AgentObservation {
+            id,
+            team,
+            position: Vec2::new(x, y),
+            max_speed: 5.0,
+            carried_flag: None,
+            in_home_territory: true,
+            last_seen_age: None,
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn flag(id: u32, team: TeamId, x: f32, y: f32, status: FlagStatus) -> FlagObservation {
+        FlagObservation {
+            id,
+            team,
+            position: Vec2::new(x, y),
+            status,
+        }
+    }
+
+    /// An empty capture point of `team` at (`x`, `y`).
+    pub fn capture_point(id: u32, team: TeamId, x: f32, y: f32) -> CapturePointObservation {
+        CapturePointObservation {
+            id,
+            team,
+            position: Vec2::new(x, y),
+            has_flag: false,
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use bevy::math::Vec2;
+use ctf_core::{
+    agent::Action,
+    policy::{AgentObservation, Observation, Policy},
+    team::TeamId,
+};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::team_actions;
+use crate::{
+    agent::{AgentState, PyAction},
+    game::GameState,
+    team::PyTeamId,
+};
+
+/// A heading an agent keeps until it is due to turn.
+struct Heading {
+    direction: Vec2,
+    turn_at: f32,
+}
+
+/// A baseline that walks each agent at full speed in a random direction,
+/// picking a new one every `turn_interval` seconds of match time.
+#[gen_stub_pyclass]
+#[pyclass]
+pub struct RandomBot {
+    side: TeamId,
+    turn_interval: f32,
+    rng: StdRng,
+    headings: HashMap<u32, Heading>,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl RandomBot {
+    /// Parameters
+    ///   `seed`: Seed for the walk. Random if unset.
+    ///   `turn_interval`: Seconds between turns.
+    #[new]
+    #[pyo3(signature = (side, seed=None, turn_interval=1.0))]
+    pub fn new(side: PyTeamId, seed: Option<u64>, turn_interval: f32) -> Self {
+        RandomBot {
+            side: side.inner,
+            turn_interval,
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_os_rng(),
+            },
+            headings: HashMap::new(),
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn startup(&self, initial_state: GameState) {}
+
+    fn get_action(&mut self, game_state: GameState, agent_state: AgentState) -> PyAction {
+        let velocity = self.velocity(
+            &Observation::from(&game_state),
+            &AgentObservation::from(&agent_state),
+        );
+        PyAction::new(agent_state.id, (velocity.x, velocity.y), None)
+    }
+}
+
+impl Policy for RandomBot {
+    fn get_actions(&mut self, observation: &Observation) -> Vec<Action> {
+        let side = self.side;
+        team_actions(observation, side, |agent| self.velocity(observation, agent))
+    }
+}
+
+impl RandomBot {
+    fn velocity(&mut self, observation: &Observation, agent: &AgentObservation) -> Vec2 {
+        let now = observation.elapsed;
+        let heading = self.headings.entry(agent.id).or_insert(Heading {
+            direction: Vec2::ZERO,
+            turn_at: now,
+        });
+        if now >= heading.turn_at {
+            let angle = self.rng.random_range(0.0..std::f32::consts::TAU);
+            heading.direction = Vec2::from_angle(angle);
+            heading.turn_at = now + self.turn_interval;
+        }
+        heading.direction * agent.max_speed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::testing::agent;
+
+    fn bot(seed: u64) -> RandomBot {
+        RandomBot::new(PyTeamId { inner: TeamId::Red }, Some(seed), 1.0)
+    }
+
+    fn at(elapsed: f32) -> Observation {
+        Observation {
+            red_team: vec![agent(0, TeamId::Red, 0.0, 0.0)],
+            elapsed,
+            ..Observation::default()
+        }
+    }
+
+    #[test]
+    fn walks_at_full_speed_until_it_is_due_to_turn() {
+        let mut bot = bot(7);
+        let agent = agent(0, TeamId::Red, 0.0, 0.0);
+
+        let first = bot.velocity(&at(0.0), &agent);
+        assert!((first.length() - agent.max_speed).abs() < 1e-4);
+        assert_eq!(bot.velocity(&at(0.5), &agent), first);
+        assert_ne!(bot.velocity(&at(1.0), &agent), first);
+    }
+
+    #[test]
+    fn the_same_seed_walks_the_same_way() {
+        let (mut a, mut b) = (bot(7), bot(7));
+        for elapsed in [0.0, 1.0, 2.0] {
+            assert_eq!(a.get_actions(&at(elapsed)), b.get_actions(&at(elapsed)));
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Context;
+use ctf_core_py::tournament::{run, TournamentConfig};
+
+/// Runs a round-robin tournament described by a JSON file and prints the
+/// standings.
+fn main() -> anyhow::Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .context("usage: tournament <tournament.json>")?;
+    let json = std::fs::read_to_string(&path).with_context(|| format!("reading {path}"))?;
+    let tournament: TournamentConfig =
+        serde_json::from_str(&json).with_context(|| format!("parsing {path}"))?;
+
+    let results = run(&tournament)?;
+
+    if tournament.output.is_none() {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+    for (rank, standing) in results.standings.iter().enumerate() {
+        println!(
+            "{:>3}. {:<24} {:>7.1}  {}W {}D {}L",
+            rank + 1,
+            standing.name,
+            standing.rating,
+            standing.wins,
+            standing.draws,
+            standing.losses
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+// --- Persistent client for the physics RPC server ---
+//
+// Policies run many queries per tick, so the connection is opened once per
+// process and reused. It is dropped after any error, since a late reply
+// would otherwise be read as the answer to the next request.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    sync::Mutex,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use pyo3::{exceptions, PyErr, PyResult};
+use serde::{Deserialize, Serialize};
+
+use super::physics::{get_physics_rpc_addr, PhysAnswer, PhysRequest};
+
+#[derive(Serialize)]
+struct Request<'a> {
+    id: u64,
+    #[serde(flatten)]
+    request: &'a PhysRequest,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    id: u64,
+    #[serde(flatten)]
+    answer: PhysAnswer,
+}
+
+struct PhysicsClient {
+    addr: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    next_id: u64,
+}
+
+static CLIENT: Lazy<Mutex<Option<PhysicsClient>>> = Lazy::new(|| Mutex::new(None));
+
+impl PhysicsClient {
+    fn connect(addr: &str) -> PyResult<Self> {
+        let stream = TcpStream::connect(addr).map_err(|e| {
+            exceptions::PyConnectionError::new_err(format!("connect {}: {}", addr, e))
+        })?;
+        let _ = stream.set_nodelay(true);
+        let reader = BufReader::new(stream.try_clone().map_err(|e| {
+            exceptions::PyConnectionError::new_err(format!("connect {}: {}", addr, e))
+        })?);
+        Ok(Self {
+            addr: addr.to_string(),
+            reader,
+            writer: stream,
+            next_id: 1,
+        })
+    }
+
+    fn query(&mut self, request: &PhysRequest, timeout_ms: Option<u64>) -> PyResult<PhysAnswer> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let _ = self
+            .writer
+            .set_read_timeout(timeout_ms.map(Duration::from_millis));
+
+        let line = serde_json::to_string(&Request { id, request }).unwrap();
+        writeln!(self.writer, "{}", line)
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| exceptions::PyRuntimeError::new_err(format!("send physics req: {}", e)))?;
+
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => {
+                return Err(exceptions::PyConnectionError::new_err(
+                    "physics server closed the connection",
+                ))
+            }
+            Ok(_) => {}
+            Err(_) => {
+                return Err(exceptions::PyTimeoutError::new_err(
+                    "Timeout waiting for physics response",
+                ))
+            }
+        }
+
+        let resp: Response = serde_json::from_str(&line)
+            .map_err(|e| exceptions::PyRuntimeError::new_err(format!("bad physics resp: {}", e)))?;
+        if resp.id != id {
+            return Err(exceptions::PyRuntimeError::new_err(format!(
+                "physics resp id {} does not match request {}",
+                resp.id, id
+            )));
+        }
+        match resp.answer {
+            PhysAnswer::Error { error } => Err(exceptions::PyRuntimeError::new_err(format!(
+                "physics query failed: {error}"
+            ))),
+            answer => Ok(answer),
+        }
+    }
+}
+
+fn server_addr() -> PyResult<String> {
+    // Server address from env or from the physics module (same-process).
+    std::env::var("PHYSICS_ADDR")
+        .ok()
+        .or_else(|| get_physics_rpc_addr().map(|a| a.to_string()))
+        .ok_or_else(|| {
+            exceptions::PyRuntimeError::new_err(
+                "Physics RPC not initialized (PHYSICS_ADDR not set)",
+            )
+        })
+}
+
+/// Sends `request` over this process's shared connection, opening it first if
+/// needed.
+pub fn query(request: &PhysRequest, timeout_ms: Option<u64>) -> PyResult<PhysAnswer> {
+    let addr = server_addr()?;
+    let mut client = CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+
+    if client.as_ref().is_none_or(|c| c.addr != addr) {
+        *client = Some(PhysicsClient::connect(&addr)?);
+    }
+
+    let result = client.as_mut().unwrap().query(request, timeout_ms);
+    if result.is_err() {
+        *client = None;
+    }
+    result
+}
+
+pub fn unexpected_answer(answer: PhysAnswer) -> PyErr {
+    exceptions::PyRuntimeError::new_err(format!("unexpected physics resp: {:?}", answer))
+}
@@ -1,2 +1,4 @@
+pub mod client;
+pub mod native;
 pub mod physics;
 pub mod policy;
@@ -0,0 +1,148 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use ctf_core::{
+    agent::{Agent, AgentIndex},
+    character_controller::MovementEvent,
+    navigation::NavGrids,
+    policy::{Navigator, Observation, Policy},
+    team::{Team, TeamId},
+};
+
+use crate::{
+    bridge::policy::{
+        apply_actions, retain_owned, write_actions, ActionsReceived, GameStatePublished, PolicySet,
+    },
+    comms::{route_messages, Mailbox},
+    config::PyConfig,
+    human::HUMAN_POLICY,
+    observation::Observer,
+    policy::builtin,
+};
+
+struct NativePolicy {
+    team: TeamId,
+    policy: Box<dyn Policy>,
+    started: bool,
+}
+
+/// The Rust policies playing inside the app.
+#[derive(Resource, Default)]
+pub struct NativePolicies {
+    policies: Vec<NativePolicy>,
+    /// Filters each team's state under fog of war.
+    observer: Option<Observer>,
+}
+
+impl NativePolicies {
+    /// Lets `policy` control `team`. The team should not also have a policy
+    /// process.
+    pub fn register(&mut self, team: TeamId, policy: Box<dyn Policy>) {
+        self.policies.push(NativePolicy {
+            team,
+            policy,
+            started: false,
+        });
+    }
+}
+
+/// Runs the built-in policies picked with `red_team_policy` and
+/// `blue_team_policy` in the config each policy tick. The ticks are paced
+/// by `PythonPolicyBridgePlugin`, which adds this plugin itself.
+pub struct NativePolicyPlugin {
+    pub config: PyConfig,
+}
+
+impl Plugin for NativePolicyPlugin {
+    fn build(&self, app: &mut App) {
+        let mut policies = NativePolicies {
+            policies: Vec::new(),
+            observer: self.config.fog_of_war.clone().map(Observer::new),
+        };
+        for team in [TeamId::Red, TeamId::Blue] {
+            if let Some(name) = self
+                .config
+                .team_policy(team)
+                .filter(|name| *name != HUMAN_POLICY)
+            {
+                // checked by PyConfig::check_team_policies before the app is built
+                let policy = builtin(name, team, self.config.seed)
+                    .unwrap_or_else(|e| panic!("Failed to start {team:?} policy: {e}"));
+                policies.register(team, policy);
+            }
+        }
+        app.insert_resource(policies);
+
+        app.add_systems(
+            Update,
+            run_native_policies
+                .in_set(PolicySet)
+                .after(apply_actions)
+                .before(route_messages),
+        );
+    }
+}
+
+/// Finds paths on the app's own navigation grids.
+struct GridNavigator<'a, 'w, 's> {
+    nav: &'a mut NavGrids,
+    spatial: &'a SpatialQuery<'w, 's>,
+}
+
+impl Navigator for GridNavigator<'_, '_, '_> {
+    fn shortest_path(&mut self, start: Vec2, end: Vec2, team: TeamId) -> Option<Vec<Vec2>> {
+        self.nav.shortest_path(self.spatial, start, end, team)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_native_policies(
+    mut native: ResMut<NativePolicies>,
+    mut published: EventReader<GameStatePublished>,
+    spatial: SpatialQuery,
+    mut nav: ResMut<NavGrids>,
+    mut mailbox: Option<ResMut<Mailbox>>,
+    index: Res<AgentIndex>,
+    agents: Query<&Agent>,
+    teams: Query<&Team>,
+    mut movement_event_writer: EventWriter<MovementEvent>,
+    mut received: EventWriter<ActionsReceived>,
+) {
+    let Some(GameStatePublished(state)) = published.read().last() else {
+        return;
+    };
+    let native = &mut *native;
+
+    for entry in &mut native.policies {
+        let team = entry.team;
+        let mut team_state = state.clone();
+        if let Some(mailbox) = &mut mailbox {
+            mailbox.deliver(&mut team_state, team);
+            mailbox.delivered(team, team_state.elapsed, |_| true);
+        }
+        let observation = match &mut native.observer {
+            Some(observer) => observer.observe(&team_state, team, None, &spatial),
+            None => team_state,
+        };
+        let observation = Observation::from(&observation);
+
+        if !entry.started {
+            entry.policy.startup(&observation);
+            entry.started = true;
+        }
+        let mut navigator = GridNavigator {
+            nav: &mut nav,
+            spatial: &spatial,
+        };
+        let mut actions = entry
+            .policy
+            .get_actions_with_nav(&observation, &mut navigator);
+        retain_owned(&mut actions, team, None, &index, &teams);
+
+        received.write(ActionsReceived {
+            team,
+            tick: observation.tick,
+            actions: actions.clone(),
+        });
+        write_actions(actions, &index, &agents, &mut movement_event_writer);
+    }
+}
@@ -2,102 +2,313 @@ use std::sync::RwLock;
 
 use avian3d::prelude::*;
 use bevy::prelude::*;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use ctf_core::navigation::NavGrids;
 use ctf_core::team::TeamId;
-use ctf_core::{segment_hits_wall_flag_or_capture_point, Segment2D};
+use ctf_core::{
+    nearest_obstacle_distance, raycast_obstacle, segment_hits_wall_flag_or_capture_point, Segment2D,
+};
 use once_cell::sync::Lazy;
 
-pub enum PhysicsQuery {
-    SegmentCollision2D {
-        seg: Segment2D,
-        team_id: TeamId,
-        reply: Sender<bool>, // one-shot back to caller
+/// A single spatial query, tagged by `kind` on the wire. Obstacles are walls
+/// plus the keep-away zones that block `team`; other agents are ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PhysRequest {
+    /// Whether an agent can move from `start` to `end`.
+    Segment {
+        start: (f32, f32),
+        end: (f32, f32),
+        team: TeamId,
+    },
+    /// Distance to the first obstacle along a ray.
+    Raycast {
+        origin: (f32, f32),
+        direction: (f32, f32),
+        max_distance: f32,
+        team: TeamId,
     },
+    /// Distance to the closest obstacle.
+    NearestObstacle { point: (f32, f32), team: TeamId },
+    /// Whether an agent fits at `point`.
+    PointFree { point: (f32, f32), team: TeamId },
+    /// Waypoints of the shortest path from `start` to `end`.
+    ShortestPath {
+        start: (f32, f32),
+        end: (f32, f32),
+        team: TeamId,
+    },
+    /// Several queries answered in the same frame, in order.
+    Batch { queries: Vec<PhysRequest> },
 }
 
-#[derive(Resource)]
-pub struct PhysicsRx(pub Receiver<PhysicsQuery>);
-
-// Replaceable global sender (set by the plugin)
-pub static PHYSICS_TX: Lazy<RwLock<Option<Sender<PhysicsQuery>>>> = Lazy::new(|| RwLock::new(None));
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PhysAnswer {
+    // Tried in order: `Distance` would also accept a batch, since its only
+    // field is optional.
+    Batch {
+        results: Vec<PhysAnswer>,
+    },
+    /// The query could not be answered.
+    Error {
+        error: String,
+    },
+    /// Empty if there is no path.
+    Path {
+        path: Vec<(f32, f32)>,
+    },
+    Free {
+        free: bool,
+    },
+    Distance {
+        distance: Option<f32>,
+    },
+}
 
-pub fn set_physics_tx(tx: Sender<PhysicsQuery>) {
-    *PHYSICS_TX.write().unwrap() = Some(tx);
+pub fn answer_request(
+    spatial: &SpatialQuery,
+    nav: &mut NavGrids,
+    request: &PhysRequest,
+) -> PhysAnswer {
+    match request {
+        PhysRequest::Segment { start, end, team } => {
+            let seg = Segment2D {
+                start: Vec2::from(*start),
+                end: Vec2::from(*end),
+            };
+            PhysAnswer::Free {
+                free: !segment_hits_wall_flag_or_capture_point(spatial, seg, *team),
+            }
+        }
+        PhysRequest::Raycast {
+            origin,
+            direction,
+            max_distance,
+            team,
+        } => PhysAnswer::Distance {
+            distance: raycast_obstacle(
+                spatial,
+                Vec2::from(*origin),
+                Vec2::from(*direction),
+                *max_distance,
+                *team,
+            ),
+        },
+        PhysRequest::NearestObstacle { point, team } => PhysAnswer::Distance {
+            distance: nearest_obstacle_distance(spatial, Vec2::from(*point), *team),
+        },
+        PhysRequest::PointFree { point, team } => {
+            let point = Vec2::from(*point);
+            let seg = Segment2D {
+                start: point,
+                end: point,
+            };
+            PhysAnswer::Free {
+                free: !segment_hits_wall_flag_or_capture_point(spatial, seg, *team),
+            }
+        }
+        PhysRequest::ShortestPath { start, end, team } => PhysAnswer::Path {
+            path: nav
+                .shortest_path(spatial, Vec2::from(*start), Vec2::from(*end), *team)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| (p.x, p.y))
+                .collect(),
+        },
+        PhysRequest::Batch { queries } => PhysAnswer::Batch {
+            results: queries
+                .iter()
+                .map(|q| answer_request(spatial, nav, q))
+                .collect(),
+        },
+    }
 }
-pub fn get_physics_tx() -> Option<Sender<PhysicsQuery>> {
-    PHYSICS_TX.read().unwrap().clone()
+
+pub struct PhysicsQuery {
+    pub request: PhysRequest,
+    pub reply: Sender<PhysAnswer>, // one-shot back to caller
 }
 
+#[derive(Resource)]
+pub struct PhysicsRx(pub Receiver<PhysicsQuery>);
+
+/// The sending half of this world's physics channel.
+#[derive(Resource, Clone)]
+pub struct PhysicsTx(pub Sender<PhysicsQuery>);
+
 // --- Bevy plugin to init the in-proc channel and system ---
 pub struct PythonPhysicsBridgePlugin;
 impl Plugin for PythonPhysicsBridgePlugin {
     fn build(&self, app: &mut App) {
         let (tx, rx) = crossbeam_channel::unbounded::<PhysicsQuery>();
-        set_physics_tx(tx);
+        app.insert_resource(PhysicsTx(tx));
         app.insert_resource(PhysicsRx(rx));
         app.add_systems(Update, process_physics_queries);
     }
 }
 
 // System that consumes PhysicsQuery and performs the casts.
-pub fn process_physics_queries(receiver: Res<PhysicsRx>, spatial: SpatialQuery) {
-    for q in receiver.0.try_iter() {
-        match q {
-            PhysicsQuery::SegmentCollision2D {
-                seg,
-                team_id,
-                reply,
-            } => {
-                let collided = segment_hits_wall_flag_or_capture_point(&spatial, seg, team_id);
-                let _ = reply.send(collided);
+pub fn process_physics_queries(
+    receiver: Res<PhysicsRx>,
+    spatial: SpatialQuery,
+    mut nav: ResMut<NavGrids>,
+) {
+    for PhysicsQuery { request, reply } in receiver.0.try_iter() {
+        let _ = reply.send(answer_request(&spatial, &mut nav, &request));
+    }
+}
+
+/// Waits up to `timeout` for a message on `rx`, answering physics queries in
+/// the meantime. Used where a system blocks on a policy that may itself be
+/// waiting on a physics query.
+pub fn recv_serving_physics<T>(
+    rx: &Receiver<T>,
+    timeout: Duration,
+    physics: &PhysicsRx,
+    spatial: &SpatialQuery,
+    nav: &mut NavGrids,
+) -> Result<T, RecvTimeoutError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        crossbeam_channel::select! {
+            recv(rx) -> msg => return msg.map_err(|_| RecvTimeoutError::Disconnected),
+            recv(physics.0) -> query => {
+                if let Ok(PhysicsQuery { request, reply }) = query {
+                    let _ = reply.send(answer_request(spatial, nav, &request));
+                }
+            }
+            default(deadline.saturating_duration_since(Instant::now())) => {
+                return Err(RecvTimeoutError::Timeout);
             }
         }
     }
 }
 
-// --- Tiny TCP RPC server so other processes can ask for segment_is_free ---
+// --- Tiny TCP RPC server so other processes can run physics queries ---
 
 use serde::{Deserialize, Serialize};
 use std::{
     io::{BufRead, BufReader, Write},
     net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+/// The server that physics queries from this process go to when
+/// `PHYSICS_ADDR` is not set.
 static PHYSICS_RPC_ADDR: Lazy<RwLock<Option<SocketAddr>>> = Lazy::new(|| RwLock::new(None));
 
 pub fn get_physics_rpc_addr() -> Option<SocketAddr> {
     *PHYSICS_RPC_ADDR.read().unwrap()
 }
-fn set_physics_rpc_addr(addr: SocketAddr) {
-    *PHYSICS_RPC_ADDR.write().unwrap() = Some(addr);
-}
 
+/// One request line. Lines without a `kind` are segment queries, as sent by
+/// older clients, and teams are matched ignoring case.
 #[derive(Deserialize)]
 struct PhysReq {
     id: u64,
-    start: [f32; 2],
-    end: [f32; 2],
-    team: String, // "Red" | "Blue"
+    #[serde(flatten)]
+    request: PhysRequest,
 }
+
 #[derive(Serialize)]
 struct PhysResp {
     id: u64,
-    free: bool,
+    #[serde(flatten)]
+    answer: PhysAnswer,
+}
+
+fn parse_request(line: &str) -> serde_json::Result<PhysReq> {
+    let mut value: serde_json::Value = serde_json::from_str(line)?;
+    normalize_request(&mut value);
+    serde_json::from_value(value)
+}
+
+/// Fills in the defaults older clients leave out, here and in batched queries.
+fn normalize_request(value: &mut serde_json::Value) {
+    let Some(obj) = value.as_object_mut() else {
+        return;
+    };
+    obj.entry("kind").or_insert_with(|| "segment".into());
+    if let Some(team) = obj.get_mut("team") {
+        let name = team.as_str().unwrap_or_default();
+        if name.eq_ignore_ascii_case("red") {
+            *team = "Red".into();
+        } else if name.eq_ignore_ascii_case("blue") {
+            *team = "Blue".into();
+        }
+    }
+    if let Some(serde_json::Value::Array(queries)) = obj.get_mut("queries") {
+        queries.iter_mut().for_each(normalize_request);
+    }
+}
+
+/// Serves this world's physics channel over TCP, so policy processes can run
+/// queries. Must be added after [`PythonPhysicsBridgePlugin`].
+pub struct PhysicsRpcServerPlugin {
+    /// Also answer queries made from this process without `PHYSICS_ADDR`.
+    /// Leave off where several worlds share the process.
+    pub process_default: bool,
+}
+
+impl Plugin for PhysicsRpcServerPlugin {
+    fn build(&self, app: &mut App) {
+        let tx = app.world().resource::<PhysicsTx>().0.clone();
+        match start_physics_rpc_server(tx) {
+            Ok(mut server) => {
+                if self.process_default {
+                    *PHYSICS_RPC_ADDR.write().unwrap() = Some(server.addr);
+                    server.process_default = true;
+                }
+                app.insert_resource(server);
+            }
+            Err(e) => warn!("Unable to start physics RPC server: {e}"),
+        }
+    }
+}
+
+/// A running physics RPC server. Stops accepting connections when dropped.
+#[derive(Resource)]
+pub struct PhysicsRpcServer {
+    pub addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    /// Whether it is the process's default server.
+    process_default: bool,
+}
+
+impl Drop for PhysicsRpcServer {
+    fn drop(&mut self) {
+        if self.process_default {
+            let mut default = PHYSICS_RPC_ADDR.write().unwrap();
+            if *default == Some(self.addr) {
+                *default = None;
+            }
+        }
+        self.stop.store(true, Ordering::Relaxed);
+        // wake the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+    }
 }
 
 /// Start the line-based TCP physics RPC server on 127.0.0.1:0.
-/// Returns the bound address.
-pub fn start_physics_rpc_server() -> std::io::Result<SocketAddr> {
-    let physics_tx =
-        get_physics_tx().expect("physics channel must be initialized before starting RPC");
+pub fn start_physics_rpc_server(
+    physics_tx: Sender<PhysicsQuery>,
+) -> std::io::Result<PhysicsRpcServer> {
     let listener = TcpListener::bind(("127.0.0.1", 0))?;
     let addr = listener.local_addr()?;
-    set_physics_rpc_addr(addr);
 
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
     thread::spawn(move || {
         for conn in listener.incoming() {
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
             match conn {
                 Ok(stream) => {
                     let tx = physics_tx.clone();
@@ -108,11 +319,16 @@ pub fn start_physics_rpc_server() -> std::io::Result<SocketAddr> {
         }
     });
 
-    Ok(addr)
+    Ok(PhysicsRpcServer {
+        addr,
+        stop,
+        process_default: false,
+    })
 }
 
 fn handle_conn(s: TcpStream, physics_tx: Sender<PhysicsQuery>) {
-    let _ = s.set_read_timeout(Some(Duration::from_millis(500)));
+    // Clients keep their connection open between queries, so only writes
+    // time out.
     let _ = s.set_write_timeout(Some(Duration::from_millis(500)));
 
     let mut r = BufReader::new(s.try_clone().unwrap());
@@ -129,36 +345,29 @@ fn handle_conn(s: TcpStream, physics_tx: Sender<PhysicsQuery>) {
             break;
         }
 
-        let req: PhysReq = match serde_json::from_str(&line) {
+        let req = match parse_request(&line) {
             Ok(v) => v,
-            Err(_) => continue,
+            Err(e) => {
+                warn!("Bad physics request: {e}");
+                continue;
+            }
         };
 
         let (tx, rx) = crossbeam_channel::bounded(1);
-        let team_id = if req.team.eq_ignore_ascii_case("red") {
-            TeamId::Red
-        } else {
-            TeamId::Blue
-        };
-
-        let _ = physics_tx.send(PhysicsQuery::SegmentCollision2D {
-            seg: Segment2D {
-                start: Vec2::new(req.start[0], req.start[1]),
-                end: Vec2::new(req.end[0], req.end[1]),
-            },
-            team_id,
+        let _ = physics_tx.send(PhysicsQuery {
+            request: req.request,
             reply: tx,
         });
 
-        let collided = rx.recv_timeout(Duration::from_millis(250)).unwrap_or(true);
+        let answer = rx
+            .recv_timeout(Duration::from_millis(250))
+            .unwrap_or_else(|_| PhysAnswer::Error {
+                error: "the simulator did not answer in time".to_string(),
+            });
         let _ = writeln!(
             w,
             "{}",
-            serde_json::to_string(&PhysResp {
-                id: req.id,
-                free: !collided
-            })
-            .unwrap()
+            serde_json::to_string(&PhysResp { id: req.id, answer }).unwrap()
         );
         let _ = w.flush();
     }
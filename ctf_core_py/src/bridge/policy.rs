@@ -1,25 +1,106 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use avian3d::prelude::*;
 use bevy::{math::NormedVectorSpace, prelude::*};
-use crossbeam_channel::{Receiver, Sender, TrySendError};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TrySendError};
 use ctf_core::{
-    agent::{Action, Agent},
+    agent::{Action, Agent, AgentId, AgentIndex},
     character_controller::MovementEvent,
-    flag::{CapturePoint, Flag, FlagCaptureCounts},
+    flag::{CapturePoint, CapturePointId, Flag, FlagCaptureCounts, FlagId},
+    match_state::{MatchEnded, MatchForfeited, MatchSet, MatchState},
+    navigation::NavGrids,
     team::{Team, TeamId},
 };
 
 use crate::{
-    agent::collect_agent_states,
+    bridge::{
+        native::NativePolicyPlugin,
+        physics::{recv_serving_physics, PhysicsRpcServer, PhysicsRx},
+    },
+    comms::{route_messages, Mailbox, MessageSent},
     config::PyConfig,
-    flag::{collect_capture_point_states, collect_flag_states},
-    game::GameState,
-    worker::policy::PolicyBridge,
+    game::{build_game_state, GameState},
+    limits::{PolicyStats, PyOverBudget, PyPolicyLimits},
+    observation::Observer,
+    protocol::ActionBatch,
+    worker::policy::{policy_label, PolicyBridge},
 };
+use serde::{Deserialize, Serialize};
+
+/// How long a lockstep frame waits for a policy before giving up on it.
+const LOCKSTEP_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to wait for room in the harness queue for the final state.
+const FINAL_STATE_TIMEOUT: Duration = Duration::from_secs(1);
 
 #[derive(Resource)]
-struct Bridge {
-    red: PolicyBridge,
-    blue: PolicyBridge,
+pub(super) struct Bridge {
+    red: TeamPolicy,
+    blue: TeamPolicy,
     test: Option<TestHarnessBridge>,
+    /// Wait for both policies to answer each state before continuing.
+    lockstep: bool,
+    /// Tick ID of the last state sent.
+    tick: u64,
+    /// Filters each team's state under fog of war.
+    observer: Option<Observer>,
+}
+
+/// A team's policy processes: one for the whole team, or one per agent.
+struct TeamPolicy {
+    team: TeamId,
+    processes: Vec<PolicyBridge>,
+    limits: PyPolicyLimits,
+    /// Set once the team's policies have gone over their budget.
+    over_budget: bool,
+}
+
+impl TeamPolicy {
+    fn start(
+        team: TeamId,
+        config: &PyConfig,
+        physics_addr: Option<SocketAddr>,
+    ) -> anyhow::Result<Self> {
+        let processes = config
+            .policy_slots(team)
+            .into_iter()
+            .map(|slot| PolicyBridge::start(team, slot, config.clone(), physics_addr))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            team,
+            processes,
+            limits: config.limits(team).clone(),
+            over_budget: false,
+        })
+    }
+
+    /// The stats of all the team's processes together.
+    fn stats(&self) -> PolicyStats {
+        self.processes
+            .iter()
+            .map(|process| process.counters.snapshot(self.over_budget))
+            .reduce(|total, stats| total.merge(&stats))
+            .unwrap_or_default()
+    }
+
+    /// Whether any one of the team's processes is over the budget.
+    fn is_over_budget(&self) -> bool {
+        self.processes.iter().any(|process| {
+            self.limits
+                .is_over_budget(&process.counters.snapshot(false))
+        })
+    }
+
+    /// Whether every one of the team's processes has been given up on.
+    fn gone(&self) -> bool {
+        self.processes.iter().all(|process| process.dead)
+    }
+
+    fn frozen(&self) -> bool {
+        self.over_budget && self.limits.on_over_budget == PyOverBudget::Freeze
+    }
 }
 
 #[derive(Clone)]
@@ -31,6 +112,44 @@ pub struct TestHarnessBridge {
 #[derive(Resource)]
 struct PolicyTimer(Timer);
 
+/// A policy process that stopped without being asked to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyCrash {
+    pub team: TeamId,
+    /// The agent whose process crashed, for per-agent policies.
+    #[serde(default)]
+    pub agent: Option<u32>,
+    /// Match time of the crash, in seconds.
+    pub time: f32,
+    pub reason: String,
+    pub status: Option<String>,
+    pub stderr_tail: Vec<String>,
+    /// Whether the policy was restarted. If not, its agent stopped, or the
+    /// team forfeited once none of its policies was left.
+    pub restarted: bool,
+}
+
+/// Every policy crash so far this match.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct PolicyCrashes(pub Vec<PolicyCrash>);
+
+/// A game state that was just sent to the policies.
+#[derive(Event, Clone)]
+pub struct GameStatePublished(pub GameState);
+
+/// A batch of actions a policy sent back, just before it is applied.
+#[derive(Event, Clone)]
+pub struct ActionsReceived {
+    pub team: TeamId,
+    /// The state the actions answer.
+    pub tick: u64,
+    pub actions: Vec<Action>,
+}
+
+/// Publishes game states to the policies and applies their actions.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PolicySet;
+
 pub struct PythonPolicyBridgePlugin {
     pub config: PyConfig,
     pub test_harness: Option<TestHarnessBridge>,
@@ -38,15 +157,32 @@ pub struct PythonPolicyBridgePlugin {
 
 impl Plugin for PythonPolicyBridgePlugin {
     fn build(&self, app: &mut App) {
-        let hz = self.config.rate_hz.unwrap_or(60.0).clamp(1.0, 240.0);
+        let hz = self.config.effective_rate_hz();
         let interval = 1.0_f32 / hz;
 
-        let red_bridge =
-            PolicyBridge::start(TeamId::Red, self.config.clone(), &self.config.python_exe)
-                .expect("Failed to start red policy");
-        let blue_bridge =
-            PolicyBridge::start(TeamId::Blue, self.config.clone(), &self.config.python_exe)
-                .expect("Failed to start red policy");
+        let physics_addr = app
+            .world()
+            .get_resource::<PhysicsRpcServer>()
+            .map(|server| server.addr);
+
+        if self.config.per_agent_policies {
+            for (team, positions) in [
+                (TeamId::Red, &self.config.red_team_agent_positions),
+                (TeamId::Blue, &self.config.blue_team_agent_positions),
+            ] {
+                let ids = self.config.agent_ids(team).len();
+                if self.config.team_policy(team).is_none() && ids != positions.len() {
+                    warn!(
+                        "{team:?} has {ids} agent IDs for {} agents; only agents with an ID get a policy",
+                        positions.len()
+                    );
+                }
+            }
+        }
+        let red_bridge = TeamPolicy::start(TeamId::Red, &self.config, physics_addr)
+            .expect("Failed to start red policy");
+        let blue_bridge = TeamPolicy::start(TeamId::Blue, &self.config, physics_addr)
+            .expect("Failed to start blue policy");
 
         app.insert_resource(PolicyTimer(Timer::from_seconds(
             interval,
@@ -57,13 +193,41 @@ impl Plugin for PythonPolicyBridgePlugin {
             red: red_bridge,
             blue: blue_bridge,
             test: self.test_harness.clone(),
+            lockstep: self.config.lockstep(),
+            tick: 0,
+            observer: self.config.fog_of_war.clone().map(Observer::new),
         });
+        app.init_resource::<PolicyCrashes>();
+
+        if let Some(comms) = &self.config.comms {
+            app.insert_resource(Mailbox::new(comms.clone()));
+        }
+
+        app.add_event::<GameStatePublished>();
+        app.add_event::<ActionsReceived>();
+        app.add_event::<MessageSent>();
 
         app.add_systems(
             Update,
-            (send_game_states, apply_actions, on_test_harness_stop),
+            (
+                (
+                    supervise_policies,
+                    send_game_states,
+                    apply_actions,
+                    route_messages,
+                    enforce_budgets,
+                )
+                    .chain()
+                    .in_set(PolicySet)
+                    .after(MatchSet),
+                on_test_harness_stop,
+            ),
         );
 
+        app.add_plugins(NativePolicyPlugin {
+            config: self.config.clone(),
+        });
+
         app.add_systems(Last, shutdown_workers_on_exit);
     }
 }
@@ -83,57 +247,101 @@ fn on_test_harness_stop(bridge: Option<Res<Bridge>>, mut exit: EventWriter<AppEx
 
 #[allow(clippy::too_many_arguments)]
 fn send_game_states(
-    time: Res<Time>,
+    time: Res<Time<Virtual>>,
     mut t: ResMut<PolicyTimer>,
     scores: Res<FlagCaptureCounts>,
-    bridge: Option<Res<Bridge>>,
-    agents: Query<(Entity, &Name, &Transform, &Agent, &Team)>,
-    flags: Query<(Entity, &Name, &Transform, &Flag)>,
-    capture_points: Query<(Entity, &Name, &Transform, &CapturePoint)>,
+    match_state: Res<MatchState>,
+    mut match_ended: EventReader<MatchEnded>,
+    bridge: Option<ResMut<Bridge>>,
+    agents: Query<(&AgentId, &Name, &Transform, &Agent, &Team)>,
+    flags: Query<(&FlagId, &Name, &Transform, &Flag)>,
+    capture_points: Query<(&CapturePointId, &Name, &Transform, &CapturePoint)>,
+    spatial: SpatialQuery,
+    mut mailbox: Option<ResMut<Mailbox>>,
+    mut published: EventWriter<GameStatePublished>,
     mut exit: EventWriter<AppExit>,
 ) {
-    let Some(bridge) = bridge else {
+    let Some(mut bridge) = bridge else {
         return;
     };
 
-    if !t.0.tick(time.delta()).just_finished() {
+    // The final state is always published so that consumers see the outcome.
+    let is_final = match_ended.read().next().is_some();
+    if !t.0.tick(time.delta()).just_finished() && !is_final {
         return;
     }
 
-    let (red_team, blue_team) = collect_agent_states(agents);
-    let (red_flags, blue_flags) = collect_flag_states(flags);
-    let (red_capture_points, blue_capture_points) = collect_capture_point_states(capture_points);
-    let num_flags_per_team = red_flags.len() as u32;
-
-    let game_state = GameState {
-        red_score: scores.red,
-        blue_score: scores.blue,
-        red_team,
-        blue_team,
-        red_flags,
-        blue_flags,
-        num_flags_per_team,
-        red_capture_points,
-        blue_capture_points,
-    };
+    let mut game_state = build_game_state(&scores, &match_state, agents, flags, capture_points);
+    bridge.tick += 1;
+    game_state.tick = bridge.tick;
+    game_state.red_policy_stats = Some(bridge.red.stats());
+    game_state.blue_policy_stats = Some(bridge.blue.stats());
+    published.write(GameStatePublished(game_state.clone()));
+    let bridge = &mut *bridge;
 
-    for bridge in [&bridge.red, &bridge.blue] {
-        match bridge
-            .tx_state
-            .as_ref()
-            .unwrap()
-            .try_send(game_state.clone())
-        {
-            Ok(_) => {}
-            Err(TrySendError::Full(_)) => { /* worker still busy; skip this one */ }
-            Err(TrySendError::Disconnected(_)) => {
-                warn!("Unable to transmit game state to a policy.");
-                exit.write(AppExit::Success);
+    for policy in [&mut bridge.red, &mut bridge.blue] {
+        let team = policy.team;
+        // built-in policies get their states from NativePolicyPlugin
+        if policy.processes.is_empty() {
+            continue;
+        }
+        let mut team_state = game_state.clone();
+        if let Some(mailbox) = &mailbox {
+            mailbox.deliver(&mut team_state, team);
+        }
+
+        // a disconnected policy has crashed; supervise_policies deals with it
+        for process in policy.processes.iter_mut() {
+            if process.dead {
+                // nobody is left to read its messages
+                if let Some(mailbox) = &mut mailbox {
+                    let agent = process.agent;
+                    mailbox.forget(team, |id| agent.is_none_or(|agent| agent == id));
+                }
+                continue;
             }
+            let mut observation = match &mut bridge.observer {
+                Some(observer) => observer.observe(&team_state, team, process.agent, &spatial),
+                None => team_state.clone(),
+            };
+            if let Some(agent) = process.agent {
+                keep_own_inbox(&mut observation, team, agent);
+            }
+            process.last_state = Some(observation.clone());
+            let tx_state = process.tx_state.as_ref().unwrap();
+            // never wait on a busy worker, even in lockstep
+            process.awaiting = match tx_state.try_send(observation) {
+                Ok(()) => {
+                    // messages not sent stay in the mailbox for the next state
+                    if let Some(mailbox) = &mut mailbox {
+                        let agent = process.agent;
+                        mailbox.delivered(team, team_state.elapsed, |id| {
+                            agent.is_none_or(|agent| agent == id)
+                        });
+                    }
+                    bridge.lockstep
+                }
+                Err(TrySendError::Full(_)) => {
+                    process.record_busy();
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            };
         }
     }
 
     if let Some(test) = &bridge.test {
+        if is_final {
+            if test
+                .tx_state
+                .send_timeout(game_state, FINAL_STATE_TIMEOUT)
+                .is_err()
+            {
+                warn!("Unable to transmit final game state to testing harness.");
+            }
+            return;
+        }
+
         match test.tx_state.try_send(game_state) {
             Ok(_) => {}
             Err(TrySendError::Full(_)) => {}
@@ -145,49 +353,280 @@ fn send_game_states(
     }
 }
 
-fn apply_actions(
-    bridge: Option<Res<Bridge>>,
-    agents: Query<(Entity, &Agent)>,
+/// Empties the inboxes of every teammate but `agent`, so that a per-agent
+/// policy only reads its own messages.
+fn keep_own_inbox(state: &mut GameState, team: TeamId, agent: u32) {
+    let own = match team {
+        TeamId::Red => &mut state.red_team,
+        TeamId::Blue => &mut state.blue_team,
+    };
+    for teammate in own.iter_mut().filter(|a| a.id != agent) {
+        teammate.messages.clear();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn apply_actions(
+    bridge: Option<ResMut<Bridge>>,
+    physics: Option<Res<PhysicsRx>>,
+    spatial: SpatialQuery,
+    mut nav: ResMut<NavGrids>,
+    index: Res<AgentIndex>,
+    agents: Query<&Agent>,
+    teams: Query<&Team>,
     mut movement_event_writer: EventWriter<MovementEvent>,
+    mut received: EventWriter<ActionsReceived>,
 ) {
-    let Some(bridge) = bridge else {
+    let Some(mut bridge) = bridge else {
         return;
     };
+    let bridge = &mut *bridge;
+    let tick = bridge.tick;
+    // every process waits against the same deadline, not one after another
+    let start = Instant::now();
 
-    for bridge in [&bridge.red, &bridge.blue] {
-        let mut latest: Option<Vec<Action>> = None;
-        while let Ok(a) = bridge.rx_action.try_recv() {
-            latest = Some(a);
+    for policy in [&mut bridge.red, &mut bridge.blue] {
+        let team = policy.team;
+        let frozen = policy.frozen();
+        let timeout = policy
+            .limits
+            .tick_deadline_ms
+            .map_or(LOCKSTEP_TIMEOUT, |ms| {
+                Duration::from_millis(ms).min(LOCKSTEP_TIMEOUT)
+            });
+        let deadline = start + timeout;
+        let mut merged: Option<ActionBatch> = None;
+        for process in policy.processes.iter_mut().filter(|p| !p.dead) {
+            let mut latest: Option<ActionBatch> = None;
+            if std::mem::take(&mut process.awaiting) && !frozen {
+                loop {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    // policies may query physics while planning, so keep answering
+                    let answer = match &physics {
+                        Some(physics) => recv_serving_physics(
+                            &process.rx_action,
+                            remaining,
+                            physics,
+                            &spatial,
+                            &mut nav,
+                        ),
+                        None => process.rx_action.recv_timeout(remaining),
+                    };
+                    match answer {
+                        // a late answer to an earlier state
+                        Ok(batch) if batch.tick < tick => continue,
+                        Ok(batch) => latest = Some(batch),
+                        Err(RecvTimeoutError::Timeout) => {
+                            let label = policy_label(team, process.agent);
+                            warn!("{label} policy did not answer tick {tick} within {timeout:?}");
+                            process.give_up(tick);
+                        }
+                        // crashed; supervise_policies deals with it
+                        Err(RecvTimeoutError::Disconnected) => {}
+                    }
+                    break;
+                }
+            } else {
+                while let Ok(a) = process.rx_action.try_recv() {
+                    latest = Some(a);
+                }
+            }
+            let Some(mut batch) = latest else {
+                continue;
+            };
+
+            retain_owned(&mut batch.actions, team, process.agent, &index, &teams);
+            match &mut merged {
+                Some(merged) => {
+                    merged.tick = merged.tick.max(batch.tick);
+                    merged.actions.extend(batch.actions);
+                }
+                None => merged = Some(batch),
+            }
         }
-        let Some(actions) = latest else {
+        let Some(ActionBatch { tick, actions }) = merged else {
             continue;
         };
+        if frozen {
+            continue;
+        }
 
-        for act in actions {
-            match act {
-                Action::Move {
-                    id: agent_id,
-                    velocity,
-                } => {
-                    let agent = agents.iter().find(|(e, _a)| e.index() == agent_id);
-                    if agent.is_none() {
-                        warn!("No agent with id {agent_id}");
-                        continue;
-                    }
-                    let (_, agent) = agent.unwrap();
-                    let velocity = if velocity.norm() > agent.speed {
-                        warn!(
-                            "Agent {agent_id} trying to move too fast: {} > {}. Capping speed.",
-                            velocity.norm(),
-                            agent.speed
+        received.write(ActionsReceived {
+            team,
+            tick,
+            actions: actions.clone(),
+        });
+        write_actions(actions, &index, &agents, &mut movement_event_writer);
+    }
+}
+
+/// Drops the actions for agents a policy does not control: any agent but
+/// `agent` if set, else any agent of the other team.
+pub(super) fn retain_owned(
+    actions: &mut Vec<Action>,
+    team: TeamId,
+    agent: Option<u32>,
+    index: &AgentIndex,
+    teams: &Query<&Team>,
+) {
+    actions.retain(|action| {
+        let Action::Move { id, .. } = action;
+        let owned = match agent {
+            Some(agent) => *id == agent,
+            None => index
+                .get(*id)
+                .and_then(|e| teams.get(e).ok())
+                .is_none_or(|t| t.0 == team),
+        };
+        if !owned {
+            warn!("Rejected {team:?} action for agent {id}, which the policy does not control");
+        }
+        owned
+    });
+}
+
+/// Restarts crashed policies while they have restarts left. A per-agent
+/// policy beyond saving stops its agent; the team forfeits once none of its
+/// policies is left.
+fn supervise_policies(
+    bridge: Option<ResMut<Bridge>>,
+    match_state: Res<MatchState>,
+    mut crashes: ResMut<PolicyCrashes>,
+    mut movement_event_writer: EventWriter<MovementEvent>,
+    mut forfeited: EventWriter<MatchForfeited>,
+) {
+    let Some(mut bridge) = bridge else {
+        return;
+    };
+    let bridge = &mut *bridge;
+
+    for policy in [&mut bridge.red, &mut bridge.blue] {
+        let team = policy.team;
+        let mut lost = false;
+        for process in policy.processes.iter_mut().filter(|p| !p.dead) {
+            let Ok(exit) = process.rx_exit.try_recv() else {
+                continue;
+            };
+            let label = policy_label(team, process.agent);
+
+            process.counters.record_crash();
+            error!(
+                "{label} policy crashed: {} (status: {})",
+                exit.reason,
+                exit.status.as_deref().unwrap_or("unknown")
+            );
+            for line in &exit.stderr_tail {
+                error!("  {line}");
+            }
+
+            let mut restarted = false;
+            if match_state.outcome.is_none() && process.restarts < policy.limits.max_restarts {
+                match process.restart() {
+                    Ok(()) => {
+                        info!(
+                            "Restarted {label} policy ({}/{})",
+                            process.restarts, policy.limits.max_restarts
                         );
-                        velocity.normalize() * agent.speed
-                    } else {
-                        velocity
-                    };
-                    movement_event_writer.write(MovementEvent::TranslateById(agent_id, velocity));
+                        if let (Some(tx_state), Some(state)) =
+                            (&process.tx_state, &process.last_state)
+                        {
+                            let _ = tx_state.try_send(state.clone());
+                        }
+                        restarted = true;
+                    }
+                    Err(e) => error!("Unable to restart {label} policy: {e}"),
+                }
+            }
+            if !restarted {
+                process.dead = true;
+                lost = true;
+                if let Some(agent) = process.agent {
+                    movement_event_writer.write(MovementEvent::TranslateById(agent, Vec2::ZERO));
                 }
             }
+
+            crashes.0.push(PolicyCrash {
+                team,
+                agent: process.agent,
+                time: match_state.elapsed,
+                reason: exit.reason,
+                status: exit.status,
+                stderr_tail: exit.stderr_tail,
+                restarted,
+            });
+        }
+        if lost && policy.gone() && match_state.outcome.is_none() {
+            forfeited.write(MatchForfeited { team });
+        }
+    }
+}
+
+/// Applies each team's `on_over_budget` the first time its policies go over
+/// their budget.
+fn enforce_budgets(
+    bridge: Option<ResMut<Bridge>>,
+    agents: Query<(&AgentId, &Team)>,
+    mut movement_event_writer: EventWriter<MovementEvent>,
+    mut forfeited: EventWriter<MatchForfeited>,
+) {
+    let Some(mut bridge) = bridge else {
+        return;
+    };
+    let bridge = &mut *bridge;
+
+    for policy in [&mut bridge.red, &mut bridge.blue] {
+        let team = policy.team;
+        if policy.over_budget || !policy.is_over_budget() {
+            continue;
+        }
+        policy.over_budget = true;
+        warn!("{team:?} policy went over its budget");
+
+        match policy.limits.on_over_budget {
+            PyOverBudget::Ignore => {}
+            PyOverBudget::Freeze => {
+                for (id, _) in agents.iter().filter(|(_, t)| t.0 == team) {
+                    movement_event_writer.write(MovementEvent::TranslateById(id.0, Vec2::ZERO));
+                }
+            }
+            PyOverBudget::Forfeit => {
+                forfeited.write(MatchForfeited { team });
+            }
+        }
+    }
+}
+
+/// Turns a batch of actions into movement events, capping each agent's
+/// velocity at its current max speed.
+pub fn write_actions(
+    actions: Vec<Action>,
+    index: &AgentIndex,
+    agents: &Query<&Agent>,
+    movement_event_writer: &mut EventWriter<MovementEvent>,
+) {
+    for act in actions {
+        match act {
+            Action::Move {
+                id: agent_id,
+                velocity,
+                ..
+            } => {
+                let Some(agent) = index.get(agent_id).and_then(|e| agents.get(e).ok()) else {
+                    warn!("No agent with id {agent_id}");
+                    continue;
+                };
+                let velocity = if velocity.norm() > agent.speed {
+                    warn!(
+                        "Agent {agent_id} trying to move too fast: {} > {}. Capping speed.",
+                        velocity.norm(),
+                        agent.speed
+                    );
+                    velocity.normalize() * agent.speed
+                } else {
+                    velocity
+                };
+                movement_event_writer.write(MovementEvent::TranslateById(agent_id, velocity));
+            }
         }
     }
 }
@@ -0,0 +1,270 @@
+use bevy::prelude::*;
+use ctf_core::{
+    agent::{Action, AgentId},
+    match_state::MatchState,
+    policy::TeamMessage,
+    team::{Team, TeamId},
+};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+use serde::{Deserialize, Serialize};
+
+use crate::{bridge::policy::ActionsReceived, game::GameState};
+
+/// Seconds of match time a message waits past its delivery time for a state
+/// to carry it before it is dropped.
+const MESSAGE_TTL: f32 = 5.0;
+
+/// Lets agents broadcast short messages to their teammates through
+/// `Action.message`.
+#[gen_stub_pyclass]
+#[pyclass(name = "Comms")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PyComms {
+    /// Longest message allowed, in bytes. Longer ones are dropped.
+    #[pyo3(get, set)]
+    pub max_message_bytes: usize,
+
+    /// Messages only reach teammates within this distance of the sender
+    /// when it was sent. Unlimited if unset.
+    #[pyo3(get, set)]
+    pub range: Option<f32>,
+
+    /// Seconds of match time before a message is delivered. Messages are
+    /// delivered with the next state at the earliest, and dropped if none
+    /// carries them within 5 seconds after that.
+    #[pyo3(get, set)]
+    pub delay: f32,
+}
+
+impl Default for PyComms {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: 64,
+            range: None,
+            delay: 0.0,
+        }
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyComms {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// A message from a teammate.
+#[gen_stub_pyclass]
+#[pyclass(name = "Message", frozen)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PyMessage {
+    /// ID of the agent that sent it.
+    #[pyo3(get)]
+    pub sender: u32,
+
+    #[pyo3(get)]
+    pub content: String,
+
+    /// Match time it was sent at, in seconds.
+    #[pyo3(get)]
+    pub sent_at: f32,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyMessage {
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<&PyMessage> for TeamMessage {
+    fn from(message: &PyMessage) -> Self {
+        TeamMessage {
+            sender: message.sender,
+            content: message.content.clone(),
+            sent_at: message.sent_at,
+        }
+    }
+}
+
+/// A message on its way, or dropped, with the teammates it will reach.
+#[derive(Event, Debug, Clone, Serialize, Deserialize)]
+pub struct MessageSent {
+    pub team: TeamId,
+    pub message: PyMessage,
+    pub recipients: Vec<u32>,
+    /// Why the message was dropped, if it was.
+    pub dropped: Option<String>,
+}
+
+struct InFlight {
+    team: TeamId,
+    message: PyMessage,
+    recipients: Vec<u32>,
+    deliver_at: f32,
+}
+
+/// Messages waiting to be delivered.
+#[derive(Resource)]
+pub struct Mailbox {
+    comms: PyComms,
+    in_flight: Vec<InFlight>,
+}
+
+impl Mailbox {
+    pub fn new(comms: PyComms) -> Self {
+        Self {
+            comms,
+            in_flight: Vec::new(),
+        }
+    }
+
+    /// Copies the messages due by `state.elapsed` into the inboxes of
+    /// `team`'s agents in `state`. They stay in the mailbox until
+    /// [`Mailbox::delivered`] is called for their recipients.
+    pub fn deliver(&self, state: &mut GameState, team: TeamId) {
+        let agents = match team {
+            TeamId::Red => &mut state.red_team,
+            TeamId::Blue => &mut state.blue_team,
+        };
+        let now = state.elapsed;
+        for flight in self.due(team, now) {
+            for agent in agents.iter_mut() {
+                if flight.recipients.contains(&agent.id) {
+                    agent.messages.push(flight.message.clone());
+                }
+            }
+        }
+    }
+
+    /// Forgets the messages due by `now` for the recipients picked by
+    /// `reached`, once a state carrying them has been sent.
+    pub fn delivered(&mut self, team: TeamId, now: f32, reached: impl Fn(u32) -> bool) {
+        for flight in &mut self.in_flight {
+            if flight.team == team && flight.deliver_at <= now {
+                flight.recipients.retain(|id| !reached(*id));
+            }
+        }
+        self.in_flight
+            .retain(|flight| !flight.recipients.is_empty());
+    }
+
+    /// Forgets all of `team`'s messages for the recipients picked by `gone`,
+    /// whose policy has stopped for good.
+    pub fn forget(&mut self, team: TeamId, gone: impl Fn(u32) -> bool) {
+        for flight in &mut self.in_flight {
+            if flight.team == team {
+                flight.recipients.retain(|id| !gone(*id));
+            }
+        }
+        self.in_flight
+            .retain(|flight| !flight.recipients.is_empty());
+    }
+
+    /// Drops the messages still undelivered [`MESSAGE_TTL`] after they were
+    /// due, such as those for a policy that is always busy.
+    fn expire(&mut self, now: f32) {
+        self.in_flight
+            .retain(|flight| now <= flight.deliver_at + MESSAGE_TTL);
+    }
+
+    fn due(&self, team: TeamId, now: f32) -> impl Iterator<Item = &InFlight> {
+        self.in_flight
+            .iter()
+            .filter(move |flight| flight.team == team && flight.deliver_at <= now)
+    }
+}
+
+/// Picks up the messages in the actions applied this frame and decides who
+/// they reach.
+pub fn route_messages(
+    mailbox: Option<ResMut<Mailbox>>,
+    mut received: EventReader<ActionsReceived>,
+    agents: Query<(&AgentId, &Transform, &Team)>,
+    match_state: Res<MatchState>,
+    mut sent: EventWriter<MessageSent>,
+) {
+    let Some(mut mailbox) = mailbox else {
+        received.clear();
+        return;
+    };
+    mailbox.expire(match_state.elapsed);
+
+    for ActionsReceived { team, actions, .. } in received.read() {
+        for action in actions {
+            let Action::Move {
+                id,
+                message: Some(content),
+                ..
+            } = action
+            else {
+                continue;
+            };
+            let message = PyMessage {
+                sender: *id,
+                content: content.clone(),
+                sent_at: match_state.elapsed,
+            };
+
+            let sender = agents
+                .iter()
+                .find(|(agent_id, _, agent_team)| agent_id.0 == *id && agent_team.0 == *team);
+            let dropped = if sender.is_none() {
+                Some(format!("no agent {id} on the team"))
+            } else if content.len() > mailbox.comms.max_message_bytes {
+                Some(format!(
+                    "{} bytes is over the limit of {}",
+                    content.len(),
+                    mailbox.comms.max_message_bytes
+                ))
+            } else {
+                None
+            };
+
+            let mut recipients = Vec::new();
+            if let (Some((_, from, _)), None) = (sender, &dropped) {
+                let from = from.translation.xz();
+                recipients = agents
+                    .iter()
+                    .filter(|(agent_id, to, agent_team)| {
+                        agent_team.0 == *team
+                            && agent_id.0 != *id
+                            && mailbox
+                                .comms
+                                .range
+                                .is_none_or(|range| from.distance(to.translation.xz()) <= range)
+                    })
+                    .map(|(agent_id, _, _)| agent_id.0)
+                    .collect();
+                recipients.sort_unstable();
+
+                let deliver_at = match_state.elapsed + mailbox.comms.delay;
+                mailbox.in_flight.push(InFlight {
+                    team: *team,
+                    message: message.clone(),
+                    recipients: recipients.clone(),
+                    deliver_at,
+                });
+            }
+
+            if let Some(reason) = &dropped {
+                warn!("Dropped message from agent {id}: {reason}");
+            }
+            sent.write(MessageSent {
+                team: *team,
+                message,
+                recipients,
+                dropped,
+            });
+        }
+    }
+}
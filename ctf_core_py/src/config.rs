@@ -1,9 +1,25 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
+use ctf_core::core::CTFPlugin;
+use ctf_core::map::{ArenaMap, MapError};
+use ctf_core::rules::GameRules;
+use ctf_core::team::TeamId;
 use derivative::Derivative;
 use pyo3::prelude::*;
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
 use serde::{Deserialize, Serialize};
 
+use crate::comms::PyComms;
+use crate::human::HUMAN_POLICY;
+use crate::launch::PyLaunchSpec;
+use crate::limits::PyPolicyLimits;
+use crate::observation::PyFogOfWar;
+use crate::policy::builtin;
+use crate::replay::RecordingPlugin;
+use crate::rules::PyGameRules;
+use crate::wire::PyWireFormat;
+
 #[gen_stub_pyclass]
 #[pyclass(name = "Config")]
 #[derive(Debug, Clone, Derivative, Serialize, Deserialize)]
@@ -42,6 +58,262 @@ pub struct PyConfig {
 
     #[pyo3(get, set)]
     pub rate_hz: Option<f32>,
+
+    /// Path to a JSON map file. Ignored if `map` is set.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub map_path: Option<String>,
+
+    /// An inline JSON map. Takes precedence over `map_path`.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub map: Option<String>,
+
+    /// Gameplay constants such as speeds, radii and cooldowns.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub rules: PyGameRules,
+
+    /// Seed for all in-game randomness. Random if unset.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Advance the simulation by exactly `1 / rate_hz` seconds per frame and
+    /// wait for both policies to answer every state, so that runs with the
+    /// same seed are reproducible.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub fixed_timestep: bool,
+
+    /// Write a gzipped replay of the match to this path. Play it back with
+    /// `replay`.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub record_path: Option<String>,
+
+    /// In `run_headless`, run frames back to back instead of in real time.
+    /// Like `fixed_timestep`, each frame advances `1 / rate_hz` seconds and
+    /// waits for both policies.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub fast_forward: bool,
+
+    /// With `fast_forward`, cap the speed at this multiple of real time.
+    /// Unlimited if unset.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub time_scale: Option<f32>,
+
+    /// How to start the red policy. Defaults to
+    /// `python_exe run launch-team {config} --side {side}`.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub red_team_launch: Option<PyLaunchSpec>,
+
+    /// Like `red_team_launch`, for the blue policy.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub blue_team_launch: Option<PyLaunchSpec>,
+
+    /// A built-in policy to play red inside the simulator instead of
+    /// launching a process: `builtin:defense_bot`, `builtin:attacker_bot`,
+    /// `builtin:random_bot`, `builtin:mixed_bot` or `builtin:interceptor_bot`.
+    /// `human` lets a player drive red's agents in the window opened by `run`.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub red_team_policy: Option<String>,
+
+    /// Like `red_team_policy`, for blue.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub blue_team_policy: Option<String>,
+
+    /// Resource limits and response budget for the red policy.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub red_team_limits: PyPolicyLimits,
+
+    /// Resource limits and response budget for the blue policy.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub blue_team_limits: PyPolicyLimits,
+
+    /// How states and actions are encoded on the policy pipes. Policies may
+    /// decline a binary format and stay on JSON.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub wire_format: PyWireFormat,
+
+    /// Limits what each policy observes to what its agents can see. Both
+    /// policies see everything if unset.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub fog_of_war: Option<PyFogOfWar>,
+
+    /// Lets agents broadcast messages to their teammates. Messages in
+    /// actions are ignored if unset.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub comms: Option<PyComms>,
+
+    /// Launch one policy process per agent instead of one per team. Each
+    /// process observes the game on its own and may only act for its agent.
+    /// An agent whose process is given up on after crashing stops.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub per_agent_policies: bool,
+}
+
+impl PyConfig {
+    /// The policy tick rate, clamped to a sane range.
+    pub fn effective_rate_hz(&self) -> f32 {
+        self.rate_hz.unwrap_or(60.0).clamp(1.0, 240.0)
+    }
+
+    /// How to start `side`'s policy.
+    pub fn launch_spec(&self, side: TeamId) -> PyLaunchSpec {
+        let launch = match side {
+            TeamId::Red => &self.red_team_launch,
+            TeamId::Blue => &self.blue_team_launch,
+        };
+        launch
+            .clone()
+            .unwrap_or_else(|| PyLaunchSpec::launch_team(&self.python_exe))
+    }
+
+    pub fn limits(&self, side: TeamId) -> &PyPolicyLimits {
+        match side {
+            TeamId::Red => &self.red_team_limits,
+            TeamId::Blue => &self.blue_team_limits,
+        }
+    }
+
+    pub fn agent_ids(&self, side: TeamId) -> &[String] {
+        match side {
+            TeamId::Red => &self.red_team_agent_ids,
+            TeamId::Blue => &self.blue_team_agent_ids,
+        }
+    }
+
+    /// The built-in policy playing `side`, or `human`, if any.
+    pub fn team_policy(&self, side: TeamId) -> Option<&str> {
+        match side {
+            TeamId::Red => self.red_team_policy.as_deref(),
+            TeamId::Blue => self.blue_team_policy.as_deref(),
+        }
+    }
+
+    /// The team a player drives, if any. Errors if both are set to `human`.
+    pub fn human_team(&self) -> PyResult<Option<TeamId>> {
+        let human = |side| self.team_policy(side) == Some(HUMAN_POLICY);
+        match (human(TeamId::Red), human(TeamId::Blue)) {
+            (true, true) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Only one team can be played by a human",
+            )),
+            (true, false) => Ok(Some(TeamId::Red)),
+            (false, true) => Ok(Some(TeamId::Blue)),
+            (false, false) => Ok(None),
+        }
+    }
+
+    /// Errors if a team's policy is neither `human` nor a known built-in.
+    pub fn check_team_policies(&self) -> PyResult<()> {
+        for side in [TeamId::Red, TeamId::Blue] {
+            if let Some(name) = self.team_policy(side).filter(|name| *name != HUMAN_POLICY) {
+                builtin(name, side, self.seed).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid {side:?} team policy: {e}"
+                    ))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The plugin recording the match to `record_path`, if set. Creates the
+    /// file, so errors if it can't be written.
+    pub fn recording(&self) -> PyResult<Option<RecordingPlugin>> {
+        self.record_path
+            .as_deref()
+            .map(|path| RecordingPlugin::create(path, self.clone()))
+            .transpose()
+    }
+
+    /// The slot of the agent each of `side`'s policy processes controls, or
+    /// a single `None` when one process controls the whole team. Empty when
+    /// a built-in policy plays `side`.
+    pub fn policy_slots(&self, side: TeamId) -> Vec<Option<usize>> {
+        if self.team_policy(side).is_some() {
+            Vec::new()
+        } else if self.per_agent_policies {
+            (0..self.agent_ids(side).len()).map(Some).collect()
+        } else {
+            vec![None]
+        }
+    }
+
+    /// Whether every frame advances by a fixed step and waits for the policies.
+    pub fn lockstep(&self) -> bool {
+        self.fixed_timestep || self.fast_forward
+    }
+
+    /// Wall-clock time a headless frame should take at least.
+    pub fn headless_frame_time(&self) -> PyResult<Duration> {
+        let frame_dt = Duration::from_secs_f64(1.0 / self.effective_rate_hz() as f64);
+        if !self.fast_forward {
+            return Ok(frame_dt);
+        }
+        match self.time_scale {
+            None => Ok(Duration::ZERO),
+            Some(scale) if scale.is_finite() && scale > 0.0 => Ok(frame_dt.div_f32(scale)),
+            Some(scale) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "time_scale must be positive, got {scale}"
+            ))),
+        }
+    }
+
+    /// Loads the configured map, falling back to the default arena.
+    pub fn load_map(&self) -> PyResult<ArenaMap> {
+        self.try_load_map()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    /// Like [`Self::load_map`], for callers outside Python.
+    pub fn try_load_map(&self) -> Result<ArenaMap, MapError> {
+        match (&self.map, &self.map_path) {
+            (Some(json), _) => ArenaMap::from_json(json),
+            (None, Some(path)) => ArenaMap::load(path),
+            (None, None) => Ok(ArenaMap::default()),
+        }
+    }
+
+    /// The plugin playing the configured map under the configured rules.
+    /// Errors if either is invalid.
+    pub fn ctf_plugin(&self, headless: bool) -> PyResult<CTFPlugin> {
+        GameRules::from(&self.rules)
+            .validate()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        Ok(self.ctf_plugin_with_map(self.load_map()?, headless))
+    }
+
+    /// Like [`Self::ctf_plugin`], but with an already loaded map.
+    pub fn ctf_plugin_with_map(&self, map: ArenaMap, headless: bool) -> CTFPlugin {
+        CTFPlugin {
+            red_team_agent_positions: self.red_team_agent_positions.clone(),
+            blue_team_agent_positions: self.blue_team_agent_positions.clone(),
+            red_team_flag_positions: self.red_team_flag_positions.clone(),
+            blue_team_flag_positions: self.blue_team_flag_positions.clone(),
+            red_team_capture_point_positions: self.red_team_capture_point_positions.clone(),
+            blue_team_capture_point_positions: self.blue_team_capture_point_positions.clone(),
+            map,
+            rules: (&self.rules).into(),
+            seed: self.seed,
+            fixed_timestep: self.lockstep().then(|| 1.0 / self.effective_rate_hz()),
+            headless,
+            replay: false,
+        }
+    }
 }
 
 #[gen_stub_pymethods]
@@ -59,6 +331,17 @@ impl PyConfig {
         })
     }
 
+    /// The walls of the configured map, each a list of points joined by
+    /// wall segments.
+    fn walls(&self) -> PyResult<Vec<Vec<(f32, f32)>>> {
+        Ok(self
+            .load_map()?
+            .walls
+            .into_iter()
+            .map(|w| w.points)
+            .collect())
+    }
+
     fn __str__(&self) -> PyResult<String> {
         serde_json::to_string_pretty(self).map_err(|e| {
             PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
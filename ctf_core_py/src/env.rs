@@ -0,0 +1,210 @@
+use avian3d::prelude::*;
+use bevy::app::PluginsState;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use ctf_core::{
+    agent::{Action, Agent, AgentIndex},
+    character_controller::{MovementEvent, MovementSet},
+    navigation::NavGrids,
+    segment_hits_wall_flag_or_capture_point,
+    team::TeamId,
+    Segment2D,
+};
+use pyo3::{exceptions::PyRuntimeError, prelude::*, types::PyDict};
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+
+use crate::{
+    agent::PyAction,
+    bridge::policy::write_actions,
+    config::PyConfig,
+    game::{current_game_state, GameState},
+    headless_app,
+    team::PyTeamId,
+};
+
+/// Actions handed to `Env::step`, applied on the first frame of the step.
+#[derive(Resource, Default)]
+struct PendingActions(Vec<Action>);
+
+fn apply_pending_actions(
+    mut pending: ResMut<PendingActions>,
+    index: Res<AgentIndex>,
+    agents: Query<&Agent>,
+    mut movement_event_writer: EventWriter<MovementEvent>,
+) {
+    let actions = std::mem::take(&mut pending.0);
+    write_actions(actions, &index, &agents, &mut movement_event_writer);
+}
+
+fn segment_is_free_system(
+    In((seg, team_id)): In<(Segment2D, TeamId)>,
+    spatial: SpatialQuery,
+) -> bool {
+    !segment_hits_wall_flag_or_capture_point(&spatial, seg, team_id)
+}
+
+fn shortest_path_system(
+    In((start, end, team_id)): In<(Vec2, Vec2, TeamId)>,
+    spatial: SpatialQuery,
+    mut nav: ResMut<NavGrids>,
+) -> Option<Vec<Vec2>> {
+    nav.shortest_path(&spatial, start, end, team_id)
+}
+
+/// A synchronous, gym-style environment. The world only advances when
+/// `step` is called, and both teams' actions are supplied by the caller
+/// instead of policy subprocesses.
+#[gen_stub_pyclass]
+#[pyclass(name = "Env", unsendable)]
+pub struct Env {
+    config: PyConfig,
+    frames_per_step: u32,
+    max_steps: Option<u64>,
+    app: Option<App>,
+    steps: u64,
+    scores: (u32, u32),
+}
+
+impl Env {
+    fn app_mut(&mut self) -> PyResult<&mut App> {
+        self.app
+            .as_mut()
+            .ok_or_else(|| PyRuntimeError::new_err("Env.reset() must be called first"))
+    }
+
+    fn snapshot(&mut self) -> PyResult<GameState> {
+        let mut state = self
+            .app_mut()?
+            .world_mut()
+            .run_system_once(current_game_state)
+            .map_err(|e| PyRuntimeError::new_err(format!("failed to read game state: {e}")))?;
+        state.tick = self.steps;
+        Ok(state)
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Env {
+    /// Creates an environment. Each `step` advances the world by
+    /// `frames_per_step` frames of `1 / config.rate_hz` seconds, and the
+    /// episode is done after `max_steps` steps if given.
+    #[new]
+    #[pyo3(signature = (config, frames_per_step=1, max_steps=None))]
+    fn new(config: PyConfig, frames_per_step: u32, max_steps: Option<u64>) -> Self {
+        Env {
+            config,
+            frames_per_step: frames_per_step.max(1),
+            max_steps,
+            app: None,
+            steps: 0,
+            scores: (0, 0),
+        }
+    }
+
+    /// Rebuilds the world from the config and returns the initial state.
+    /// `seed` overrides `config.seed` for this episode.
+    #[pyo3(signature = (seed=None))]
+    fn reset(&mut self, seed: Option<u64>) -> PyResult<GameState> {
+        let mut config = self.config.clone();
+        if seed.is_some() {
+            config.seed = seed;
+        }
+        config.fixed_timestep = true;
+
+        let mut app = headless_app();
+        app.add_plugins((PhysicsPlugins::default(), config.ctf_plugin(true)?));
+        app.init_resource::<PendingActions>();
+        app.add_systems(Update, apply_pending_actions.before(MovementSet));
+
+        while app.plugins_state() == PluginsState::Adding {
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+
+        // The first update runs the startup schedules, which spawn the world.
+        app.update();
+
+        self.app = Some(app);
+        self.steps = 0;
+        self.scores = (0, 0);
+        self.snapshot()
+    }
+
+    /// Applies `actions` and advances the world by one step.
+    ///
+    /// Returns `(state, (red_reward, blue_reward), done, info)`, where each
+    /// reward is the number of flags that team captured during the step. The
+    /// episode is done once the match ends or `max_steps` is reached.
+    #[allow(clippy::type_complexity)]
+    fn step(
+        &mut self,
+        py: Python<'_>,
+        actions: Vec<PyAction>,
+    ) -> PyResult<(GameState, (f32, f32), bool, Py<PyDict>)> {
+        let frames = self.frames_per_step;
+        let app = self.app_mut()?;
+        app.world_mut().resource_mut::<PendingActions>().0 =
+            actions.into_iter().map(Into::into).collect();
+        for _ in 0..frames {
+            app.update();
+        }
+        self.steps += 1;
+
+        let state = self.snapshot()?;
+        let rewards = (
+            state.red_score.saturating_sub(self.scores.0) as f32,
+            state.blue_score.saturating_sub(self.scores.1) as f32,
+        );
+        self.scores = (state.red_score, state.blue_score);
+        let done = state.outcome.is_some() || self.max_steps.is_some_and(|max| self.steps >= max);
+
+        let info = PyDict::new(py);
+        info.set_item("steps", self.steps)?;
+        info.set_item("red_score", state.red_score)?;
+        info.set_item("blue_score", state.blue_score)?;
+        if let Some(outcome) = state.outcome() {
+            info.set_item("outcome", outcome)?;
+        }
+
+        Ok((state, rewards, done, info.unbind()))
+    }
+
+    /// The current state, without advancing the world.
+    fn state(&mut self) -> PyResult<GameState> {
+        self.snapshot()
+    }
+
+    /// Like `segment_is_free`, but queries this environment's world directly.
+    fn segment_is_free(
+        &mut self,
+        start: (f32, f32),
+        end: (f32, f32),
+        side: PyTeamId,
+    ) -> PyResult<bool> {
+        let seg = Segment2D {
+            start: start.into(),
+            end: end.into(),
+        };
+        self.app_mut()?
+            .world_mut()
+            .run_system_once_with(segment_is_free_system, (seg, side.inner))
+            .map_err(|e| PyRuntimeError::new_err(format!("physics query failed: {e}")))
+    }
+
+    /// Like `shortest_path`, but queries this environment's world directly.
+    fn shortest_path(
+        &mut self,
+        start: (f32, f32),
+        end: (f32, f32),
+        side: PyTeamId,
+    ) -> PyResult<Option<Vec<(f32, f32)>>> {
+        let path = self
+            .app_mut()?
+            .world_mut()
+            .run_system_once_with(shortest_path_system, (start.into(), end.into(), side.inner))
+            .map_err(|e| PyRuntimeError::new_err(format!("physics query failed: {e}")))?;
+        Ok(path.map(|path| path.into_iter().map(|p| (p.x, p.y)).collect()))
+    }
+}
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use ctf_core::{
-    flag::{CapturePoint, Flag, FlagStatus},
+    flag::{CapturePoint, CapturePointId, Flag, FlagId, FlagStatus},
+    policy::{CapturePointObservation, FlagObservation},
     team::TeamId,
 };
 use pyo3::prelude::*;
@@ -18,7 +19,7 @@ pub struct FlagState {
     pub name: String,
     pub team: TeamId,
     pub position: (f32, f32),
-    pub flag: Flag,
+    pub status: FlagStatus,
 }
 
 #[gen_stub_pymethods]
@@ -30,7 +31,8 @@ impl FlagState {
         &self.name
     }
 
-    /// The unique identifier of the flag.
+    /// The stable identifier of the flag: its slot in the team, offset by
+    /// 1000 for blue.
     #[getter]
     pub fn id(&self) -> u32 {
         self.id
@@ -50,7 +52,7 @@ impl FlagState {
 
     #[getter]
     pub fn status(&self) -> PyFlagStatus {
-        self.flag.status.into()
+        self.status.into()
     }
 }
 
@@ -75,7 +77,8 @@ impl CapturePointState {
         &self.name
     }
 
-    /// The unique identifier of the capture point.
+    /// The stable identifier of the capture point: its slot in the team,
+    /// offset by 1000 for blue.
     #[getter]
     fn id(&self) -> u32 {
         self.id
@@ -89,16 +92,38 @@ impl CapturePointState {
 
     /// The position of the flag in the game world as an (x, y) tuple.
     #[getter]
-    fn position(&self) -> (f32, f32) {
+    pub fn position(&self) -> (f32, f32) {
         self.position
     }
 
     // Whether the capture point currently has a flag.
-    fn has_flag(&self) -> bool {
+    pub fn has_flag(&self) -> bool {
         self.has_flag
     }
 }
 
+impl From<&FlagState> for FlagObservation {
+    fn from(state: &FlagState) -> Self {
+        FlagObservation {
+            id: state.id,
+            team: state.team,
+            position: state.position.into(),
+            status: state.status,
+        }
+    }
+}
+
+impl From<&CapturePointState> for CapturePointObservation {
+    fn from(state: &CapturePointState) -> Self {
+        CapturePointObservation {
+            id: state.id,
+            team: state.team,
+            position: state.position.into(),
+            has_flag: state.has_flag,
+        }
+    }
+}
+
 #[gen_stub_pyclass_enum]
 #[pyclass(name = "FlagStatus", frozen)]
 #[derive(Debug, Clone, Copy)]
@@ -119,18 +144,18 @@ impl From<FlagStatus> for PyFlagStatus {
 }
 
 pub fn collect_flag_states(
-    flags: Query<(Entity, &Name, &Transform, &Flag)>,
+    flags: Query<(&FlagId, &Name, &Transform, &Flag)>,
 ) -> (Vec<FlagState>, Vec<FlagState>) {
     let mut red_team = vec![];
     let mut blue_team = vec![];
 
-    for (entity, name, transform, flag) in &flags {
+    for (id, name, transform, flag) in &flags {
         let agent_state = FlagState {
-            id: entity.index(),
+            id: id.0,
             name: name.as_str().to_string(),
             position: (transform.translation.x, transform.translation.z),
             team: flag.team,
-            flag: *flag,
+            status: flag.status,
         };
 
         match flag.team {
@@ -145,15 +170,15 @@ pub fn collect_flag_states(
 }
 
 pub fn collect_capture_point_states(
-    capture_points: Query<(Entity, &Name, &Transform, &CapturePoint)>,
+    capture_points: Query<(&CapturePointId, &Name, &Transform, &CapturePoint)>,
 ) -> (Vec<CapturePointState>, Vec<CapturePointState>) {
     let mut red_team = vec![];
     let mut blue_team = vec![];
 
-    for (entity, name, transform, capture_point) in &capture_points {
+    for (id, name, transform, capture_point) in &capture_points {
         let cp_state = CapturePointState {
             name: name.as_str().to_string(),
-            id: entity.index(),
+            id: id.0,
             team: capture_point.team,
             position: (transform.translation.x, transform.translation.z),
             has_flag: capture_point.flag.is_some(),
@@ -1,17 +1,31 @@
+use bevy::prelude::*;
 use pyo3::prelude::*;
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
 use serde::{Deserialize, Serialize};
 
-use crate::agent::AgentState;
-use crate::flag::{CapturePointState, FlagState};
+use crate::agent::{collect_agent_states, AgentState, PyAction};
+use crate::flag::{
+    collect_capture_point_states, collect_flag_states, CapturePointState, FlagState,
+};
+use crate::limits::PolicyStats;
+use crate::outcome::PyMatchOutcome;
+use crate::protocol::{encode_reply, ActionBatch};
 use crate::team::PyTeamId;
-use ctf_core::team::TeamId;
+use crate::wire::PyWireFormat;
+use ctf_core::agent::{Agent, AgentId};
+use ctf_core::flag::{CapturePoint, CapturePointId, Flag, FlagCaptureCounts, FlagId};
+use ctf_core::match_state::{MatchOutcome, MatchState};
+use ctf_core::policy::Observation;
+use ctf_core::team::{Team, TeamId};
 
 /// A snapshot of the current game state, including scores and agent states for both teams.
 #[gen_stub_pyclass]
 #[pyclass(name = "GameState", frozen)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameState {
+    /// Increases by one with every state sent to the policies.
+    #[serde(default)]
+    pub tick: u64,
     pub red_score: u32,
     pub blue_score: u32,
     pub red_team: Vec<AgentState>,
@@ -21,11 +35,25 @@ pub struct GameState {
     pub num_flags_per_team: u32,
     pub red_capture_points: Vec<CapturePointState>,
     pub blue_capture_points: Vec<CapturePointState>,
+    #[serde(default)]
+    pub elapsed: f32,
+    pub time_remaining: Option<f32>,
+    pub outcome: Option<MatchOutcome>,
+    #[serde(default)]
+    pub red_policy_stats: Option<PolicyStats>,
+    #[serde(default)]
+    pub blue_policy_stats: Option<PolicyStats>,
 }
 
 #[gen_stub_pymethods]
 #[pymethods]
 impl GameState {
+    /// The ID of this state. Answers to it must carry the same tick.
+    #[getter]
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
     /// The number of flags captured by the red team.
     #[getter]
     pub fn red_score(&self) -> u32 {
@@ -80,6 +108,42 @@ impl GameState {
         self.blue_capture_points.clone()
     }
 
+    /// Seconds of game time since the match started.
+    #[getter]
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Seconds left before the time limit, or `None` if there is no limit.
+    #[getter]
+    pub fn time_remaining(&self) -> Option<f32> {
+        self.time_remaining
+    }
+
+    /// How the match ended, or `None` while it is still running.
+    #[getter]
+    pub fn outcome(&self) -> Option<PyMatchOutcome> {
+        self.outcome.map(|inner| PyMatchOutcome { inner })
+    }
+
+    /// Whether the match has ended.
+    #[getter]
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// How well the red policy has kept up, or `None` outside of a policy match.
+    #[getter]
+    pub fn red_policy_stats(&self) -> Option<PolicyStats> {
+        self.red_policy_stats
+    }
+
+    /// How well the blue policy has kept up, or `None` outside of a policy match.
+    #[getter]
+    pub fn blue_policy_stats(&self) -> Option<PolicyStats> {
+        self.blue_policy_stats
+    }
+
     /// Gets the score for the specified team.
     ///
     /// Parameters
@@ -124,6 +188,26 @@ impl GameState {
         }
     }
 
+    /// The line to answer this state with.
+    ///
+    /// Parameters
+    ///   `actions`: The actions chosen for this state.
+    pub fn reply(&self, actions: Vec<PyAction>) -> String {
+        serde_json::to_string(&ActionBatch::message(self.tick, actions)).unwrap()
+    }
+
+    /// Like `reply`, but encoded in `wire_format` with framing included,
+    /// ready to write to `sys.stdout.buffer`.
+    #[pyo3(signature = (actions, wire_format=PyWireFormat::Json))]
+    pub fn encode_reply<'py>(
+        &self,
+        py: Python<'py>,
+        actions: Vec<PyAction>,
+        wire_format: PyWireFormat,
+    ) -> PyResult<Bound<'py, pyo3::types::PyBytes>> {
+        encode_reply(py, self.tick, actions, wire_format)
+    }
+
     #[staticmethod]
     pub fn from_json(json_str: &str) -> PyResult<Self> {
         serde_json::from_str(json_str).map_err(|e| {
@@ -134,3 +218,68 @@ impl GameState {
         })
     }
 }
+
+impl From<&GameState> for Observation {
+    fn from(state: &GameState) -> Self {
+        fn all<'a, T: 'a, U: From<&'a T>>(items: &'a [T]) -> Vec<U> {
+            items.iter().map(U::from).collect()
+        }
+        Observation {
+            tick: state.tick,
+            red_score: state.red_score,
+            blue_score: state.blue_score,
+            red_team: all(&state.red_team),
+            blue_team: all(&state.blue_team),
+            red_flags: all(&state.red_flags),
+            blue_flags: all(&state.blue_flags),
+            red_capture_points: all(&state.red_capture_points),
+            blue_capture_points: all(&state.blue_capture_points),
+            elapsed: state.elapsed,
+            time_remaining: state.time_remaining,
+            outcome: state.outcome,
+        }
+    }
+}
+
+pub fn build_game_state(
+    scores: &FlagCaptureCounts,
+    match_state: &MatchState,
+    agents: Query<(&AgentId, &Name, &Transform, &Agent, &Team)>,
+    flags: Query<(&FlagId, &Name, &Transform, &Flag)>,
+    capture_points: Query<(&CapturePointId, &Name, &Transform, &CapturePoint)>,
+) -> GameState {
+    let (red_team, blue_team) =
+        collect_agent_states(agents, |flag| flags.get(flag).ok().map(|(id, ..)| *id));
+    let (red_flags, blue_flags) = collect_flag_states(flags);
+    let (red_capture_points, blue_capture_points) = collect_capture_point_states(capture_points);
+    let num_flags_per_team = red_flags.len() as u32;
+
+    GameState {
+        tick: 0,
+        red_score: scores.red,
+        blue_score: scores.blue,
+        red_team,
+        blue_team,
+        red_flags,
+        blue_flags,
+        num_flags_per_team,
+        red_capture_points,
+        blue_capture_points,
+        elapsed: match_state.elapsed,
+        time_remaining: match_state.remaining(),
+        outcome: match_state.outcome,
+        red_policy_stats: None,
+        blue_policy_stats: None,
+    }
+}
+
+/// System form of [`build_game_state`], for snapshotting a world directly.
+pub fn current_game_state(
+    scores: Res<FlagCaptureCounts>,
+    match_state: Res<MatchState>,
+    agents: Query<(&AgentId, &Name, &Transform, &Agent, &Team)>,
+    flags: Query<(&FlagId, &Name, &Transform, &Flag)>,
+    capture_points: Query<(&CapturePointId, &Name, &Transform, &CapturePoint)>,
+) -> GameState {
+    build_game_state(&scores, &match_state, agents, flags, capture_points)
+}
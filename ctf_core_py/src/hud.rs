@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use bevy::prelude::*;
+use ctf_core::{
+    agent::Agent,
+    flag::{Flag, FlagCaptureCounts, FlagId, FlagStatus},
+    match_state::{MatchSet, MatchState},
+};
+
+use crate::bridge::policy::GameStatePublished;
+use crate::limits::PolicyStats;
+use crate::replay::{AppliedFlagEvents, ReplayEvent};
+
+/// How many flag events the feed shows at once.
+const FEED_LEN: usize = 6;
+/// Seconds of game time a flag event stays in the feed.
+const FEED_SECONDS: f32 = 10.0;
+
+/// Overlays the score, the match clock, each flag's status and carrier, the
+/// teams' policy latency and a feed of flag pickups, drops and captures.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hud>();
+        app.add_systems(Startup, spawn_hud);
+        app.add_systems(
+            Update,
+            (
+                record_policy_stats,
+                record_flag_events,
+                update_scoreboard,
+                update_feed,
+            )
+                .chain()
+                .after(MatchSet),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct Hud {
+    red_stats: Option<PolicyStats>,
+    blue_stats: Option<PolicyStats>,
+    /// Flag events with the match time they happened at, oldest first.
+    feed: VecDeque<(f32, String)>,
+}
+
+impl Hud {
+    fn push(&mut self, at: f32, line: String) {
+        self.feed.push_back((at, line));
+        if self.feed.len() > FEED_LEN {
+            self.feed.pop_front();
+        }
+    }
+}
+
+#[derive(Component)]
+struct Scoreboard;
+
+#[derive(Component)]
+struct Feed;
+
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        Scoreboard,
+        Text::default(),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            left: Val::Px(5.0),
+            ..default()
+        },
+    ));
+    commands.spawn((
+        Feed,
+        Text::default(),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(5.0),
+            left: Val::Px(5.0),
+            ..default()
+        },
+    ));
+}
+
+fn record_policy_stats(mut published: EventReader<GameStatePublished>, mut hud: ResMut<Hud>) {
+    if let Some(GameStatePublished(state)) = published.read().last() {
+        hud.red_stats = state.red_policy_stats;
+        hud.blue_stats = state.blue_policy_stats;
+    }
+}
+
+/// Adds the pickups, drops and captures that went through this frame to the
+/// feed. Events the rules turned down are left out.
+fn record_flag_events(
+    mut events: AppliedFlagEvents,
+    match_state: Res<MatchState>,
+    mut hud: ResMut<Hud>,
+) {
+    let at = match_state.elapsed;
+    for event in events.read() {
+        let line = match event {
+            ReplayEvent::Pickup { agent, flag } => format!("{agent} picked up {flag}"),
+            ReplayEvent::Drop { agent, flag } => format!("{agent} dropped {flag}"),
+            ReplayEvent::Score { agent, flag, .. } => format!("{agent} captured {flag}"),
+        };
+        hud.push(at, line);
+    }
+}
+
+fn update_scoreboard(
+    hud: Res<Hud>,
+    scores: Res<FlagCaptureCounts>,
+    match_state: Res<MatchState>,
+    flags: Query<(Entity, &FlagId, &Name, &Flag)>,
+    carriers: Query<(&Name, &Agent)>,
+    mut text: Single<&mut Text, With<Scoreboard>>,
+) {
+    let mut out = format!("Red {} - {} Blue\n", scores.red, scores.blue);
+    let _ = write!(out, "{} elapsed", clock(match_state.elapsed));
+    if let Some(remaining) = match_state.remaining() {
+        let _ = write!(out, " | {} left", clock(remaining));
+    }
+    out.push('\n');
+    if let Some(outcome) = match_state.outcome {
+        let _ = match outcome.winner {
+            Some(team) => writeln!(out, "{team:?} wins ({:?})", outcome.reason),
+            None => writeln!(out, "Draw ({:?})", outcome.reason),
+        };
+    }
+
+    for (team, stats) in [("Red", &hud.red_stats), ("Blue", &hud.blue_stats)] {
+        let _ = writeln!(out, "{team} policy: {}", latency(stats.as_ref()));
+    }
+
+    let mut flags: Vec<_> = flags.iter().collect();
+    flags.sort_by_key(|(_, id, _, _)| id.0);
+    for (entity, _, name, flag) in flags {
+        let status = match flag.status {
+            FlagStatus::Dropped => "on the ground".to_string(),
+            FlagStatus::Captured => "captured".to_string(),
+            FlagStatus::PickedUp => {
+                let carrier = carriers
+                    .iter()
+                    .find(|(_, agent)| agent.flag == Some(entity))
+                    .map_or("?", |(name, _)| name.as_str());
+                format!("carried by {carrier}")
+            }
+        };
+        let _ = writeln!(out, "{name}: {status}");
+    }
+
+    text.0 = out;
+}
+
+fn update_feed(
+    hud: Res<Hud>,
+    match_state: Res<MatchState>,
+    mut text: Single<&mut Text, With<Feed>>,
+) {
+    let since = match_state.elapsed - FEED_SECONDS;
+    let mut out = String::new();
+    for (at, line) in hud.feed.iter().filter(|(at, _)| *at >= since) {
+        let _ = writeln!(out, "[{}] {line}", clock(*at));
+    }
+    text.0 = out;
+}
+
+/// `seconds` as minutes and seconds.
+fn clock(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as u32;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn latency(stats: Option<&PolicyStats>) -> String {
+    match stats {
+        Some(stats) if stats.answered + stats.late > 0 => {
+            let mut out = format!(
+                "{:.1} ms (mean {:.1}, max {:.1})",
+                stats.last_latency_ms, stats.mean_latency_ms, stats.max_latency_ms
+            );
+            if stats.over_budget {
+                out.push_str(", over budget");
+            }
+            out
+        }
+        _ => "-".to_string(),
+    }
+}
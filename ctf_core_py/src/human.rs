@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use ctf_core::{
+    agent::{Action, Agent, AgentId, AgentIndex},
+    character_controller::MovementEvent,
+    match_state::MatchSet,
+    team::{Team, TeamId},
+};
+
+use crate::bridge::policy::write_actions;
+
+/// The `team_policy` that hands a team to a player in the rendered app.
+pub const HUMAN_POLICY: &str = "human";
+
+/// Lets a player drive one agent of `team` with WASD or a gamepad's left
+/// stick. Tab or the right bumper cycles through the team's agents; the
+/// others stand still.
+pub struct HumanControlPlugin {
+    pub team: TeamId,
+}
+
+impl Plugin for HumanControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HumanControl {
+            team: self.team,
+            selected: 0,
+        });
+        app.add_systems(
+            Update,
+            (
+                cycle_controlled_agent,
+                drive_controlled_agent,
+                draw_controlled_agent,
+            )
+                .chain()
+                .after(MatchSet),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct HumanControl {
+    team: TeamId,
+    /// Index of the controlled agent among the team's agents, by ID.
+    selected: usize,
+}
+
+impl HumanControl {
+    /// IDs of the team's agents, in order.
+    fn agent_ids(&self, agents: &Query<(&AgentId, &Team, &Transform)>) -> Vec<u32> {
+        let mut ids: Vec<u32> = agents
+            .iter()
+            .filter(|(_, team, _)| team.0 == self.team)
+            .map(|(id, _, _)| id.0)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn controlled(&self, agents: &Query<(&AgentId, &Team, &Transform)>) -> Option<u32> {
+        let ids = self.agent_ids(agents);
+        (!ids.is_empty()).then(|| ids[self.selected % ids.len()])
+    }
+}
+
+fn cycle_controlled_agent(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    positions: Query<(&AgentId, &Team, &Transform)>,
+    mut control: ResMut<HumanControl>,
+    mut movement_event_writer: EventWriter<MovementEvent>,
+) {
+    let pressed = keys.just_pressed(KeyCode::Tab)
+        || gamepads
+            .iter()
+            .any(|pad| pad.just_pressed(GamepadButton::RightTrigger));
+    if !pressed {
+        return;
+    }
+    // agents keep their last velocity, so stop the one let go of
+    if let Some(id) = control.controlled(&positions) {
+        movement_event_writer.write(MovementEvent::TranslateById(id, Vec2::ZERO));
+    }
+    control.selected = control.selected.wrapping_add(1);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn drive_controlled_agent(
+    control: Res<HumanControl>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    positions: Query<(&AgentId, &Team, &Transform)>,
+    index: Res<AgentIndex>,
+    agents: Query<&Agent>,
+    mut movement_event_writer: EventWriter<MovementEvent>,
+) {
+    let Some(id) = control.controlled(&positions) else {
+        return;
+    };
+
+    // screen directions: +x right, +y up
+    let mut input = Vec2::ZERO;
+    for (key, dir) in [
+        (KeyCode::KeyW, Vec2::Y),
+        (KeyCode::KeyS, Vec2::NEG_Y),
+        (KeyCode::KeyA, Vec2::NEG_X),
+        (KeyCode::KeyD, Vec2::X),
+    ] {
+        if keys.pressed(key) {
+            input += dir;
+        }
+    }
+    for pad in &gamepads {
+        input += pad.left_stick();
+    }
+    let input = input.clamp_length_max(1.0);
+
+    let speed = index
+        .get(id)
+        .and_then(|e| agents.get(e).ok())
+        .map_or(0.0, |agent| agent.speed);
+    let at = positions
+        .iter()
+        .find(|(agent_id, _, _)| agent_id.0 == id)
+        .map_or(Vec3::ZERO, |(_, _, t)| t.translation);
+    let velocity = screen_to_world(input, at, cameras.single().ok()) * speed;
+
+    let actions = vec![Action::Move {
+        id,
+        velocity,
+        message: None,
+    }];
+    write_actions(actions, &index, &agents, &mut movement_event_writer);
+}
+
+/// The ground-plane velocity that moves along `input` on screen, seen from
+/// `camera` around `at`.
+fn screen_to_world(input: Vec2, at: Vec3, camera: Option<(&Camera, &GlobalTransform)>) -> Vec2 {
+    if input == Vec2::ZERO {
+        return Vec2::ZERO;
+    }
+    let projected = camera.and_then(|(camera, transform)| {
+        let ndc = |p: Vec3| camera.world_to_ndc(transform, p).map(|n| n.truncate());
+        let origin = ndc(at)?;
+        // where world +x and +z land on screen
+        let x = ndc(at + Vec3::X)? - origin;
+        let z = ndc(at + Vec3::Z)? - origin;
+        let basis = Mat2::from_cols(x, z);
+        (basis.determinant().abs() > 1e-6).then(|| basis.inverse() * input)
+    });
+    // without a camera, assume a top-down view with -z up
+    let world = projected.unwrap_or(Vec2::new(input.x, -input.y));
+    world.normalize_or_zero() * input.length()
+}
+
+fn draw_controlled_agent(
+    control: Res<HumanControl>,
+    positions: Query<(&AgentId, &Team, &Transform)>,
+    mut gizmos: Gizmos,
+) {
+    let Some(id) = control.controlled(&positions) else {
+        return;
+    };
+    let Some((_, _, transform)) = positions.iter().find(|(agent_id, _, _)| agent_id.0 == id) else {
+        return;
+    };
+    let ring = Isometry3d::new(
+        transform.translation,
+        Quat::from_rotation_arc(Vec3::Z, Vec3::Y),
+    );
+    gizmos.circle(ring, 1.0, Color::WHITE);
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::process::Command;
+
+use ctf_core::team::TeamId;
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
+use serde::{Deserialize, Serialize};
+
+use crate::team::PyTeamId;
+
+/// How to start a team's policy process. Any executable that speaks the
+/// JSON-lines protocol on stdin/stdout works.
+///
+/// `args`, `env` values and `cwd` may contain these placeholders:
+///   - `{config}`: the config as JSON.
+///   - `{side}`: the side as JSON, as read by `Team.from_str`.
+///   - `{side_name}`: `red` or `blue`.
+///   - `{physics_addr}`: the physics RPC address, or an empty string.
+///   - `{agent}`: the ID of the agent the process controls with
+///     `Config.per_agent_policies`, or an empty string.
+///   - `{agent_name}`: that agent's entry in the team's agent IDs, or an
+///     empty string.
+#[gen_stub_pyclass]
+#[pyclass(name = "LaunchSpec")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PyLaunchSpec {
+    #[pyo3(get, set)]
+    pub program: String,
+
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Extra environment variables, on top of the simulator's own.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Working directory. Defaults to the simulator's.
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+impl PyLaunchSpec {
+    /// The original launcher: `python_exe run launch-team <config> --side <side>`.
+    pub fn launch_team(python_exe: &str) -> Self {
+        Self {
+            program: python_exe.to_string(),
+            args: ["run", "launch-team", "{config}", "--side", "{side}"]
+                .map(String::from)
+                .to_vec(),
+            env: HashMap::new(),
+            cwd: None,
+        }
+    }
+
+    /// Builds the command for `side`, or for one of its agents, with
+    /// placeholders filled in.
+    pub fn command(
+        &self,
+        side: TeamId,
+        agent: Option<(u32, &str)>,
+        config_json: &str,
+        physics_addr: Option<SocketAddr>,
+    ) -> anyhow::Result<Command> {
+        let side_json = serde_json::to_string(&PyTeamId { inner: side })?;
+        let side_name = match side {
+            TeamId::Red => "red",
+            TeamId::Blue => "blue",
+        };
+        let physics_addr = physics_addr.map(|a| a.to_string()).unwrap_or_default();
+        let agent_id = agent.map(|(id, _)| id.to_string()).unwrap_or_default();
+        let agent_name = agent.map(|(_, name)| name).unwrap_or_default();
+        let values = [
+            ("config", config_json),
+            ("side", side_json.as_str()),
+            ("side_name", side_name),
+            ("physics_addr", physics_addr.as_str()),
+            ("agent", agent_id.as_str()),
+            ("agent_name", agent_name),
+        ];
+        let fill = |s: &str| fill_placeholders(s, &values);
+
+        let mut command = Command::new(fill(&self.program));
+        command.args(self.args.iter().map(|a| fill(a)));
+        for (key, value) in &self.env {
+            command.env(key, fill(value));
+        }
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(fill(cwd));
+        }
+        Ok(command)
+    }
+}
+
+/// Replaces every `{name}` in `template` in a single pass, so substituted
+/// values are never scanned for placeholders themselves.
+fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    'scan: while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+        for (name, value) in values {
+            if let Some(after) = rest
+                .strip_prefix('{')
+                .and_then(|r| r.strip_prefix(*name))
+                .and_then(|r| r.strip_prefix('}'))
+            {
+                filled.push_str(value);
+                rest = after;
+                continue 'scan;
+            }
+        }
+        filled.push('{');
+        rest = &rest[1..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyLaunchSpec {
+    #[new]
+    #[pyo3(signature = (program, args=Vec::new(), env=HashMap::new(), cwd=None))]
+    fn new(
+        program: String,
+        args: Vec<String>,
+        env: HashMap<String, String>,
+        cwd: Option<String>,
+    ) -> Self {
+        Self {
+            program,
+            args,
+            env,
+            cwd,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUES: [(&str, &str); 3] = [("side", "\"Red\""), ("side_name", "red"), ("agent", "")];
+
+    #[test]
+    fn fills_every_placeholder() {
+        assert_eq!(
+            fill_placeholders("--side {side} --name {side_name}{agent}.log", &VALUES),
+            "--side \"Red\" --name red.log"
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_and_unclosed_braces_alone() {
+        assert_eq!(
+            fill_placeholders("{other} {side_name", &VALUES),
+            "{other} {side_name"
+        );
+        assert_eq!(fill_placeholders("{{side_name}}", &VALUES), "{red}");
+        assert_eq!(fill_placeholders("}{", &VALUES), "}{");
+    }
+
+    #[test]
+    fn does_not_fill_placeholders_inside_values() {
+        let values = [
+            ("config", "{\"name\": \"{side_name}\"}"),
+            ("side_name", "red"),
+        ];
+        assert_eq!(
+            fill_placeholders("{config}", &values),
+            "{\"name\": \"{side_name}\"}"
+        );
+    }
+}
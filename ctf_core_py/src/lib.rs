@@ -1,11 +1,25 @@
 mod agent;
 mod agents;
 mod bridge;
+mod comms;
 mod config;
+mod env;
 mod flag;
 mod game;
+mod hud;
+mod human;
+mod launch;
+mod limits;
+mod observation;
+mod outcome;
+mod policy;
+mod protocol;
+mod replay;
+mod rules;
 mod state_queue;
 mod team;
+pub mod tournament;
+mod wire;
 mod worker;
 
 use avian3d::prelude::*;
@@ -13,37 +27,57 @@ use bevy::app::ScheduleRunnerPlugin;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy::winit::WinitWindows;
-use ctf_core::team::TeamId;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
-use std::io::{BufRead, BufReader, Write};
-use std::net::TcpStream;
-use std::time::Duration;
 
 use bevy_inspector_egui::bevy_egui::EguiPlugin;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use pyo3_stub_gen::define_stub_info_gatherer;
 
-use ctf_core::core;
 use ctf_core::debug;
 
 use agent::*;
 use game::*;
 use pyo3_stub_gen::derive::gen_stub_pyfunction;
 
-use crate::agents::defense_bot::DefenseBot;
+use crate::agents::attacker_bot::AttackerBot;
+use crate::agents::defense_bot::{DefenseBot, DefenseTask, PyDefenseRole};
+use crate::agents::interceptor_bot::InterceptorBot;
+use crate::agents::mixed_bot::MixedBot;
+use crate::agents::random_bot::RandomBot;
+use crate::bridge::physics::{PhysAnswer, PhysRequest};
 use crate::bridge::policy::TestHarnessBridge;
+use crate::comms::{PyComms, PyMessage};
 use crate::config::PyConfig;
+use crate::env::Env;
 use crate::flag::CapturePointState;
 use crate::flag::FlagState;
 use crate::flag::PyFlagStatus;
+use crate::launch::PyLaunchSpec;
+use crate::limits::{PolicyStats, PyOverBudget, PyPolicyLimits};
+use crate::observation::{PyFogOfWar, PyUnseenOpponents};
+use crate::outcome::{PyMatchEndReason, PyMatchOutcome};
+use crate::protocol::{read_message, Hello, PROTOCOL_VERSION};
+use crate::rules::{PyGameRules, PyTagPenalty, PyTaggingMode};
 use crate::state_queue::StateQueue;
 use crate::team::PyTeamId;
+use crate::wire::PyWireFormat;
 
 #[gen_stub_pyfunction]
 #[pyfunction(name = "run")]
 /// Runs the Capture the Flag simulation with the given policies for each team.
+///
+/// A team whose policy is `human` is driven with WASD or a gamepad's left
+/// stick. Tab or the right bumper switches to the next agent.
+///
+/// Raises `RuntimeError` if the match ended in a forfeit.
 fn run(py: Python<'_>, config: &PyConfig) -> PyResult<()> {
-    py.detach(|| {
+    let ctf_plugin = config.ctf_plugin(false)?;
+    let human_team = config.human_team()?;
+    config.check_team_policies()?;
+    let recording = config.recording()?;
+
+    let exit = py.detach(|| {
         let mut app = App::new();
         app.add_plugins((
             DefaultPlugins.set(WindowPlugin {
@@ -54,30 +88,30 @@ fn run(py: Python<'_>, config: &PyConfig) -> PyResult<()> {
                 ..Default::default()
             }),
             PhysicsPlugins::default(),
-            core::CTFPlugin {
-                red_team_agent_positions: config.red_team_agent_positions.clone(),
-                blue_team_agent_positions: config.blue_team_agent_positions.clone(),
-                red_team_flag_positions: config.red_team_flag_positions.clone(),
-                blue_team_flag_positions: config.blue_team_flag_positions.clone(),
-                red_team_capture_point_positions: config.red_team_capture_point_positions.clone(),
-                blue_team_capture_point_positions: config.blue_team_capture_point_positions.clone(),
-                headless: false,
-            },
+            ctf_plugin,
             // Initialize in-proc physics channel + processing system
             bridge::physics::PythonPhysicsBridgePlugin,
         ));
 
-        // Start the physics RPC server now (after physics bridge exists),
-        // and export its address so children can connect.
-        if let Ok(addr) = bridge::physics::start_physics_rpc_server() {
-            std::env::set_var("PHYSICS_ADDR", addr.to_string());
-        }
+        // Start the physics RPC server now (after physics bridge exists).
+        app.add_plugins(bridge::physics::PhysicsRpcServerPlugin {
+            process_default: true,
+        });
 
-        // Now start policy bridges (children will inherit PHYSICS_ADDR).
+        // Now start policy bridges (children are given PHYSICS_ADDR).
         app.add_plugins(bridge::policy::PythonPolicyBridgePlugin {
             config: config.clone(),
             test_harness: None,
         });
+        app.add_plugins(hud::HudPlugin);
+
+        if let Some(recording) = recording {
+            app.add_plugins(recording);
+        }
+
+        if let Some(team) = human_team {
+            app.add_plugins(human::HumanControlPlugin { team });
+        }
 
         if config.debug {
             app.add_plugins((
@@ -95,6 +129,52 @@ fn run(py: Python<'_>, config: &PyConfig) -> PyResult<()> {
             ..Default::default()
         });
 
+        app.run()
+    });
+
+    if exit.is_error() {
+        return Err(PyRuntimeError::new_err(
+            "The match ended in a forfeit; see the log for the policy that failed",
+        ));
+    }
+    Ok(())
+}
+
+#[gen_stub_pyfunction]
+#[pyfunction(name = "replay")]
+/// Plays back a match recorded with `Config.record_path`.
+///
+/// Space pauses, Left/Right seek by five seconds and Up/Down change the
+/// playback speed.
+fn play_replay(py: Python<'_>, path: &str) -> PyResult<()> {
+    let replay = replay::Replay::load(path)?;
+    let mut ctf_plugin = replay.config.ctf_plugin_with_map(replay.map.clone(), false);
+    ctf_plugin.fixed_timestep = None;
+    ctf_plugin.replay = true;
+
+    py.detach(|| {
+        let mut app = App::new();
+        app.add_plugins((
+            DefaultPlugins.set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Capture the Flag Replay".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            PhysicsPlugins::default(),
+            ctf_plugin,
+            replay::ReplayPlugin { replay },
+        ));
+
+        app.add_systems(PostStartup, force_focus);
+
+        app.insert_resource(AmbientLight {
+            color: Color::WHITE,
+            brightness: 1_500.0,
+            ..Default::default()
+        });
+
         app.run();
     });
 
@@ -104,67 +184,44 @@ fn run(py: Python<'_>, config: &PyConfig) -> PyResult<()> {
 #[gen_stub_pyfunction]
 #[pyfunction(name = "run_headless")]
 fn run_headless(py: Python<'_>, config: &PyConfig) -> PyResult<StateQueue> {
-    let rate = config.rate_hz.unwrap_or(60.0).clamp(1.0, 240.0);
+    let rate = config.effective_rate_hz();
     let config = config.clone();
-    let frame_dt = Duration::from_secs_f64(1.0 / rate as f64);
+    let frame_time = config.headless_frame_time()?;
+    if config.human_team()?.is_some() {
+        return Err(PyValueError::new_err(
+            "A team played by a human needs the window opened by `run`",
+        ));
+    }
+    config.check_team_policies()?;
 
     let (tx_state, rx_state) = crossbeam_channel::bounded::<GameState>(256);
     let (tx_stop, rx_stop) = crossbeam_channel::unbounded::<()>();
 
-    let red_team_agent_positions = config.red_team_agent_positions.clone();
-    let blue_team_agent_positions = config.blue_team_agent_positions.clone();
-    let red_team_flag_positions = config.red_team_flag_positions.clone();
-    let blue_team_flag_positions = config.blue_team_flag_positions.clone();
-    let red_team_capture_point_positions = config.red_team_capture_point_positions.clone();
-    let blue_team_capture_point_positions = config.blue_team_capture_point_positions.clone();
+    let ctf_plugin = config.ctf_plugin(true)?;
+    let recording = config.recording()?;
 
     let join = py.detach(|| {
         std::thread::spawn(move || {
-            let mut app = App::new();
-
-            app.add_plugins((
-                DefaultPlugins
-                    .build()
-                    .disable::<bevy::winit::WinitPlugin>()
-                    .disable::<bevy::window::WindowPlugin>()
-                    .disable::<bevy::render::RenderPlugin>()
-                    .disable::<bevy::pbr::PbrPlugin>()
-                    .disable::<bevy::sprite::SpritePlugin>()
-                    .disable::<bevy::ui::UiPlugin>()
-                    .disable::<bevy::gizmos::GizmoPlugin>()
-                    .disable::<PointerInputPlugin>()
-                    .disable::<bevy::picking::PickingPlugin>()
-                    .disable::<bevy::picking::InteractionPlugin>()
-                    .disable::<bevy::text::TextPlugin>()
-                    .disable::<bevy::core_pipeline::CorePipelinePlugin>(),
-                ScheduleRunnerPlugin::run_loop(frame_dt),
-            ));
-
-            // Provide Assets<Mesh> since RenderPlugin is disabled
-            app.init_asset::<bevy::render::mesh::Mesh>();
+            let mut app = headless_app();
+            app.add_plugins(ScheduleRunnerPlugin::run_loop(frame_time));
 
             app.add_plugins((
                 PhysicsPlugins::default(),
-                core::CTFPlugin {
-                    red_team_agent_positions,
-                    blue_team_agent_positions,
-                    red_team_flag_positions,
-                    blue_team_flag_positions,
-                    red_team_capture_point_positions,
-                    blue_team_capture_point_positions,
-                    headless: true,
-                },
+                ctf_plugin,
                 // physics bridge first
                 bridge::physics::PythonPhysicsBridgePlugin,
+                // then RPC, so segment_is_free can connect (also useful for
+                // same-process tests)
+                bridge::physics::PhysicsRpcServerPlugin {
+                    process_default: true,
+                },
             ));
 
-            // Start RPC and export addr so segment_is_free can connect (also
-            // useful for same-process tests).
-            if let Ok(addr) = bridge::physics::start_physics_rpc_server() {
-                std::env::set_var("PHYSICS_ADDR", addr.to_string());
+            if let Some(recording) = recording {
+                app.add_plugins(recording);
             }
 
-            // Now policy (children inherit PHYSICS_ADDR)
+            // Now policy (children are given PHYSICS_ADDR)
             app.add_plugins(bridge::policy::PythonPolicyBridgePlugin {
                 config,
                 test_harness: Some(TestHarnessBridge {
@@ -185,11 +242,40 @@ fn run_headless(py: Python<'_>, config: &PyConfig) -> PyResult<StateQueue> {
     })
 }
 
+/// An app with the default plugins minus windowing, rendering and UI, and
+/// without a runner, so callers choose how it is driven.
+pub(crate) fn headless_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins(
+        DefaultPlugins
+            .build()
+            .disable::<bevy::winit::WinitPlugin>()
+            .disable::<bevy::window::WindowPlugin>()
+            .disable::<bevy::render::RenderPlugin>()
+            .disable::<bevy::pbr::PbrPlugin>()
+            .disable::<bevy::sprite::SpritePlugin>()
+            .disable::<bevy::ui::UiPlugin>()
+            .disable::<bevy::gizmos::GizmoPlugin>()
+            .disable::<PointerInputPlugin>()
+            .disable::<bevy::picking::PickingPlugin>()
+            .disable::<bevy::picking::InteractionPlugin>()
+            .disable::<bevy::text::TextPlugin>()
+            .disable::<bevy::core_pipeline::CorePipelinePlugin>(),
+    );
+
+    // Provide Assets<Mesh> since RenderPlugin is disabled
+    app.init_asset::<bevy::render::mesh::Mesh>();
+
+    app
+}
+
 #[gen_stub_pyfunction]
 #[pyfunction(name = "segment_is_free")]
 #[pyo3(signature = (start, end, side, timeout_ms=None))]
 /// Checks if the line segment from `start` to `end` is free of obstacles by
-/// making a blocking RPC to the Bevy app's physics server.
+/// making a blocking RPC to the Bevy app's physics server. The connection is
+/// kept open and reused by later calls from the same process.
 ///
 /// Parameters:
 ///   - `start`: Tuple of (x, y) coordinates for the start of the segment.
@@ -205,62 +291,151 @@ pub fn segment_is_free(
     side: PyTeamId,
     timeout_ms: Option<u64>,
 ) -> PyResult<bool> {
-    // Get server address from env or from the physics module (same-process).
-    let addr = std::env::var("PHYSICS_ADDR")
-        .ok()
-        .or_else(|| bridge::physics::get_physics_rpc_addr().map(|a| a.to_string()))
-        .ok_or_else(|| {
-            pyo3::exceptions::PyRuntimeError::new_err(
-                "Physics RPC not initialized (PHYSICS_ADDR not set)",
-            )
-        })?;
-
-    let stream = TcpStream::connect(&addr).map_err(|e| {
-        pyo3::exceptions::PyConnectionError::new_err(format!("connect {}: {}", addr, e))
-    })?;
-
-    if let Some(timeout_ms) = timeout_ms {
-        let _ = stream.set_read_timeout(Some(Duration::from_millis(timeout_ms)));
+    let request = PhysRequest::Segment {
+        start,
+        end,
+        team: side.inner,
+    };
+    match bridge::client::query(&request, timeout_ms)? {
+        PhysAnswer::Free { free } => Ok(free),
+        other => Err(bridge::client::unexpected_answer(other)),
     }
-    // let _ = stream.set_write_timeout(Some(Duration::from_millis(50)));
+}
+
+#[gen_stub_pyfunction]
+#[pyfunction(name = "segments_are_free")]
+#[pyo3(signature = (segments, side, timeout_ms=None))]
+/// Like `segment_is_free`, but checks many segments in a single RPC.
+///
+/// Parameters:
+///   - `segments`: List of ((x, y), (x, y)) start and end points.
+///   - `side`: Team ID (Team.RED or Team.BLUE)
+///   - `timeout_ms`: Timeout in milliseconds for the whole batch.
+///
+/// Returns:
+///  - One bool per segment, in the same order.
+pub fn segments_are_free(
+    segments: Vec<((f32, f32), (f32, f32))>,
+    side: PyTeamId,
+    timeout_ms: Option<u64>,
+) -> PyResult<Vec<bool>> {
+    let request = PhysRequest::Batch {
+        queries: segments
+            .into_iter()
+            .map(|(start, end)| PhysRequest::Segment {
+                start,
+                end,
+                team: side.inner,
+            })
+            .collect(),
+    };
+    let PhysAnswer::Batch { results } = bridge::client::query(&request, timeout_ms)? else {
+        return Err(PyRuntimeError::new_err("expected a batch physics resp"));
+    };
+    results
+        .into_iter()
+        .map(|answer| match answer {
+            PhysAnswer::Free { free } => Ok(free),
+            other => Err(bridge::client::unexpected_answer(other)),
+        })
+        .collect()
+}
 
-    // Simple one-shot request with id=1.
-    let team_str = match side.inner {
-        TeamId::Red => "Red",
-        TeamId::Blue => "Blue",
+#[gen_stub_pyfunction]
+#[pyfunction(name = "point_is_free")]
+#[pyo3(signature = (point, side, timeout_ms=None))]
+/// Checks if an agent standing at `point` would overlap an obstacle.
+///
+/// Returns:
+///  - `True` if the point is free of obstacles. This does not include other agents
+pub fn point_is_free(point: (f32, f32), side: PyTeamId, timeout_ms: Option<u64>) -> PyResult<bool> {
+    let request = PhysRequest::PointFree {
+        point,
+        team: side.inner,
     };
-    let req = serde_json::json!({
-        "id": 1u64,
-        "start": [start.0, start.1],
-        "end":   [end.0,   end.1],
-        "team":  team_str,
-    });
+    match bridge::client::query(&request, timeout_ms)? {
+        PhysAnswer::Free { free } => Ok(free),
+        other => Err(bridge::client::unexpected_answer(other)),
+    }
+}
 
-    {
-        let mut w = std::io::BufWriter::new(&stream);
-        writeln!(w, "{}", req).map_err(|e| {
-            pyo3::exceptions::PyRuntimeError::new_err(format!("send physics req: {}", e))
-        })?;
-        let _ = w.flush();
+#[gen_stub_pyfunction]
+#[pyfunction(name = "raycast")]
+#[pyo3(signature = (origin, direction, max_distance, side, timeout_ms=None))]
+/// Casts a ray from `origin` along `direction`.
+///
+/// Returns:
+///  - The distance to the first obstacle, or `None` if nothing is hit within
+///    `max_distance`. This does not include other agents
+pub fn raycast(
+    origin: (f32, f32),
+    direction: (f32, f32),
+    max_distance: f32,
+    side: PyTeamId,
+    timeout_ms: Option<u64>,
+) -> PyResult<Option<f32>> {
+    let request = PhysRequest::Raycast {
+        origin,
+        direction,
+        max_distance,
+        team: side.inner,
+    };
+    match bridge::client::query(&request, timeout_ms)? {
+        PhysAnswer::Distance { distance } => Ok(distance),
+        other => Err(bridge::client::unexpected_answer(other)),
     }
+}
 
-    let mut r = BufReader::new(stream);
-    let mut line = String::new();
-    r.read_line(&mut line).map_err(|_| {
-        pyo3::exceptions::PyTimeoutError::new_err("Timeout waiting for physics response")
-    })?;
-
-    #[derive(serde::Deserialize)]
-    #[allow(dead_code)]
-    struct PhysResp {
-        id: u64,
-        free: bool,
+#[gen_stub_pyfunction]
+#[pyfunction(name = "nearest_obstacle_distance")]
+#[pyo3(signature = (point, side, timeout_ms=None))]
+/// Distance from `point` to the closest obstacle.
+///
+/// Returns:
+///  - The distance, `0.0` if the point is inside an obstacle, or `None` if the
+///    map has no obstacles. This does not include other agents
+pub fn nearest_obstacle_distance(
+    point: (f32, f32),
+    side: PyTeamId,
+    timeout_ms: Option<u64>,
+) -> PyResult<Option<f32>> {
+    let request = PhysRequest::NearestObstacle {
+        point,
+        team: side.inner,
+    };
+    match bridge::client::query(&request, timeout_ms)? {
+        PhysAnswer::Distance { distance } => Ok(distance),
+        other => Err(bridge::client::unexpected_answer(other)),
     }
-    let resp: PhysResp = serde_json::from_str(&line).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("bad physics resp: {}", e))
-    })?;
+}
 
-    Ok(resp.free)
+#[gen_stub_pyfunction]
+#[pyfunction(name = "shortest_path")]
+#[pyo3(signature = (start, end, side, timeout_ms=None))]
+/// Finds the shortest path from `start` to `end` around walls and the
+/// keep-away zones that block `side`. Zones around carried flags are left
+/// out, since they move with the carrier.
+///
+/// Returns:
+///  - The waypoints, starting at `start` and ending at `end`, or `None` if
+///    `end` cannot be reached. This does not include other agents
+///
+/// Raises `RuntimeError` if the simulator does not answer in time.
+pub fn shortest_path(
+    start: (f32, f32),
+    end: (f32, f32),
+    side: PyTeamId,
+    timeout_ms: Option<u64>,
+) -> PyResult<Option<Vec<(f32, f32)>>> {
+    let request = PhysRequest::ShortestPath {
+        start,
+        end,
+        team: side.inner,
+    };
+    match bridge::client::query(&request, timeout_ms)? {
+        PhysAnswer::Path { path } => Ok((!path.is_empty()).then_some(path)),
+        other => Err(bridge::client::unexpected_answer(other)),
+    }
 }
 
 fn force_focus(
@@ -279,16 +454,47 @@ fn force_focus(
 fn _core(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(run, m)?)?;
     m.add_function(wrap_pyfunction!(run_headless, m)?)?;
+    m.add_function(wrap_pyfunction!(play_replay, m)?)?;
     m.add_function(wrap_pyfunction!(segment_is_free, m)?)?;
+    m.add_function(wrap_pyfunction!(segments_are_free, m)?)?;
+    m.add_function(wrap_pyfunction!(point_is_free, m)?)?;
+    m.add_function(wrap_pyfunction!(raycast, m)?)?;
+    m.add_function(wrap_pyfunction!(nearest_obstacle_distance, m)?)?;
+    m.add_function(wrap_pyfunction!(shortest_path, m)?)?;
+    m.add_function(wrap_pyfunction!(tournament::run_tournament, m)?)?;
+    m.add_function(wrap_pyfunction!(read_message, m)?)?;
     m.add_class::<AgentState>()?;
     m.add_class::<GameState>()?;
     m.add_class::<FlagState>()?;
     m.add_class::<CapturePointState>()?;
     m.add_class::<PyConfig>()?;
+    m.add_class::<PyLaunchSpec>()?;
+    m.add_class::<PyPolicyLimits>()?;
+    m.add_class::<PyOverBudget>()?;
+    m.add_class::<PolicyStats>()?;
+    m.add_class::<PyFogOfWar>()?;
+    m.add_class::<PyComms>()?;
+    m.add_class::<PyMessage>()?;
+    m.add_class::<PyUnseenOpponents>()?;
+    m.add_class::<PyGameRules>()?;
+    m.add_class::<PyTaggingMode>()?;
+    m.add_class::<PyTagPenalty>()?;
     m.add_class::<PyFlagStatus>()?;
+    m.add_class::<PyMatchEndReason>()?;
+    m.add_class::<PyMatchOutcome>()?;
     m.add_class::<PyTeamId>()?;
     m.add_class::<PyAction>()?;
     m.add_class::<DefenseBot>()?;
+    m.add_class::<DefenseTask>()?;
+    m.add_class::<PyDefenseRole>()?;
+    m.add_class::<AttackerBot>()?;
+    m.add_class::<RandomBot>()?;
+    m.add_class::<MixedBot>()?;
+    m.add_class::<InterceptorBot>()?;
+    m.add_class::<Env>()?;
+    m.add_class::<Hello>()?;
+    m.add_class::<PyWireFormat>()?;
+    m.add("PROTOCOL_VERSION", PROTOCOL_VERSION)?;
     Ok(())
 }
 
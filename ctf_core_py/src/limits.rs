@@ -0,0 +1,216 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pymethods};
+use serde::{Deserialize, Serialize};
+
+/// What happens to a team whose policy goes over its response budget.
+#[gen_stub_pyclass_enum]
+#[pyclass(name = "OverBudget", eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PyOverBudget {
+    /// Only log it.
+    #[default]
+    Ignore,
+    /// Stop the team's agents and ignore the policy for the rest of the match.
+    Freeze,
+    /// End the match in the other team's favour.
+    Forfeit,
+}
+
+/// Resource limits and time budget for one team's policy process.
+#[gen_stub_pyclass]
+#[pyclass(name = "PolicyLimits")]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PyPolicyLimits {
+    /// Address space cap for the process, in MiB. Unix only.
+    #[pyo3(get, set)]
+    pub memory_mb: Option<u64>,
+
+    /// CPU time cap for the process, in seconds. The process is killed once
+    /// it is used up. Unix only.
+    #[pyo3(get, set)]
+    pub cpu_seconds: Option<u64>,
+
+    /// Milliseconds a policy has to answer a state. Later answers are
+    /// dropped and counted as late.
+    #[pyo3(get, set)]
+    pub tick_deadline_ms: Option<u64>,
+
+    /// Late answers allowed from any one policy process before the team is
+    /// over budget.
+    #[pyo3(get, set)]
+    pub max_late: Option<u32>,
+
+    /// Missed states allowed from any one policy process before the team is
+    /// over budget. A state is missed if the policy is still busy with an
+    /// earlier one, does not answer in time in lockstep mode, or leaves it
+    /// unanswered past the deadline or behind 64 newer states. States sent
+    /// before the policy answers the hello do not count.
+    #[pyo3(get, set)]
+    pub max_missed: Option<u32>,
+
+    /// What happens once the team is over budget.
+    #[pyo3(get, set)]
+    pub on_over_budget: PyOverBudget,
+
+    /// How many times a crashed policy process is restarted before it is
+    /// given up on. The team forfeits once none of its processes is left.
+    #[pyo3(get, set)]
+    pub max_restarts: u32,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyPolicyLimits {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl PyPolicyLimits {
+    pub fn is_over_budget(&self, stats: &PolicyStats) -> bool {
+        self.max_late.is_some_and(|max| stats.late > max)
+            || self.max_missed.is_some_and(|max| stats.missed > max)
+    }
+}
+
+/// Live response counters for one policy process, shared with its IO
+/// threads.
+#[derive(Debug, Default)]
+pub struct PolicyCounters {
+    answered: AtomicU32,
+    late: AtomicU32,
+    missed: AtomicU32,
+    crashes: AtomicU32,
+    /// Latencies of every matched answer, in microseconds.
+    latency_total_us: AtomicU64,
+    latency_max_us: AtomicU64,
+    latency_last_us: AtomicU64,
+}
+
+impl PolicyCounters {
+    /// Records how long a policy took to answer a state, whether or not the
+    /// answer was in time.
+    pub fn record_latency(&self, latency: Duration) {
+        let us = latency.as_micros() as u64;
+        self.latency_total_us.fetch_add(us, Ordering::Relaxed);
+        self.latency_max_us.fetch_max(us, Ordering::Relaxed);
+        self.latency_last_us.store(us, Ordering::Relaxed);
+    }
+
+    pub fn record_answered(&self) {
+        self.answered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_late(&self) {
+        self.late.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_missed(&self) {
+        self.missed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_crash(&self) {
+        self.crashes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self, over_budget: bool) -> PolicyStats {
+        let answered = self.answered.load(Ordering::Relaxed);
+        let late = self.late.load(Ordering::Relaxed);
+        let ms = |us: u64| us as f32 / 1000.0;
+        PolicyStats {
+            answered,
+            late,
+            missed: self.missed.load(Ordering::Relaxed),
+            over_budget,
+            crashes: self.crashes.load(Ordering::Relaxed),
+            mean_latency_ms: ms(self.latency_total_us.load(Ordering::Relaxed))
+                / (answered + late).max(1) as f32,
+            max_latency_ms: ms(self.latency_max_us.load(Ordering::Relaxed)),
+            last_latency_ms: ms(self.latency_last_us.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// How well a team's policy has kept up so far. With per-agent policies,
+/// the counts are summed over the processes.
+#[gen_stub_pyclass]
+#[pyclass(name = "PolicyStats", frozen)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyStats {
+    /// States answered in time.
+    #[pyo3(get)]
+    pub answered: u32,
+
+    /// Answers that came after the deadline and were dropped.
+    #[pyo3(get)]
+    pub late: u32,
+
+    /// States the policy never answered.
+    #[pyo3(get)]
+    pub missed: u32,
+
+    /// Whether the team has gone over its budget.
+    #[pyo3(get)]
+    pub over_budget: bool,
+
+    /// How many times the policy process crashed.
+    #[pyo3(get)]
+    #[serde(default)]
+    pub crashes: u32,
+
+    /// Mean time from sending a state to receiving its answer, in
+    /// milliseconds. Late answers are included.
+    #[pyo3(get)]
+    #[serde(default)]
+    pub mean_latency_ms: f32,
+
+    /// Slowest answer so far, in milliseconds.
+    #[pyo3(get)]
+    #[serde(default)]
+    pub max_latency_ms: f32,
+
+    /// Latency of the most recent answer, in milliseconds. With per-agent
+    /// policies, the slowest of each process's most recent answers.
+    #[pyo3(get)]
+    #[serde(default)]
+    pub last_latency_ms: f32,
+}
+
+impl PolicyStats {
+    /// The stats of two processes of one team taken together.
+    pub fn merge(&self, other: &PolicyStats) -> PolicyStats {
+        let timed = |stats: &PolicyStats| (stats.answered + stats.late) as f32;
+        let total = timed(self) + timed(other);
+        PolicyStats {
+            answered: self.answered + other.answered,
+            late: self.late + other.late,
+            missed: self.missed + other.missed,
+            over_budget: self.over_budget || other.over_budget,
+            crashes: self.crashes + other.crashes,
+            mean_latency_ms: if total > 0.0 {
+                (self.mean_latency_ms * timed(self) + other.mean_latency_ms * timed(other)) / total
+            } else {
+                0.0
+            },
+            max_latency_ms: self.max_latency_ms.max(other.max_latency_ms),
+            last_latency_ms: self.last_latency_ms.max(other.last_latency_ms),
+        }
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PolicyStats {
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
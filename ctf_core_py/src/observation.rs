@@ -0,0 +1,370 @@
+use std::collections::{HashMap, HashSet};
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use ctf_core::{line_of_sight, team::TeamId};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pymethods};
+use serde::{Deserialize, Serialize};
+
+use crate::{agent::AgentState, game::GameState};
+
+/// What a team is told about opponents none of its agents can see.
+#[gen_stub_pyclass_enum]
+#[pyclass(name = "UnseenOpponents", eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PyUnseenOpponents {
+    /// Leave them out of the state.
+    #[default]
+    Omit,
+    /// Report them as last seen, with `last_seen_age` set. Opponents never
+    /// seen are left out.
+    LastKnown,
+}
+
+/// Limits what each team's policy observes to what its agents can see.
+/// Flags carried by opponents out of sight are reported where they were
+/// last seen.
+#[gen_stub_pyclass]
+#[pyclass(name = "FogOfWar")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PyFogOfWar {
+    /// How far each agent sees.
+    #[pyo3(get, set)]
+    pub vision_radius: f32,
+
+    /// Whether walls block sight.
+    #[pyo3(get, set)]
+    pub line_of_sight: bool,
+
+    #[pyo3(get, set)]
+    pub unseen_opponents: PyUnseenOpponents,
+}
+
+impl Default for PyFogOfWar {
+    fn default() -> Self {
+        Self {
+            vision_radius: 20.0,
+            line_of_sight: true,
+            unseen_opponents: PyUnseenOpponents::default(),
+        }
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyFogOfWar {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// An opponent as it was last seen.
+#[derive(Debug, Clone)]
+struct Sighting {
+    state: AgentState,
+    /// Match time of the sighting.
+    seen_at: f32,
+}
+
+/// What a team, or an agent of a team, has seen so far.
+#[derive(Debug, Clone, Default)]
+struct Memory {
+    /// Last sightings by opponent ID.
+    opponents: HashMap<u32, Sighting>,
+    /// Last seen positions by flag ID.
+    flags: HashMap<u32, (f32, f32)>,
+}
+
+/// Filters game states down to each team's or agent's view, remembering
+/// where opponents and flags were last seen.
+#[derive(Debug, Clone)]
+pub struct Observer {
+    fog: PyFogOfWar,
+    /// For each team, or each agent of a team.
+    memories: HashMap<(TeamId, Option<u32>), Memory>,
+}
+
+impl Observer {
+    pub fn new(fog: PyFogOfWar) -> Self {
+        Self {
+            fog,
+            memories: HashMap::new(),
+        }
+    }
+
+    /// `state` as `team` sees it, or as only its agent `agent` sees it.
+    pub fn observe(
+        &mut self,
+        state: &GameState,
+        team: TeamId,
+        agent: Option<u32>,
+        spatial: &SpatialQuery,
+    ) -> GameState {
+        self.observe_with(state, team, agent, |from, to| {
+            line_of_sight(spatial, from, to)
+        })
+    }
+
+    /// Like `observe`, with `clear` telling whether walls leave a line of
+    /// sight between two points.
+    fn observe_with(
+        &mut self,
+        state: &GameState,
+        team: TeamId,
+        agent: Option<u32>,
+        clear: impl Fn(Vec2, Vec2) -> bool,
+    ) -> GameState {
+        let (own, opponents) = match team {
+            TeamId::Red => (&state.red_team, &state.blue_team),
+            TeamId::Blue => (&state.blue_team, &state.red_team),
+        };
+        let own: Vec<AgentState> = own
+            .iter()
+            .filter(|a| agent.is_none_or(|id| a.id == id))
+            .cloned()
+            .collect();
+        let memory = self.memories.entry((team, agent)).or_default();
+
+        let mut observed = Vec::new();
+        let mut seen = HashSet::new();
+        for opponent in opponents {
+            if sees(&self.fog, &own, opponent, &clear) {
+                seen.insert(opponent.id);
+                memory.opponents.insert(
+                    opponent.id,
+                    Sighting {
+                        state: opponent.clone(),
+                        seen_at: state.elapsed,
+                    },
+                );
+                observed.push(opponent.clone());
+            } else if self.fog.unseen_opponents == PyUnseenOpponents::LastKnown {
+                if let Some(sighting) = memory.opponents.get(&opponent.id) {
+                    let mut last_known = sighting.state.clone();
+                    last_known.last_seen_age = Some(state.elapsed - sighting.seen_at);
+                    observed.push(last_known);
+                }
+            }
+        }
+
+        let mut view = state.clone();
+        match team {
+            TeamId::Red => view.blue_team = observed,
+            TeamId::Blue => view.red_team = observed,
+        }
+
+        // a flag moves with its carrier, so it must not give one away
+        for flags in [&mut view.red_flags, &mut view.blue_flags] {
+            flags.retain_mut(|flag| {
+                let hidden = opponents
+                    .iter()
+                    .any(|o| o.carried_flag == Some(flag.id) && !seen.contains(&o.id));
+                if !hidden {
+                    memory.flags.insert(flag.id, flag.position);
+                    return true;
+                }
+                match memory.flags.get(&flag.id) {
+                    Some(position) => {
+                        flag.position = *position;
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+        view
+    }
+}
+
+/// Whether any of `own` agents can see `opponent`.
+fn sees(
+    fog: &PyFogOfWar,
+    own: &[AgentState],
+    opponent: &AgentState,
+    clear: impl Fn(Vec2, Vec2) -> bool,
+) -> bool {
+    let target = Vec2::from(opponent.position);
+    own.iter().any(|agent| {
+        let eye = Vec2::from(agent.position);
+        eye.distance(target) <= fog.vision_radius && (!fog.line_of_sight || clear(eye, target))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ctf_core::flag::FlagStatus;
+
+    use super::*;
+    use crate::flag::FlagState;
+
+    fn agent(id: u32, team: TeamId, position: (f32, f32)) -> AgentState {
+        AgentState {
+            name: format!("agent {id}"),
+            id,
+            team,
+            position,
+            max_speed: 5.0,
+            carried_flag: None,
+            in_home_territory: true,
+            last_seen_age: None,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Red agents at x = 0 and x = 100, blue agents along the x axis.
+    fn state(elapsed: f32, blue: &[(u32, f32)]) -> GameState {
+        GameState {
+            tick: 0,
+            red_score: 0,
+            blue_score: 0,
+            red_team: vec![
+                agent(0, TeamId::Red, (0.0, 0.0)),
+                agent(1, TeamId::Red, (100.0, 0.0)),
+            ],
+            blue_team: blue
+                .iter()
+                .map(|&(id, x)| agent(id, TeamId::Blue, (x, 0.0)))
+                .collect(),
+            red_flags: vec![FlagState {
+                id: 0,
+                name: "red flag".to_string(),
+                team: TeamId::Red,
+                position: (0.0, 0.0),
+                status: FlagStatus::Dropped,
+            }],
+            blue_flags: Vec::new(),
+            num_flags_per_team: 1,
+            red_capture_points: Vec::new(),
+            blue_capture_points: Vec::new(),
+            elapsed,
+            time_remaining: None,
+            outcome: None,
+            red_policy_stats: None,
+            blue_policy_stats: None,
+        }
+    }
+
+    fn ids(agents: &[AgentState]) -> Vec<u32> {
+        agents.iter().map(|a| a.id).collect()
+    }
+
+    /// A wall stands across the x axis between x = 12 and x = 14.
+    fn clear(from: Vec2, to: Vec2) -> bool {
+        from.x.max(to.x) < 12.0 || from.x.min(to.x) > 14.0
+    }
+
+    #[test]
+    fn sees_opponents_in_range_and_in_sight() {
+        let mut observer = Observer::new(PyFogOfWar::default());
+        let view = observer.observe_with(
+            &state(
+                0.0,
+                &[(1000, 10.0), (1001, 15.0), (1002, 50.0), (1003, 90.0)],
+            ),
+            TeamId::Red,
+            None,
+            clear,
+        );
+
+        // 1001 is behind the wall and 1002 out of range of both agents
+        assert_eq!(ids(&view.blue_team), [1000, 1003]);
+        assert_eq!(ids(&view.red_team), [0, 1]);
+    }
+
+    #[test]
+    fn walls_do_not_block_sight_without_line_of_sight() {
+        let fog = PyFogOfWar {
+            line_of_sight: false,
+            ..PyFogOfWar::default()
+        };
+        let mut observer = Observer::new(fog);
+        let view =
+            observer.observe_with(&state(0.0, &[(1001, 15.0)]), TeamId::Red, None, |_, _| {
+                false
+            });
+
+        assert_eq!(ids(&view.blue_team), [1001]);
+    }
+
+    #[test]
+    fn an_agent_sees_only_what_it_sees() {
+        let mut observer = Observer::new(PyFogOfWar::default());
+        let view = observer.observe_with(
+            &state(0.0, &[(1000, 10.0), (1003, 90.0)]),
+            TeamId::Red,
+            Some(1),
+            clear,
+        );
+
+        // it still knows where its teammates are, but not what they see
+        assert_eq!(ids(&view.red_team), [0, 1]);
+        assert_eq!(ids(&view.blue_team), [1003]);
+    }
+
+    #[test]
+    fn unseen_opponents_are_reported_where_last_seen() {
+        let fog = PyFogOfWar {
+            unseen_opponents: PyUnseenOpponents::LastKnown,
+            ..PyFogOfWar::default()
+        };
+        let mut observer = Observer::new(fog);
+        observer.observe_with(&state(1.0, &[(1000, 10.0)]), TeamId::Red, None, clear);
+        let view = observer.observe_with(
+            &state(3.5, &[(1000, 50.0), (1001, 50.0)]),
+            TeamId::Red,
+            None,
+            clear,
+        );
+
+        // 1001 was never seen, so there is nothing to report
+        assert_eq!(ids(&view.blue_team), [1000]);
+        assert_eq!(view.blue_team[0].position, (10.0, 0.0));
+        assert_eq!(view.blue_team[0].last_seen_age, Some(2.5));
+
+        // memories are kept per team and per agent
+        let view = observer.observe_with(&state(3.5, &[(1000, 50.0)]), TeamId::Red, Some(0), clear);
+        assert!(view.blue_team.is_empty());
+    }
+
+    #[test]
+    fn unseen_opponents_are_omitted_by_default() {
+        let mut observer = Observer::new(PyFogOfWar::default());
+        observer.observe_with(&state(1.0, &[(1000, 10.0)]), TeamId::Red, None, clear);
+        let view = observer.observe_with(&state(2.0, &[(1000, 50.0)]), TeamId::Red, None, clear);
+
+        assert!(view.blue_team.is_empty());
+    }
+
+    #[test]
+    fn flags_do_not_give_away_their_carrier() {
+        let mut observer = Observer::new(PyFogOfWar::default());
+        let carried = |x: f32| {
+            let mut state = state(0.0, &[(1000, x)]);
+            state.blue_team[0].carried_flag = Some(0);
+            state.red_flags[0].position = (x, 0.0);
+            state.red_flags[0].status = FlagStatus::PickedUp;
+            state
+        };
+
+        // never seen where it is, so left out
+        let view = observer.observe_with(&carried(50.0), TeamId::Blue, None, clear);
+        assert_eq!(
+            view.red_flags.len(),
+            1,
+            "a team always sees its own carrier"
+        );
+        let view = observer.observe_with(&carried(50.0), TeamId::Red, None, clear);
+        assert!(view.red_flags.is_empty());
+
+        observer.observe_with(&carried(10.0), TeamId::Red, None, clear);
+        let view = observer.observe_with(&carried(50.0), TeamId::Red, None, clear);
+        assert_eq!(view.red_flags[0].position, (10.0, 0.0));
+    }
+}
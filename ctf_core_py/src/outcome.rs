@@ -0,0 +1,57 @@
+use ctf_core::match_state::{MatchEndReason, MatchOutcome};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pymethods};
+
+use crate::team::PyTeamId;
+
+#[gen_stub_pyclass_enum]
+#[pyclass(name = "MatchEndReason", frozen, eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PyMatchEndReason {
+    TimeLimit,
+    ScoreLimit,
+    AllFlagsCaptured,
+    Forfeit,
+}
+
+impl From<MatchEndReason> for PyMatchEndReason {
+    fn from(reason: MatchEndReason) -> Self {
+        match reason {
+            MatchEndReason::TimeLimit => PyMatchEndReason::TimeLimit,
+            MatchEndReason::ScoreLimit => PyMatchEndReason::ScoreLimit,
+            MatchEndReason::AllFlagsCaptured => PyMatchEndReason::AllFlagsCaptured,
+            MatchEndReason::Forfeit => PyMatchEndReason::Forfeit,
+        }
+    }
+}
+
+/// How a finished match ended.
+#[gen_stub_pyclass]
+#[pyclass(name = "MatchOutcome", frozen)]
+#[derive(Debug, Clone)]
+pub struct PyMatchOutcome {
+    pub inner: MatchOutcome,
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyMatchOutcome {
+    /// The winning team, or `None` for a draw.
+    #[getter]
+    fn winner(&self) -> Option<PyTeamId> {
+        self.inner.winner.map(|inner| PyTeamId { inner })
+    }
+
+    /// Why the match ended.
+    #[getter]
+    fn reason(&self) -> PyMatchEndReason {
+        self.inner.reason.into()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MatchOutcome(winner={:?}, reason={:?})",
+            self.inner.winner, self.inner.reason
+        )
+    }
+}
@@ -0,0 +1,74 @@
+//! The built-in policies, and what they need to run in a policy process.
+
+use bevy::math::Vec2;
+use ctf_core::{
+    policy::{Navigator, Policy},
+    team::TeamId,
+};
+
+use crate::{
+    agents::{
+        attacker_bot::AttackerBot, defense_bot::DefenseBot, interceptor_bot::InterceptorBot,
+        mixed_bot::MixedBot, random_bot::RandomBot,
+    },
+    bridge::{
+        client,
+        physics::{PhysAnswer, PhysRequest},
+    },
+    team::PyTeamId,
+};
+
+/// Prefix of the built-in policy names accepted by `Config.red_team_policy`
+/// and `Config.blue_team_policy`.
+pub const BUILTIN_PREFIX: &str = "builtin:";
+
+/// The names of the built-in policies, without the prefix.
+pub const BUILTINS: &[&str] = &[
+    "defense_bot",
+    "attacker_bot",
+    "random_bot",
+    "mixed_bot",
+    "interceptor_bot",
+];
+
+/// Asks the simulator's physics RPC server, for policies running in a
+/// policy process.
+pub struct RpcNavigator;
+
+impl Navigator for RpcNavigator {
+    fn shortest_path(&mut self, start: Vec2, end: Vec2, team: TeamId) -> Option<Vec<Vec2>> {
+        let request = PhysRequest::ShortestPath {
+            start: start.into(),
+            end: end.into(),
+            team,
+        };
+        match client::query(&request, None) {
+            Ok(PhysAnswer::Path { path }) if !path.is_empty() => {
+                Some(path.into_iter().map(Vec2::from).collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The built-in policy called `name`, such as `builtin:defense_bot`, playing
+/// `side`. Random ones are seeded with `seed`, if given.
+pub fn builtin(name: &str, side: TeamId, seed: Option<u64>) -> anyhow::Result<Box<dyn Policy>> {
+    let side = PyTeamId { inner: side };
+    let policy: Box<dyn Policy> = match name.strip_prefix(BUILTIN_PREFIX) {
+        Some("defense_bot") => Box::new(DefenseBot::new(side)),
+        Some("attacker_bot") => Box::new(AttackerBot::new(side)),
+        Some("random_bot") => Box::new(RandomBot::new(side, seed, 1.0)),
+        Some("mixed_bot") => Box::new(MixedBot::new(side)),
+        Some("interceptor_bot") => Box::new(InterceptorBot::new(side)),
+        _ => anyhow::bail!(
+            "unknown built-in policy {name:?}; expected one of {}",
+            BUILTINS
+                .iter()
+                .map(|b| format!("{BUILTIN_PREFIX}{b}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    Ok(policy)
+}
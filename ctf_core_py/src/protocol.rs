@@ -0,0 +1,269 @@
+//! The protocol spoken with policy processes.
+//!
+//! The simulator opens with a [`Hello`] and the policy must answer with a
+//! [`HelloReply`] before any state is sent, both as JSON lines. After that
+//! messages use the wire format they agreed on, every [`GameState`] carries
+//! a tick ID, and the policy answers each one with an [`ActionBatch`]
+//! echoing it.
+
+use ctf_core::{agent::Action, team::TeamId};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyfunction, gen_stub_pymethods};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agent::PyAction,
+    game::GameState,
+    team::PyTeamId,
+    wire::{self, PyWireFormat},
+};
+
+/// Bumped whenever the messages change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Optional features this simulator offers its policies.
+pub const CAPABILITIES: &[&str] = &["tick_ids", "physics_rpc", "msgpack"];
+
+/// A line sent to a policy.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SimMessage {
+    Hello(Hello),
+    State(GameState),
+}
+
+/// A line sent back by a policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PolicyMessage {
+    Hello(HelloReply),
+    Actions(ActionBatch),
+}
+
+/// The first message a policy receives.
+#[gen_stub_pyclass]
+#[pyclass(name = "Hello", frozen)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hello {
+    pub protocol_version: u32,
+    pub capabilities: Vec<String>,
+    pub side: TeamId,
+    /// The wire format the simulator would like to switch to.
+    #[serde(default)]
+    pub wire_format: PyWireFormat,
+    /// The only agent the policy controls, when each agent has its own.
+    #[serde(default)]
+    pub agent: Option<u32>,
+}
+
+impl Hello {
+    pub fn new(side: TeamId, wire_format: PyWireFormat, agent: Option<u32>) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+            side,
+            wire_format,
+            agent,
+        }
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl Hello {
+    /// The protocol version the simulator speaks.
+    #[getter]
+    fn protocol_version(&self) -> u32 {
+        self.protocol_version
+    }
+
+    /// Optional features the simulator offers.
+    #[getter]
+    fn capabilities(&self) -> Vec<String> {
+        self.capabilities.clone()
+    }
+
+    /// The side the policy plays.
+    #[getter]
+    fn side(&self) -> PyTeamId {
+        PyTeamId { inner: self.side }
+    }
+
+    /// The wire format the simulator would like to switch to. Pass it to
+    /// `reply` to accept it.
+    #[getter]
+    fn wire_format(&self) -> PyWireFormat {
+        self.wire_format
+    }
+
+    /// The ID of the only agent the policy controls, or `None` if it
+    /// controls the whole team. Actions for other agents are rejected.
+    #[getter]
+    fn agent(&self) -> Option<u32> {
+        self.agent
+    }
+
+    /// Parses the first line sent by the simulator.
+    #[staticmethod]
+    fn from_json(json_str: &str) -> PyResult<Self> {
+        match serde_json::from_str(json_str) {
+            Ok(SimMessage::Hello(hello)) => Ok(hello),
+            Ok(SimMessage::State(_)) => Err(PyValueError::new_err(
+                "Expected a hello, got a game state. The simulator is older than this package",
+            )),
+            Err(e) => Err(PyValueError::new_err(format!(
+                "Failed to parse hello from JSON: {e}"
+            ))),
+        }
+    }
+
+    /// The line to answer this hello with.
+    ///
+    /// Raises `ValueError` if the simulator speaks a different protocol
+    /// version than this package.
+    ///
+    /// Parameters
+    ///   `wire_format`: The format for all later messages. Either JSON or
+    ///   the one the simulator offered.
+    #[pyo3(signature = (capabilities=Vec::new(), name=None, wire_format=PyWireFormat::Json))]
+    fn reply(
+        &self,
+        capabilities: Vec<String>,
+        name: Option<String>,
+        wire_format: PyWireFormat,
+    ) -> PyResult<String> {
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(PyValueError::new_err(format!(
+                "The simulator speaks protocol v{} but this package speaks v{PROTOCOL_VERSION}",
+                self.protocol_version
+            )));
+        }
+        if wire_format != PyWireFormat::Json && wire_format != self.wire_format {
+            return Err(PyValueError::new_err(format!(
+                "The simulator did not offer {wire_format:?}"
+            )));
+        }
+        let reply = PolicyMessage::Hello(HelloReply {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities,
+            name,
+            wire_format,
+        });
+        Ok(serde_json::to_string(&reply).unwrap())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// A policy's answer to [`Hello`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelloReply {
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Shown in logs.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The wire format the policy accepted.
+    #[serde(default)]
+    pub wire_format: PyWireFormat,
+}
+
+/// The actions a policy chose for the state with tick ID `tick`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionBatch {
+    pub tick: u64,
+    pub actions: Vec<Action>,
+}
+
+impl ActionBatch {
+    pub fn message(tick: u64, actions: Vec<PyAction>) -> PolicyMessage {
+        PolicyMessage::Actions(ActionBatch {
+            tick,
+            actions: actions.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+/// Checks a policy's hello reply against the `offered` hello.
+pub fn check_hello_reply(line: &str, offered: &Hello) -> anyhow::Result<HelloReply> {
+    let reply = match serde_json::from_str(line) {
+        Ok(PolicyMessage::Hello(reply)) => reply,
+        _ => anyhow::bail!(
+            "expected a hello reply, got {:?}. The policy package is older than the simulator",
+            line.trim_end()
+        ),
+    };
+    if reply.protocol_version != PROTOCOL_VERSION {
+        anyhow::bail!(
+            "the policy speaks protocol v{} but the simulator speaks v{PROTOCOL_VERSION}",
+            reply.protocol_version
+        );
+    }
+    if reply.wire_format != PyWireFormat::Json && reply.wire_format != offered.wire_format {
+        anyhow::bail!(
+            "the policy picked {:?}, which was not offered",
+            reply.wire_format
+        );
+    }
+    Ok(reply)
+}
+
+#[gen_stub_pyfunction]
+#[pyfunction]
+#[pyo3(signature = (stream, wire_format=PyWireFormat::Json))]
+/// Reads the next message from the simulator off a binary stream such as
+/// `sys.stdin.buffer`.
+///
+/// Parameters:
+///   - `stream`: Any object with binary `read` and `readline` methods.
+///   - `wire_format`: The agreed format. The hello is always JSON.
+///
+/// Returns:
+///  - A `Hello` or a `GameState`, or `None` at the end of the stream.
+pub fn read_message(
+    py: Python<'_>,
+    stream: &Bound<'_, PyAny>,
+    wire_format: PyWireFormat,
+) -> PyResult<Option<Py<PyAny>>> {
+    let payload: Vec<u8> = match wire_format {
+        PyWireFormat::Json => stream.call_method0("readline")?.extract()?,
+        PyWireFormat::MsgPack => {
+            let len: Vec<u8> = stream.call_method1("read", (4,))?.extract()?;
+            if len.len() < 4 {
+                return Ok(None);
+            }
+            let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+            let payload: Vec<u8> = stream.call_method1("read", (len,))?.extract()?;
+            if payload.len() < len {
+                return Ok(None);
+            }
+            payload
+        }
+    };
+    if payload.is_empty() {
+        return Ok(None);
+    }
+
+    let message = wire::decode::<SimMessage>(wire_format, &payload)
+        .map_err(|e| PyValueError::new_err(format!("Failed to parse message: {e}")))?;
+    Ok(Some(match message {
+        SimMessage::Hello(hello) => Py::new(py, hello)?.into_any(),
+        SimMessage::State(state) => Py::new(py, state)?.into_any(),
+    }))
+}
+
+/// Encodes the answer to the state with tick ID `tick`, framing included.
+pub fn encode_reply<'py>(
+    py: Python<'py>,
+    tick: u64,
+    actions: Vec<PyAction>,
+    wire_format: PyWireFormat,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let buf = wire::encode(wire_format, &ActionBatch::message(tick, actions))
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(PyBytes::new(py, &buf))
+}
@@ -0,0 +1,162 @@
+mod playback;
+mod record;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+
+use ctf_core::agent::Action;
+use ctf_core::map::ArenaMap;
+use ctf_core::team::TeamId;
+use flate2::read::MultiGzDecoder;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::comms::MessageSent;
+use crate::config::PyConfig;
+use crate::game::GameState;
+
+pub use playback::ReplayPlugin;
+pub(crate) use record::AppliedFlagEvents;
+pub use record::RecordingPlugin;
+
+/// Bumped whenever the record layout changes incompatibly.
+const REPLAY_VERSION: u32 = 1;
+
+/// The first bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// One line of a replay file. Replays are written gzipped, as compact JSON
+/// lines; uncompressed files load too. Files start with a single `Header` followed by
+/// the other records in the order they happened.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReplayRecord {
+    Header {
+        version: u32,
+        config: PyConfig,
+        map: ArenaMap,
+    },
+    State {
+        time: f32,
+        state: GameState,
+    },
+    Actions {
+        time: f32,
+        team: TeamId,
+        /// The state the actions answer.
+        #[serde(default)]
+        tick: u64,
+        actions: Vec<Action>,
+    },
+    Event {
+        time: f32,
+        event: ReplayEvent,
+    },
+    Message {
+        time: f32,
+        message: MessageSent,
+    },
+}
+
+/// A flag interaction, with every entity referred to by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayEvent {
+    Pickup {
+        agent: String,
+        flag: String,
+    },
+    Drop {
+        agent: String,
+        flag: String,
+    },
+    Score {
+        agent: String,
+        flag: String,
+        capture_point: String,
+    },
+}
+
+/// A replay file loaded into memory.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub config: PyConfig,
+    pub map: ArenaMap,
+    /// Published states with their match time, in order.
+    pub states: Vec<(f32, GameState)>,
+    /// Flag interactions with their match time, in order.
+    pub events: Vec<(f32, ReplayEvent)>,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> PyResult<Self> {
+        let file = File::open(path).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("open {path}: {e}"))
+        })?;
+        let mut file = BufReader::new(file);
+        let gzipped = file
+            .fill_buf()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("read {path}: {e}")))?
+            .starts_with(&GZIP_MAGIC);
+        let reader: Box<dyn Read> = if gzipped {
+            Box::new(MultiGzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+
+        let mut header = None;
+        let mut states = vec![];
+        let mut events = vec![];
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("read {path}: {e}"))
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: ReplayRecord = serde_json::from_str(&line).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "{path}:{}: bad replay record: {e}",
+                    i + 1
+                ))
+            })?;
+
+            match record {
+                ReplayRecord::Header {
+                    version,
+                    config,
+                    map,
+                } => {
+                    if version != REPLAY_VERSION {
+                        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                            "{path}: unsupported replay version {version}"
+                        )));
+                    }
+                    header = Some((config, map));
+                }
+                ReplayRecord::State { time, state } => states.push((time, state)),
+                ReplayRecord::Event { time, event } => events.push((time, event)),
+                ReplayRecord::Actions { .. } | ReplayRecord::Message { .. } => {}
+            }
+        }
+
+        let Some((config, map)) = header else {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "{path}: missing replay header"
+            )));
+        };
+        if states.is_empty() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "{path}: replay has no states"
+            )));
+        }
+
+        Ok(Self {
+            config,
+            map,
+            states,
+            events,
+        })
+    }
+}
@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use ctf_core::agent::{Agent, AgentGraphicsAssets};
+use ctf_core::flag::{CapturePoint, Flag, FlagCaptureCounts, FlagStatus};
+use ctf_core::match_state::MatchState;
+use ctf_core::team::{Team, TeamId};
+
+use crate::game::GameState;
+
+use super::{Replay, ReplayEvent};
+
+/// How far Left/Right seek, in seconds of match time.
+const SEEK_STEP: f32 = 5.0;
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 16.0;
+
+/// Drives a world built with `CTFPlugin::replay` from a recording.
+///
+/// Space pauses, Left/Right seek and Up/Down halve or double the speed.
+pub struct ReplayPlugin {
+    pub replay: Replay,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let start = self.replay.states[0].0;
+        app.insert_resource(Playback {
+            replay: self.replay.clone(),
+            cursor: start,
+            speed: 1.0,
+            paused: false,
+        });
+
+        app.add_systems(
+            Update,
+            (playback_controls, advance_playback, apply_playback_frame).chain(),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct Playback {
+    replay: Replay,
+    /// Current position in match time.
+    cursor: f32,
+    speed: f32,
+    paused: bool,
+}
+
+impl Playback {
+    fn start(&self) -> f32 {
+        self.replay.states[0].0
+    }
+
+    fn end(&self) -> f32 {
+        self.replay.states[self.replay.states.len() - 1].0
+    }
+
+    /// The last recorded state at or before the cursor.
+    fn current_state(&self) -> &GameState {
+        let states = &self.replay.states;
+        let i = states.partition_point(|(t, _)| *t <= self.cursor);
+        &states[i.saturating_sub(1)].1
+    }
+
+    /// The capture point the flag was last scored at, as of the cursor.
+    fn captured_at(&self, flag_name: &str) -> Option<&str> {
+        self.replay
+            .events
+            .iter()
+            .take_while(|(t, _)| *t <= self.cursor)
+            .filter_map(|(_, event)| match event {
+                ReplayEvent::Score {
+                    flag,
+                    capture_point,
+                    ..
+                } if flag == flag_name => Some(capture_point.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+}
+
+fn playback_controls(keys: Res<ButtonInput<KeyCode>>, mut playback: ResMut<Playback>) {
+    if keys.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        playback.cursor = (playback.cursor - SEEK_STEP).max(playback.start());
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        playback.cursor = (playback.cursor + SEEK_STEP).min(playback.end());
+    }
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        playback.speed = (playback.speed * 2.0).min(MAX_SPEED);
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        playback.speed = (playback.speed / 2.0).max(MIN_SPEED);
+    }
+}
+
+fn advance_playback(time: Res<Time>, mut playback: ResMut<Playback>) {
+    if playback.paused {
+        return;
+    }
+    let cursor = playback.cursor + time.delta_secs() * playback.speed;
+    playback.cursor = cursor.min(playback.end());
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_playback_frame(
+    playback: Res<Playback>,
+    mut agents: Query<
+        (
+            &Name,
+            &Team,
+            &mut Transform,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        (With<Agent>, Without<Flag>),
+    >,
+    mut flags: Query<
+        (&Name, &mut Flag, &mut Transform, &mut Visibility),
+        (Without<Agent>, Without<CapturePoint>),
+    >,
+    capture_points: Query<(&Name, &Transform), (With<CapturePoint>, Without<Agent>, Without<Flag>)>,
+    agent_graphics: Res<AgentGraphicsAssets>,
+    mut scores: ResMut<FlagCaptureCounts>,
+    mut match_state: ResMut<MatchState>,
+) {
+    let state = playback.current_state();
+
+    for (name, Team(team), mut transform, mut material) in &mut agents {
+        let Some(agent) = state
+            .red_team
+            .iter()
+            .chain(&state.blue_team)
+            .find(|a| a.name == name.as_str())
+        else {
+            continue;
+        };
+        transform.translation.x = agent.position.0;
+        transform.translation.z = agent.position.1;
+
+        let handle = match (team, agent.carried_flag.is_some()) {
+            (TeamId::Red, false) => &agent_graphics.red_material,
+            (TeamId::Red, true) => &agent_graphics.red_pickup_material,
+            (TeamId::Blue, false) => &agent_graphics.blue_material,
+            (TeamId::Blue, true) => &agent_graphics.blue_pickup_material,
+        };
+        if material.0 != *handle {
+            material.0 = handle.clone();
+        }
+    }
+
+    for (name, mut flag, mut transform, mut visibility) in &mut flags {
+        let Some(recorded) = state
+            .red_flags
+            .iter()
+            .chain(&state.blue_flags)
+            .find(|f| f.name == name.as_str())
+        else {
+            continue;
+        };
+        flag.status = recorded.status;
+
+        // picked up and captured flags are recorded relative to their parent
+        let position = match recorded.status {
+            FlagStatus::Dropped => Some(recorded.position),
+            FlagStatus::PickedUp => None,
+            FlagStatus::Captured => playback
+                .captured_at(name.as_str())
+                .and_then(|cp| capture_points.iter().find(|(n, _)| n.as_str() == cp))
+                .map(|(_, t)| (t.translation.x, t.translation.z)),
+        };
+        match position {
+            Some((x, z)) => {
+                transform.translation.x = x;
+                transform.translation.z = z;
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+
+    scores.red = state.red_score;
+    scores.blue = state.blue_score;
+    match_state.elapsed = state.elapsed;
+    match_state.outcome = state.outcome;
+}
@@ -0,0 +1,244 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use ctf_core::agent::Agent;
+use ctf_core::core::CTFConfig;
+use ctf_core::flag::{CapturePoint, Flag, FlagStatus};
+use ctf_core::interaction_range::{FlagDropEvent, FlagPickupEvent, FlagScoreEvent};
+use ctf_core::match_state::MatchState;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use pyo3::exceptions::PyIOError;
+use pyo3::prelude::*;
+
+use crate::bridge::policy::{ActionsReceived, GameStatePublished, PolicySet};
+use crate::comms::MessageSent;
+use crate::config::PyConfig;
+
+use super::{ReplayEvent, ReplayRecord, REPLAY_VERSION};
+
+#[derive(Resource)]
+struct Recorder {
+    writer: BufWriter<GzEncoder<File>>,
+    config: PyConfig,
+}
+
+impl Recorder {
+    /// Ends the gzip stream, so that the file can be read back.
+    fn finish(&mut self) {
+        let result = self
+            .writer
+            .flush()
+            .and_then(|_| self.writer.get_mut().try_finish());
+        if let Err(e) = result {
+            warn!("Failed to flush replay file: {e}");
+        }
+    }
+
+    fn write(&mut self, record: &ReplayRecord) {
+        let result = serde_json::to_writer(&mut self.writer, record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(self.writer));
+        if let Err(e) = result {
+            warn!("Failed to write replay record: {e}");
+        }
+    }
+}
+
+/// Also finishes the file of an app that stops without exiting, such as one
+/// unwinding from a panic.
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Writes the published states, the applied actions, team messages and all flag
+/// interactions to a gzipped replay file. Must be added after the policy bridge.
+pub struct RecordingPlugin {
+    file: File,
+    config: PyConfig,
+}
+
+impl RecordingPlugin {
+    /// Creates the replay file at `path`, so a bad path is reported before the
+    /// app is built.
+    pub fn create(path: &str, config: PyConfig) -> PyResult<Self> {
+        let file =
+            File::create(path).map_err(|e| PyIOError::new_err(format!("create {path}: {e}")))?;
+        Ok(Self { file, config })
+    }
+}
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        let file = self
+            .file
+            .try_clone()
+            .expect("Failed to duplicate replay file handle");
+
+        app.insert_resource(Recorder {
+            writer: BufWriter::new(GzEncoder::new(file, Compression::default())),
+            config: self.config.clone(),
+        });
+
+        app.add_systems(PostStartup, write_header);
+        app.add_systems(
+            Update,
+            (
+                record_events,
+                record_states,
+                record_actions,
+                record_messages,
+            )
+                .chain()
+                .after(PolicySet),
+        );
+        app.add_systems(Last, flush_on_exit);
+    }
+}
+
+fn write_header(mut recorder: ResMut<Recorder>, config: Res<CTFConfig>) {
+    let header = ReplayRecord::Header {
+        version: REPLAY_VERSION,
+        config: recorder.config.clone(),
+        map: config.map.clone(),
+    };
+    recorder.write(&header);
+}
+
+fn record_states(
+    mut recorder: ResMut<Recorder>,
+    mut published: EventReader<GameStatePublished>,
+    match_state: Res<MatchState>,
+) {
+    for GameStatePublished(state) in published.read() {
+        recorder.write(&ReplayRecord::State {
+            time: match_state.elapsed,
+            state: state.clone(),
+        });
+    }
+}
+
+fn record_actions(
+    mut recorder: ResMut<Recorder>,
+    mut received: EventReader<ActionsReceived>,
+    match_state: Res<MatchState>,
+) {
+    for ActionsReceived {
+        team,
+        tick,
+        actions,
+    } in received.read()
+    {
+        recorder.write(&ReplayRecord::Actions {
+            time: match_state.elapsed,
+            team: *team,
+            tick: *tick,
+            actions: actions.clone(),
+        });
+    }
+}
+
+fn record_messages(
+    mut recorder: ResMut<Recorder>,
+    mut sent: EventReader<MessageSent>,
+    match_state: Res<MatchState>,
+) {
+    for message in sent.read() {
+        recorder.write(&ReplayRecord::Message {
+            time: match_state.elapsed,
+            message: message.clone(),
+        });
+    }
+}
+
+/// The flag pickups, drops and captures that went through this frame.
+/// Detected ones can still be turned down by the rules when they are applied.
+#[derive(SystemParam)]
+pub(crate) struct AppliedFlagEvents<'w, 's> {
+    pickups: EventReader<'w, 's, FlagPickupEvent>,
+    drops: EventReader<'w, 's, FlagDropEvent>,
+    scores: EventReader<'w, 's, FlagScoreEvent>,
+    names: Query<'w, 's, &'static Name>,
+    agents: Query<'w, 's, &'static Agent>,
+    flags: Query<'w, 's, &'static Flag>,
+    capture_points: Query<'w, 's, Ref<'static, CapturePoint>>,
+}
+
+impl AppliedFlagEvents<'_, '_> {
+    /// The events since the last call, with every entity referred to by name.
+    pub(crate) fn read(&mut self) -> Vec<ReplayEvent> {
+        let Self {
+            pickups,
+            drops,
+            scores,
+            names,
+            agents,
+            flags,
+            capture_points,
+        } = self;
+        let name = |entity: Entity| {
+            names
+                .get(entity)
+                .map(|n| n.as_str().to_string())
+                .unwrap_or_else(|_| format!("{entity}"))
+        };
+
+        let mut events = vec![];
+        for e in pickups.read() {
+            if agents.get(e.agent).is_ok_and(|a| a.flag == Some(e.flag)) {
+                events.push(ReplayEvent::Pickup {
+                    agent: name(e.agent),
+                    flag: name(e.flag),
+                });
+            }
+        }
+        for e in drops.read() {
+            if flags
+                .get(e.flag)
+                .is_ok_and(|f| f.status == FlagStatus::Dropped)
+            {
+                events.push(ReplayEvent::Drop {
+                    agent: name(e.agent),
+                    flag: name(e.flag),
+                });
+            }
+        }
+        for e in scores.read() {
+            let Ok(capture_point) = capture_points.get(e.capture_point) else {
+                continue;
+            };
+            // a capture point already holding a flag turns the score down
+            if let (true, Some(flag)) = (capture_point.is_changed(), capture_point.flag) {
+                events.push(ReplayEvent::Score {
+                    agent: name(e.agent),
+                    flag: name(flag),
+                    capture_point: name(e.capture_point),
+                });
+            }
+        }
+        events
+    }
+}
+
+fn record_events(
+    mut recorder: ResMut<Recorder>,
+    mut events: AppliedFlagEvents,
+    match_state: Res<MatchState>,
+) {
+    for event in events.read() {
+        recorder.write(&ReplayRecord::Event {
+            time: match_state.elapsed,
+            event,
+        });
+    }
+}
+
+fn flush_on_exit(mut exit: EventReader<AppExit>, mut recorder: ResMut<Recorder>) {
+    if exit.read().next().is_some() {
+        recorder.finish();
+    }
+}
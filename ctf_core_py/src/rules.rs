@@ -0,0 +1,186 @@
+use ctf_core::rules::GameRules;
+use ctf_core::territory::{TagPenalty, TaggingMode};
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyclass_enum, gen_stub_pymethods};
+use serde::{Deserialize, Serialize};
+
+/// Who can be tagged, and where.
+#[gen_stub_pyclass_enum]
+#[pyclass(name = "TaggingMode", eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PyTaggingMode {
+    /// Flag carriers are tagged by any opponent, anywhere on the map.
+    Legacy,
+    /// Any intruder is tagged by a defender on the defender's home side.
+    Territory,
+}
+
+impl From<TaggingMode> for PyTaggingMode {
+    fn from(mode: TaggingMode) -> Self {
+        match mode {
+            TaggingMode::Legacy => PyTaggingMode::Legacy,
+            TaggingMode::Territory => PyTaggingMode::Territory,
+        }
+    }
+}
+
+impl From<PyTaggingMode> for TaggingMode {
+    fn from(mode: PyTaggingMode) -> Self {
+        match mode {
+            PyTaggingMode::Legacy => TaggingMode::Legacy,
+            PyTaggingMode::Territory => TaggingMode::Territory,
+        }
+    }
+}
+
+/// What happens to an agent tagged in `TaggingMode.Territory`.
+#[gen_stub_pyclass_enum]
+#[pyclass(name = "TagPenalty", eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PyTagPenalty {
+    /// Stop in place for the penalty time.
+    Freeze,
+    /// Move to the team's jail and stay there for the penalty time.
+    Jail,
+    /// Move back to the agent's spawn position.
+    Respawn,
+}
+
+impl From<TagPenalty> for PyTagPenalty {
+    fn from(penalty: TagPenalty) -> Self {
+        match penalty {
+            TagPenalty::Freeze => PyTagPenalty::Freeze,
+            TagPenalty::Jail => PyTagPenalty::Jail,
+            TagPenalty::Respawn => PyTagPenalty::Respawn,
+        }
+    }
+}
+
+impl From<PyTagPenalty> for TagPenalty {
+    fn from(penalty: PyTagPenalty) -> Self {
+        match penalty {
+            PyTagPenalty::Freeze => TagPenalty::Freeze,
+            PyTagPenalty::Jail => TagPenalty::Jail,
+            PyTagPenalty::Respawn => TagPenalty::Respawn,
+        }
+    }
+}
+
+/// Tunable gameplay constants. Defaults match the built-in game. Speeds and
+/// tag and flag radii must be positive, and times must not be negative.
+#[gen_stub_pyclass]
+#[pyclass(name = "GameRules")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PyGameRules {
+    /// Speed of an agent that is not carrying a flag.
+    #[pyo3(get, set)]
+    pub agent_default_speed: f32,
+
+    /// Speed of an agent carrying a flag.
+    #[pyo3(get, set)]
+    pub agent_flag_speed: f32,
+
+    /// Seconds an agent is stunned after being tagged.
+    #[pyo3(get, set)]
+    pub agent_cooldown_time: f32,
+
+    /// Distance at which an opponent tags a flag carrier.
+    #[pyo3(get, set)]
+    pub agent_tag_radius: f32,
+
+    /// Seconds before a dropped flag can be picked up again.
+    #[pyo3(get, set)]
+    pub flag_cooldown_time: f32,
+
+    /// Distance at which an agent picks up a flag.
+    #[pyo3(get, set)]
+    pub flag_interaction_radius: f32,
+
+    /// Distance a dropped flag is thrown from its carrier.
+    #[pyo3(get, set)]
+    pub flag_spawn_radius: f32,
+
+    /// Radius around flags and capture points that blocks their own team.
+    #[pyo3(get, set)]
+    pub keep_away_radius: f32,
+
+    /// Seconds of game time after which the match ends. Unlimited if unset.
+    #[pyo3(get, set)]
+    pub time_limit: Option<f32>,
+
+    /// Captures needed to win the match. Unlimited if unset.
+    #[pyo3(get, set)]
+    pub score_to_win: Option<u32>,
+
+    /// Who can be tagged, and where.
+    #[pyo3(get, set)]
+    pub tagging: PyTaggingMode,
+
+    /// What happens to an agent tagged in `TaggingMode.Territory`.
+    #[pyo3(get, set)]
+    pub tag_penalty: PyTagPenalty,
+
+    /// Seconds a tagged agent stays frozen or jailed.
+    #[pyo3(get, set)]
+    pub tag_penalty_time: f32,
+}
+
+impl Default for PyGameRules {
+    fn default() -> Self {
+        GameRules::default().into()
+    }
+}
+
+#[gen_stub_pymethods]
+#[pymethods]
+impl PyGameRules {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl From<GameRules> for PyGameRules {
+    fn from(rules: GameRules) -> Self {
+        Self {
+            agent_default_speed: rules.agent_default_speed,
+            agent_flag_speed: rules.agent_flag_speed,
+            agent_cooldown_time: rules.agent_cooldown_time,
+            agent_tag_radius: rules.agent_tag_radius,
+            flag_cooldown_time: rules.flag_cooldown_time,
+            flag_interaction_radius: rules.flag_interaction_radius,
+            flag_spawn_radius: rules.flag_spawn_radius,
+            keep_away_radius: rules.keep_away_radius,
+            time_limit: rules.time_limit,
+            score_to_win: rules.score_to_win,
+            tagging: rules.tagging.into(),
+            tag_penalty: rules.tag_penalty.into(),
+            tag_penalty_time: rules.tag_penalty_time,
+        }
+    }
+}
+
+impl From<&PyGameRules> for GameRules {
+    fn from(rules: &PyGameRules) -> Self {
+        Self {
+            agent_default_speed: rules.agent_default_speed,
+            agent_flag_speed: rules.agent_flag_speed,
+            agent_cooldown_time: rules.agent_cooldown_time,
+            agent_tag_radius: rules.agent_tag_radius,
+            flag_cooldown_time: rules.flag_cooldown_time,
+            flag_interaction_radius: rules.flag_interaction_radius,
+            flag_spawn_radius: rules.flag_spawn_radius,
+            keep_away_radius: rules.keep_away_radius,
+            time_limit: rules.time_limit,
+            score_to_win: rules.score_to_win,
+            tagging: rules.tagging.into(),
+            tag_penalty: rules.tag_penalty.into(),
+            tag_penalty_time: rules.tag_penalty_time,
+        }
+    }
+}
@@ -26,10 +26,7 @@ impl PyTeamId {
 
     pub fn other(&self) -> PyTeamId {
         PyTeamId {
-            inner: match self.inner {
-                team::TeamId::Red => team::TeamId::Blue,
-                team::TeamId::Blue => team::TeamId::Red,
-            },
+            inner: self.inner.other(),
         }
     }
 
@@ -0,0 +1,129 @@
+mod ratings;
+mod runner;
+
+use ctf_core::rules::GameRules;
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::gen_stub_pyfunction;
+use serde::{Deserialize, Serialize};
+
+use crate::config::PyConfig;
+use crate::launch::PyLaunchSpec;
+
+pub use ratings::*;
+pub use runner::*;
+
+pub const DEFAULT_ELO: f32 = 1500.0;
+pub const DEFAULT_ELO_K: f32 = 32.0;
+
+/// A policy taking part in a tournament.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entrant {
+    pub name: String,
+    pub launch: PyLaunchSpec,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TournamentConfig {
+    pub entrants: Vec<Entrant>,
+    /// Settings shared by every match. Launch specs are overridden per
+    /// match and `fast_forward` is always on.
+    #[serde(default)]
+    pub config: PyConfig,
+    /// How many times every pairing is played on each side.
+    #[serde(default = "default_rounds")]
+    pub rounds: u32,
+    /// Matches run in parallel. Defaults to the number of CPUs.
+    #[serde(default)]
+    pub workers: Option<usize>,
+    /// Frames a match may run before it is aborted. Each frame advances
+    /// `1 / rate_hz` seconds of match time.
+    #[serde(default)]
+    pub max_ticks: Option<u64>,
+    #[serde(default = "default_elo_k")]
+    pub elo_k: f32,
+    /// Where to write the results as JSON.
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+fn default_rounds() -> u32 {
+    1
+}
+
+fn default_elo_k() -> f32 {
+    DEFAULT_ELO_K
+}
+
+impl TournamentConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.entrants.len() < 2 {
+            anyhow::bail!("a tournament needs at least two entrants");
+        }
+        for (i, entrant) in self.entrants.iter().enumerate() {
+            if entrant.launch.program.is_empty() {
+                anyhow::bail!("entrant {} has no program to launch", entrant.name);
+            }
+            if self.entrants[..i].iter().any(|e| e.name == entrant.name) {
+                anyhow::bail!("entrant name {} is used twice", entrant.name);
+            }
+        }
+        GameRules::from(&self.config.rules).validate()?;
+        // a score to win alone is never reached by policies that cannot score
+        if self.config.rules.time_limit.is_none() && self.max_ticks.is_none() {
+            anyhow::bail!(
+                "matches need a time limit or a maximum number of ticks, or they may never end"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[gen_stub_pyfunction]
+#[pyfunction(name = "run_tournament")]
+#[pyo3(signature = (entrants, config, rounds=1, workers=None, output=None, max_ticks=None))]
+/// Plays every pair of `entrants` against each other `rounds` times on each
+/// side, in parallel headless worlds.
+///
+/// Parameters:
+///   - `entrants`: List of (name, launch spec) pairs.
+///   - `config`: Settings shared by every match. It must set
+///     `rules.time_limit` unless `max_ticks` is given.
+///   - `workers`: Matches run in parallel. Defaults to the number of CPUs.
+///   - `output`: Also write the results to this JSON file.
+///   - `max_ticks`: Abort a match after this many frames.
+///
+/// Returns:
+///  - A dict with the `standings`, sorted by Elo rating, and every `matches` result.
+pub fn run_tournament<'py>(
+    py: Python<'py>,
+    entrants: Vec<(String, PyLaunchSpec)>,
+    config: PyConfig,
+    rounds: u32,
+    workers: Option<usize>,
+    output: Option<String>,
+    max_ticks: Option<u64>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let tournament = TournamentConfig {
+        entrants: entrants
+            .into_iter()
+            .map(|(name, launch)| Entrant { name, launch })
+            .collect(),
+        config,
+        rounds,
+        workers,
+        max_ticks,
+        elo_k: DEFAULT_ELO_K,
+        output,
+    };
+    tournament
+        .validate()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+
+    let results = py
+        .detach(|| run(&tournament))
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    let json = serde_json::to_string(&results)
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+
+    py.import("json")?.call_method1("loads", (json,))
+}
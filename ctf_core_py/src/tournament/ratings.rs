@@ -0,0 +1,235 @@
+use ctf_core::match_state::MatchEndReason;
+use serde::{Deserialize, Serialize};
+
+use crate::bridge::policy::PolicyCrash;
+
+use super::{Entrant, DEFAULT_ELO};
+
+/// The result of one tournament match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchResult {
+    /// Position in the schedule.
+    pub index: usize,
+    pub red: String,
+    pub blue: String,
+    pub red_score: u32,
+    pub blue_score: u32,
+    /// The winning entrant, or `None` for a draw or an aborted match.
+    pub winner: Option<String>,
+    pub reason: Option<MatchEndReason>,
+    /// Why the match was aborted. Aborted matches do not count towards the
+    /// standings.
+    pub error: Option<String>,
+    /// Policy crashes during the match, restarted or not.
+    #[serde(default)]
+    pub crashes: Vec<PolicyCrash>,
+}
+
+impl MatchResult {
+    /// Red's result: 1 for a win, 0.5 for a draw and 0 for a loss.
+    fn red_points(&self) -> f32 {
+        match &self.winner {
+            Some(winner) if *winner == self.red => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Standing {
+    pub name: String,
+    pub rating: f32,
+    pub played: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub aborted: u32,
+    /// Flags captured by this entrant.
+    pub captures_for: u32,
+    /// Flags captured against this entrant.
+    pub captures_against: u32,
+}
+
+impl Standing {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rating: DEFAULT_ELO,
+            played: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            aborted: 0,
+            captures_for: 0,
+            captures_against: 0,
+        }
+    }
+
+    fn record(&mut self, points: f32, captures_for: u32, captures_against: u32) {
+        self.played += 1;
+        match points {
+            p if p > 0.5 => self.wins += 1,
+            p if p < 0.5 => self.losses += 1,
+            _ => self.draws += 1,
+        }
+        self.captures_for += captures_for;
+        self.captures_against += captures_against;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TournamentResults {
+    /// Sorted by rating, best first.
+    pub standings: Vec<Standing>,
+    /// In schedule order.
+    pub matches: Vec<MatchResult>,
+}
+
+impl TournamentResults {
+    /// Rates the entrants by applying Elo updates for `matches` in schedule
+    /// order, so the ratings do not depend on which match finished first.
+    pub fn from_matches(entrants: &[Entrant], mut matches: Vec<MatchResult>, elo_k: f32) -> Self {
+        matches.sort_by_key(|m| m.index);
+        let mut standings: Vec<Standing> =
+            entrants.iter().map(|e| Standing::new(&e.name)).collect();
+        let position = |name: &str| entrants.iter().position(|e| e.name == name);
+
+        for result in &matches {
+            let (Some(red), Some(blue)) = (position(&result.red), position(&result.blue)) else {
+                continue;
+            };
+            if result.error.is_some() {
+                standings[red].aborted += 1;
+                standings[blue].aborted += 1;
+                continue;
+            }
+
+            let red_points = result.red_points();
+            let expected = expected_score(standings[red].rating, standings[blue].rating);
+            let delta = elo_k * (red_points - expected);
+            standings[red].rating += delta;
+            standings[blue].rating -= delta;
+
+            standings[red].record(red_points, result.red_score, result.blue_score);
+            standings[blue].record(1.0 - red_points, result.blue_score, result.red_score);
+        }
+
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        Self { standings, matches }
+    }
+}
+
+/// The score a player rated `rating` is expected to get against `opponent`.
+fn expected_score(rating: f32, opponent: f32) -> f32 {
+    1.0 / (1.0 + 10f32.powf((opponent - rating) / 400.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::launch::PyLaunchSpec;
+
+    use super::*;
+
+    fn entrants(names: &[&str]) -> Vec<Entrant> {
+        names
+            .iter()
+            .map(|name| Entrant {
+                name: name.to_string(),
+                launch: PyLaunchSpec::launch_team("python"),
+            })
+            .collect()
+    }
+
+    fn result(index: usize, red: &str, blue: &str, winner: Option<&str>) -> MatchResult {
+        MatchResult {
+            index,
+            red: red.to_string(),
+            blue: blue.to_string(),
+            red_score: u32::from(winner == Some(red)),
+            blue_score: u32::from(winner == Some(blue)),
+            winner: winner.map(String::from),
+            reason: None,
+            error: None,
+            crashes: Vec::new(),
+        }
+    }
+
+    fn rating(results: &TournamentResults, name: &str) -> f32 {
+        results
+            .standings
+            .iter()
+            .find(|s| s.name == name)
+            .unwrap()
+            .rating
+    }
+
+    #[test]
+    fn even_players_trade_half_of_k() {
+        let results = TournamentResults::from_matches(
+            &entrants(&["a", "b"]),
+            vec![result(0, "a", "b", Some("a"))],
+            32.0,
+        );
+
+        assert_eq!(rating(&results, "a"), DEFAULT_ELO + 16.0);
+        assert_eq!(rating(&results, "b"), DEFAULT_ELO - 16.0);
+        assert_eq!(results.standings[0].name, "a");
+        assert_eq!(
+            (results.standings[0].wins, results.standings[1].losses),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn a_draw_between_even_players_changes_nothing() {
+        let results = TournamentResults::from_matches(
+            &entrants(&["a", "b"]),
+            vec![result(0, "a", "b", None)],
+            32.0,
+        );
+
+        assert_eq!(rating(&results, "a"), DEFAULT_ELO);
+        assert_eq!(rating(&results, "b"), DEFAULT_ELO);
+        assert!(results.standings.iter().all(|s| s.draws == 1));
+    }
+
+    #[test]
+    fn ratings_follow_schedule_order_and_skip_aborted_matches() {
+        let mut aborted = result(1, "b", "c", Some("b"));
+        aborted.error = Some("no result within 10 ticks".to_string());
+        let matches = vec![
+            result(2, "c", "a", Some("c")),
+            aborted,
+            result(0, "a", "b", Some("a")),
+        ];
+        let mut reversed = matches.clone();
+        reversed.reverse();
+
+        let results = TournamentResults::from_matches(&entrants(&["a", "b", "c"]), matches, 32.0);
+        let again = TournamentResults::from_matches(&entrants(&["a", "b", "c"]), reversed, 32.0);
+
+        assert_eq!(
+            results.matches.iter().map(|m| m.index).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        for name in ["a", "b", "c"] {
+            assert_eq!(rating(&results, name), rating(&again, name));
+        }
+        // a beat b as an even match, then lost to c while rated higher
+        let a_after_loss =
+            DEFAULT_ELO + 16.0 - 32.0 * expected_score(DEFAULT_ELO + 16.0, DEFAULT_ELO);
+        assert!((rating(&results, "a") - a_after_loss).abs() < 1e-3);
+        assert_eq!(rating(&results, "b"), DEFAULT_ELO - 16.0);
+        let b = results.standings.iter().find(|s| s.name == "b").unwrap();
+        assert_eq!((b.played, b.aborted), (1, 1));
+    }
+
+    #[test]
+    fn expected_scores_are_complementary() {
+        let stronger = expected_score(1700.0, 1500.0);
+        assert!(stronger > 0.5);
+        assert!((stronger + expected_score(1500.0, 1700.0) - 1.0).abs() < 1e-6);
+        assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+    }
+}
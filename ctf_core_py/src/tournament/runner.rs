@@ -0,0 +1,201 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use avian3d::prelude::*;
+use bevy::app::PluginsState;
+use bevy::prelude::*;
+use ctf_core::flag::FlagCaptureCounts;
+use ctf_core::map::ArenaMap;
+use ctf_core::match_state::{MatchOutcome, MatchState};
+use ctf_core::team::TeamId;
+
+use crate::bridge::physics::{PhysicsRpcServerPlugin, PythonPhysicsBridgePlugin};
+use crate::bridge::policy::{PolicyCrash, PolicyCrashes, PythonPolicyBridgePlugin};
+use crate::config::PyConfig;
+use crate::headless_app;
+
+use super::{Entrant, MatchResult, TournamentConfig, TournamentResults};
+
+/// Every ordered pair of entrants, once per round, as `(red, blue)` indices.
+pub fn schedule(entrants: usize, rounds: u32) -> Vec<(usize, usize)> {
+    let mut pairings = Vec::new();
+    for _ in 0..rounds {
+        for a in 0..entrants {
+            for b in a + 1..entrants {
+                pairings.push((a, b));
+                pairings.push((b, a));
+            }
+        }
+    }
+    pairings
+}
+
+/// Plays the whole schedule on a pool of headless worlds and writes the
+/// results to `tournament.output`, if set.
+pub fn run(tournament: &TournamentConfig) -> anyhow::Result<TournamentResults> {
+    tournament.validate()?;
+    let map = tournament.config.try_load_map()?;
+
+    let pairings = schedule(tournament.entrants.len(), tournament.rounds);
+    let workers = tournament
+        .workers
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+        .clamp(1, pairings.len().max(1));
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(pairings.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&(red, blue)) = pairings.get(index) else {
+                    break;
+                };
+                let result = play_match(
+                    index,
+                    tournament,
+                    &map,
+                    &tournament.entrants[red],
+                    &tournament.entrants[blue],
+                );
+                info!(
+                    "Match {}/{}: {} (red) {} - {} {} (blue){}",
+                    index + 1,
+                    pairings.len(),
+                    result.red,
+                    result.red_score,
+                    result.blue_score,
+                    result.blue,
+                    result
+                        .error
+                        .as_ref()
+                        .map(|e| format!(" aborted: {e}"))
+                        .unwrap_or_default(),
+                );
+                results.lock().unwrap().push(result);
+            });
+        }
+    });
+
+    let matches = results.into_inner().unwrap();
+    let results = TournamentResults::from_matches(&tournament.entrants, matches, tournament.elo_k);
+
+    if let Some(path) = &tournament.output {
+        std::fs::write(path, serde_json::to_string_pretty(&results)?)?;
+    }
+    Ok(results)
+}
+
+fn play_match(
+    index: usize,
+    tournament: &TournamentConfig,
+    map: &ArenaMap,
+    red: &Entrant,
+    blue: &Entrant,
+) -> MatchResult {
+    let base = &tournament.config;
+    let mut config = base.clone();
+    config.red_team_launch = Some(red.launch.clone());
+    config.blue_team_launch = Some(blue.launch.clone());
+    config.red_team_policy = None;
+    config.blue_team_policy = None;
+    config.fast_forward = true;
+    config.time_scale = None;
+    config.record_path = None;
+    // vary the seed per match, but keep the tournament reproducible
+    config.seed = base.seed.map(|seed| seed.wrapping_add(index as u64));
+
+    let mut result = MatchResult {
+        index,
+        red: red.name.clone(),
+        blue: blue.name.clone(),
+        red_score: 0,
+        blue_score: 0,
+        winner: None,
+        reason: None,
+        error: None,
+        crashes: Vec::new(),
+    };
+
+    // policy plugins panic if a command cannot be started
+    let played = catch_unwind(AssertUnwindSafe(|| {
+        simulate(config, map.clone(), tournament.max_ticks)
+    }))
+    .unwrap_or_else(|_| Err("match panicked".to_string()));
+
+    match played {
+        Ok(Played {
+            outcome,
+            scores: (red_score, blue_score),
+            crashes,
+        }) => {
+            result.red_score = red_score;
+            result.blue_score = blue_score;
+            result.winner = outcome.winner.map(|team| match team {
+                TeamId::Red => red.name.clone(),
+                TeamId::Blue => blue.name.clone(),
+            });
+            result.reason = Some(outcome.reason);
+            result.crashes = crashes;
+        }
+        Err(error) => result.error = Some(error),
+    }
+    result
+}
+
+/// What [`simulate`] reports about a finished match.
+struct Played {
+    outcome: MatchOutcome,
+    /// Red's and blue's captures.
+    scores: (u32, u32),
+    crashes: Vec<PolicyCrash>,
+}
+
+/// Runs one headless match to completion, or for at most `max_ticks` frames.
+fn simulate(config: PyConfig, map: ArenaMap, max_ticks: Option<u64>) -> Result<Played, String> {
+    let mut app = headless_app();
+    app.add_plugins((
+        PhysicsPlugins::default(),
+        config.ctf_plugin_with_map(map, true),
+        PythonPhysicsBridgePlugin,
+        // matches share the process, so none of them is its default server
+        PhysicsRpcServerPlugin {
+            process_default: false,
+        },
+    ));
+    app.add_plugins(PythonPolicyBridgePlugin {
+        config,
+        test_harness: None,
+    });
+
+    while app.plugins_state() == PluginsState::Adding {
+        bevy::tasks::tick_global_task_pools_on_main_thread();
+    }
+    app.finish();
+    app.cleanup();
+
+    let mut ticks = 0;
+    loop {
+        if max_ticks.is_some_and(|max| ticks >= max) {
+            return Err(format!("no result within {ticks} ticks"));
+        }
+        ticks += 1;
+        app.update();
+
+        let world = app.world();
+        if let Some(outcome) = world.resource::<MatchState>().outcome {
+            let scores = world.resource::<FlagCaptureCounts>();
+            return Ok(Played {
+                outcome,
+                scores: (scores.red, scores.blue),
+                crashes: world.resource::<PolicyCrashes>().0.clone(),
+            });
+        }
+        if app.should_exit().is_some() {
+            return Err("a policy disconnected".to_string());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Framing for the messages exchanged with policy processes.
+//!
+//! JSON messages are newline-terminated lines. MessagePack messages are
+//! prefixed with their length as a little-endian `u32`.
+
+use std::io::{self, BufRead, Read, Write};
+
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::gen_stub_pyclass_enum;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// How messages to and from policies are encoded after the handshake, which
+/// is always JSON.
+#[gen_stub_pyclass_enum]
+#[pyclass(name = "WireFormat", eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PyWireFormat {
+    /// One JSON object per line.
+    #[default]
+    Json,
+    /// Length-prefixed MessagePack, much cheaper to encode and parse.
+    MsgPack,
+}
+
+/// Largest message read from a policy, in bytes. Anything bigger is
+/// treated as corrupt rather than allocated.
+pub const MAX_MESSAGE_BYTES: usize = 4 << 20;
+
+fn too_large(len: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("a {len} byte message is over the limit of {MAX_MESSAGE_BYTES}"),
+    )
+}
+
+fn invalid_data(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Encodes `message` as one framed message.
+pub fn encode<T: Serialize>(format: PyWireFormat, message: &T) -> io::Result<Vec<u8>> {
+    match format {
+        PyWireFormat::Json => {
+            let mut buf = serde_json::to_vec(message).map_err(invalid_data)?;
+            buf.push(b'\n');
+            Ok(buf)
+        }
+        PyWireFormat::MsgPack => {
+            // named fields, so internally tagged enums can be read back
+            let payload = rmp_serde::to_vec_named(message).map_err(invalid_data)?;
+            let len = u32::try_from(payload.len()).map_err(invalid_data)?;
+            let mut buf = Vec::with_capacity(4 + payload.len());
+            buf.extend_from_slice(&len.to_le_bytes());
+            buf.extend_from_slice(&payload);
+            Ok(buf)
+        }
+    }
+}
+
+/// Decodes one message without its framing.
+pub fn decode<T: DeserializeOwned>(format: PyWireFormat, payload: &[u8]) -> io::Result<T> {
+    match format {
+        PyWireFormat::Json => serde_json::from_slice(payload).map_err(invalid_data),
+        PyWireFormat::MsgPack => rmp_serde::from_slice(payload).map_err(invalid_data),
+    }
+}
+
+pub fn write_message<T: Serialize>(
+    writer: &mut impl Write,
+    format: PyWireFormat,
+    message: &T,
+) -> io::Result<()> {
+    writer.write_all(&encode(format, message)?)?;
+    writer.flush()
+}
+
+/// Reads one message, or `None` at the end of the stream.
+pub fn read_message<T: DeserializeOwned>(
+    reader: &mut impl BufRead,
+    format: PyWireFormat,
+) -> io::Result<Option<T>> {
+    let mut payload = Vec::new();
+    match format {
+        PyWireFormat::Json => {
+            let limit = MAX_MESSAGE_BYTES as u64 + 1;
+            if reader
+                .by_ref()
+                .take(limit)
+                .read_until(b'\n', &mut payload)?
+                == 0
+            {
+                return Ok(None);
+            }
+            if payload.len() > MAX_MESSAGE_BYTES {
+                return Err(too_large(payload.len()));
+            }
+        }
+        PyWireFormat::MsgPack => {
+            let mut len = [0; 4];
+            match reader.read_exact(&mut len) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            }
+            let len = u32::from_le_bytes(len) as usize;
+            if len > MAX_MESSAGE_BYTES {
+                return Err(too_large(len));
+            }
+            payload.resize(len, 0);
+            reader.read_exact(&mut payload)?;
+        }
+    }
+    decode(format, &payload).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bevy::math::Vec2;
+    use ctf_core::agent::Action;
+
+    use super::*;
+    use crate::protocol::{ActionBatch, PolicyMessage};
+
+    fn batch() -> PolicyMessage {
+        PolicyMessage::Actions(ActionBatch {
+            tick: 7,
+            actions: vec![Action::Move {
+                id: 1001,
+                velocity: Vec2::new(1.5, -2.0),
+                message: Some("go left".to_string()),
+            }],
+        })
+    }
+
+    #[test]
+    fn round_trips_in_both_formats() {
+        for format in [PyWireFormat::Json, PyWireFormat::MsgPack] {
+            let mut buf = encode(format, &batch()).unwrap();
+            buf.extend(encode(format, &batch()).unwrap());
+            let mut reader = Cursor::new(buf);
+
+            for _ in 0..2 {
+                let message = read_message::<PolicyMessage>(&mut reader, format).unwrap();
+                let Some(PolicyMessage::Actions(ActionBatch { tick, actions })) = message else {
+                    panic!("{format:?}: expected an action batch, got {message:?}");
+                };
+                assert_eq!(tick, 7);
+                let PolicyMessage::Actions(expected) = batch() else {
+                    unreachable!();
+                };
+                assert_eq!(actions, expected.actions);
+            }
+            let end = read_message::<PolicyMessage>(&mut reader, format).unwrap();
+            assert!(end.is_none(), "{format:?}: expected the end of the stream");
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_msgpack_frame_before_reading_it() {
+        let len = (MAX_MESSAGE_BYTES as u32 + 1).to_le_bytes();
+        let mut reader = Cursor::new(len.to_vec());
+
+        let err = read_message::<PolicyMessage>(&mut reader, PyWireFormat::MsgPack).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_oversized_json_line() {
+        let mut line = vec![b' '; MAX_MESSAGE_BYTES + 10];
+        line.push(b'\n');
+        let mut reader = Cursor::new(line);
+
+        let err = read_message::<PolicyMessage>(&mut reader, PyWireFormat::Json).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
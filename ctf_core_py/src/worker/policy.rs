@@ -1,114 +1,425 @@
 use std::{
-    io::{BufRead, BufReader},
+    collections::VecDeque,
+    io::{BufRead, BufReader, ErrorKind},
+    net::SocketAddr,
     process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
-use bevy::log::warn;
-use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use bevy::log::{info, warn};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError};
 
-use crate::{config::PyConfig, game::GameState, team::PyTeamId};
-use ctf_core::{agent::Action, team::TeamId};
+use crate::{
+    config::PyConfig,
+    game::GameState,
+    limits::{PolicyCounters, PyPolicyLimits},
+    protocol::{
+        check_hello_reply, ActionBatch, Hello, HelloReply, PolicyMessage, SimMessage,
+        PROTOCOL_VERSION,
+    },
+    wire::{self, PyWireFormat},
+};
+use ctf_core::team::TeamId;
+
+/// How long a policy has to answer the hello after it starts.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Lines of a crashed policy's stderr kept for the crash report.
+const STDERR_TAIL_LINES: usize = 20;
+/// States kept awaiting an answer. Older ones are counted as missed.
+const MAX_PENDING: usize = 64;
 
 pub struct PolicyBridge {
     pub tx_state: Option<Sender<GameState>>,
-    pub rx_action: Receiver<Vec<Action>>,
+    pub rx_action: Receiver<ActionBatch>,
+    /// Receives a report if the current process fails.
+    pub rx_exit: Receiver<PolicyExit>,
+    /// This process's response counters. They carry over restarts.
+    pub counters: Arc<PolicyCounters>,
+    /// The only agent this process controls, if each agent has its own.
+    pub agent: Option<u32>,
+    side: TeamId,
+    slot: Option<usize>,
+    config: PyConfig,
+    physics_addr: Option<SocketAddr>,
+    /// How many times the process was restarted after a crash.
+    pub restarts: u32,
+    /// Set once the policy crashed for good.
+    pub dead: bool,
+    /// The last state sent, handed to restarted policies.
+    pub last_state: Option<GameState>,
+    /// Set while the answer to a state sent in lockstep is still due.
+    pub awaiting: bool,
+    tracker: Arc<Mutex<ResponseTracker>>,
+}
+
+/// Why a policy process stopped without being asked to.
+#[derive(Debug, Clone)]
+pub struct PolicyExit {
+    pub reason: String,
+    /// The process's exit status, if it could be read.
+    pub status: Option<String>,
+    /// The last lines the process wrote to stderr.
+    pub stderr_tail: Vec<String>,
 }
 
+/// Matches answers to the states they were for.
+struct ResponseTracker {
+    deadline: Option<Duration>,
+    counters: Arc<PolicyCounters>,
+    /// Tick and send time of each state still awaiting an answer, oldest first.
+    pending: VecDeque<(u64, Instant)>,
+    /// Set once the policy has answered the hello. States before that are
+    /// not held against it.
+    ready: bool,
+    /// The last tick the simulator stopped waiting for. Answers up to it were
+    /// already counted as missed.
+    given_up: Option<u64>,
+}
+
+impl ResponseTracker {
+    /// Records a state sent to the policy. The states it has not answered
+    /// past the deadline, or beyond the last `MAX_PENDING`, are missed.
+    fn sent(&mut self, tick: u64) {
+        let now = Instant::now();
+        while let Some(&(t, sent_at)) = self.pending.front() {
+            let expired = self
+                .deadline
+                .is_some_and(|deadline| now.duration_since(sent_at) > deadline);
+            if !expired && self.pending.len() < MAX_PENDING {
+                break;
+            }
+            self.pending.pop_front();
+            self.give_up(t);
+        }
+        self.pending.push_back((tick, now));
+    }
+
+    /// Counts a state the policy was too busy to take.
+    fn busy(&mut self) {
+        if self.ready {
+            self.counters.record_missed();
+        }
+    }
+
+    /// Counts the answer to `tick` as missed, so that it is not counted
+    /// again if it still comes.
+    fn give_up(&mut self, tick: u64) {
+        if self.ready && self.given_up.is_none_or(|t| t < tick) {
+            self.given_up = Some(tick);
+            self.counters.record_missed();
+        }
+    }
+
+    /// Records the answer to `tick` and returns whether it should be applied.
+    fn answered(&mut self, tick: u64) -> bool {
+        // policies may skip states that were superseded while they were busy
+        while self.pending.front().is_some_and(|(t, _)| *t < tick) {
+            self.pending.pop_front();
+        }
+        let sent_at = match self.pending.front() {
+            Some(&(t, sent_at)) if t == tick => {
+                self.pending.pop_front();
+                Some(sent_at)
+            }
+            _ => None,
+        };
+        if self.given_up.is_some_and(|t| tick <= t) {
+            return false;
+        }
+        let Some(sent_at) = sent_at else {
+            warn!("Policy answered unknown tick {tick}");
+            return false;
+        };
+
+        let latency = sent_at.elapsed();
+        self.counters.record_latency(latency);
+        if self.deadline.is_some_and(|deadline| latency > deadline) {
+            self.counters.record_late();
+            return false;
+        }
+        self.counters.record_answered();
+        true
+    }
+}
+
+/// Runs one policy process until the simulator shuts it down or it fails.
+/// Returns why it failed, or `None` after a shutdown.
 fn run_io_loop(
-    mut child: Child,
+    child: &mut Child,
     mut stdin: impl std::io::Write + Send + 'static,
     stdout: impl std::io::Read + Send + 'static,
     rx_state: Receiver<GameState>,
-    tx_action: Sender<Vec<Action>>,
-) {
-    let (tx_err, rx_err) = bounded::<()>(1);
+    tx_action: Sender<ActionBatch>,
+    hello: Hello,
+    tracker: Arc<Mutex<ResponseTracker>>,
+) -> Option<String> {
+    let label = policy_label(hello.side, hello.agent);
+    let (tx_err, rx_err) = bounded::<String>(1);
+    let (tx_hello, rx_hello) = bounded::<anyhow::Result<HelloReply>>(1);
+    let reader_tracker = tracker.clone();
 
     let tx_action_clone = tx_action.clone();
+    let offered = hello.clone();
     let reader = std::thread::spawn(move || {
         let mut buf_reader = BufReader::new(stdout);
         let mut line = String::new();
-        loop {
-            line.clear();
-            match buf_reader.read_line(&mut line) {
-                Ok(0) => break, // EOF
-                Ok(_) => match serde_json::from_str::<Vec<Action>>(&line) {
-                    Ok(actions) => {
-                        match tx_action_clone.try_send(actions) {
+
+        let reply = match buf_reader.read_line(&mut line) {
+            Ok(0) => Err(anyhow::anyhow!(
+                "the policy exited before answering the hello; it may not speak protocol v{PROTOCOL_VERSION}"
+            )),
+            Ok(_) => check_hello_reply(&line, &offered),
+            Err(e) => Err(e.into()),
+        };
+        let format = match &reply {
+            Ok(reply) => reply.wire_format,
+            Err(_) => {
+                let _ = tx_hello.send(reply);
+                return;
+            }
+        };
+        let _ = tx_hello.send(reply);
+
+        let reason = loop {
+            match wire::read_message::<PolicyMessage>(&mut buf_reader, format) {
+                Ok(None) => break "the policy closed its stdout".to_string(),
+                Ok(Some(message)) => match message {
+                    PolicyMessage::Actions(batch) => {
+                        if !reader_tracker.lock().unwrap().answered(batch.tick) {
+                            continue;
+                        }
+                        match tx_action_clone.try_send(batch) {
                             Ok(()) => {}
                             Err(TrySendError::Full(_)) => {
                                 // drop newest (or choose a strategy to prefer latest)
                                 // simplest: just drop; apply_actions will consume older pending ones this frame
                             }
                             Err(TrySendError::Disconnected(_)) => {
-                                warn!("Policy action receiver disconnected");
-                                break;
+                                break "the action receiver disconnected".to_string();
                             }
                         };
                     }
-                    Err(e) => {
-                        warn!("Failed to parse actions from policy: {}, {}", line, e);
-                        break;
-                    }
+                    PolicyMessage::Hello(_) => warn!("Policy sent a second hello; ignoring"),
                 },
-                Err(_) => break,
+                Err(e) if e.kind() == ErrorKind::InvalidData => {
+                    break format!("failed to parse actions from the policy: {e}");
+                }
+                Err(e) => break format!("failed to read from the policy: {e}"),
             }
-        }
-        let _ = tx_err.send(());
+        };
+        let _ = tx_err.send(reason);
     });
 
-    while let Ok(state) = rx_state.recv() {
-        if serde_json::to_writer(&mut stdin, &state).is_ok() {
-            let _ = stdin.write_all(b"\n");
-            let _ = stdin.flush();
-        } else {
-            break;
+    let handshake = wire::write_message(&mut stdin, PyWireFormat::Json, &SimMessage::Hello(hello))
+        .map_err(anyhow::Error::from)
+        .and_then(|()| match rx_hello.recv_timeout(HANDSHAKE_TIMEOUT) {
+            Ok(reply) => reply,
+            Err(RecvTimeoutError::Timeout) => Err(anyhow::anyhow!(
+                "no hello within {HANDSHAKE_TIMEOUT:?}; the policy may not speak protocol v{PROTOCOL_VERSION}"
+            )),
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(anyhow::anyhow!("the policy reader stopped"))
+            }
+        });
+    let format = match handshake {
+        Ok(reply) => {
+            info!(
+                "{label} policy {}connected: protocol v{}, {:?}, capabilities {:?}",
+                reply.name.map(|n| format!("{n} ")).unwrap_or_default(),
+                reply.protocol_version,
+                reply.wire_format,
+                reply.capabilities
+            );
+            tracker.lock().unwrap().ready = true;
+            reply.wire_format
+        }
+        Err(e) => {
+            let _ = child.kill();
+            let _ = reader.join();
+            return Some(format!("handshake failed: {e}"));
         }
+    };
 
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                warn!("Policy process exited with status {status}. Exiting...");
-                break;
+    let failure = loop {
+        crossbeam_channel::select! {
+            recv(rx_state) -> state => {
+                let Ok(state) = state else {
+                    break None;
+                };
+                tracker.lock().unwrap().sent(state.tick);
+                if let Err(e) = wire::write_message(&mut stdin, format, &SimMessage::State(state)) {
+                    break Some(format!("failed to write to the policy: {e}"));
+                }
             }
-            Ok(None) => { /* still running */ }
-            Err(e) => {
-                warn!("Unable to wait for child process: {e}");
-                break;
+            recv(rx_err) -> reason => {
+                break Some(reason.unwrap_or_else(|_| "the policy reader stopped".to_string()));
             }
         }
+    };
+
+    let _ = child.kill();
+    let _ = reader.join();
+    failure
+}
+
+/// `Red`, or `Red agent 2` for a per-agent policy.
+pub fn policy_label(side: TeamId, agent: Option<u32>) -> String {
+    match agent {
+        Some(id) => format!("{side:?} agent {id}"),
+        None => format!("{side:?}"),
+    }
+}
 
-        if rx_err.try_recv().is_ok() {
+/// Logs the policy's stderr, keeping the last few lines.
+fn forward_stderr(label: String, stderr: impl std::io::Read, tail: Arc<Mutex<VecDeque<String>>>) {
+    for line in BufReader::new(stderr).lines() {
+        let Ok(line) = line else {
             break;
+        };
+        info!("[{label}] {line}");
+        let mut tail = tail.lock().unwrap();
+        if tail.len() == STDERR_TAIL_LINES {
+            tail.pop_front();
         }
+        tail.push_back(line);
     }
-
-    let _ = child.kill();
-    let _ = reader.join();
 }
 
 impl PolicyBridge {
-    pub fn start(side: TeamId, config: PyConfig, python_exe: &str) -> anyhow::Result<Self> {
-        let config_json = serde_json::to_string(&config)?;
-        let side_json = serde_json::to_string(&PyTeamId { inner: side })?;
+    /// Spawns a policy process for the team, or for the agent in `slot`. It
+    /// is pointed at `physics_addr` for physics queries, if given.
+    pub fn start(
+        side: TeamId,
+        slot: Option<usize>,
+        config: PyConfig,
+        physics_addr: Option<SocketAddr>,
+    ) -> anyhow::Result<Self> {
+        let counters = Arc::new(PolicyCounters::default());
+        let (tx_state, rx_action, rx_exit, tracker) =
+            Self::spawn(side, slot, &config, physics_addr, counters.clone())?;
+
+        Ok(Self {
+            tx_state: Some(tx_state),
+            rx_action,
+            rx_exit,
+            counters,
+            agent: slot.map(|slot| side.slot_id(slot)),
+            side,
+            slot,
+            config,
+            physics_addr,
+            restarts: 0,
+            dead: false,
+            last_state: None,
+            awaiting: false,
+            tracker,
+        })
+    }
+
+    /// Counts a state the policy was too busy to take, once it is past the
+    /// handshake.
+    pub fn record_busy(&self) {
+        self.tracker.lock().unwrap().busy();
+    }
 
-        let mut child = Command::new(python_exe)
-            .args(["run", "launch-team", &config_json, "--side", &side_json])
+    /// Stops waiting for the answer to `tick` and counts it as missed, once
+    /// the policy is past the handshake. The answer is dropped if it still
+    /// comes.
+    pub fn give_up(&self, tick: u64) {
+        self.tracker.lock().unwrap().give_up(tick);
+    }
+
+    /// Replaces a crashed policy process with a fresh one. Its stats carry
+    /// over.
+    pub fn restart(&mut self) -> anyhow::Result<()> {
+        self.restarts += 1;
+        let (tx_state, rx_action, rx_exit, tracker) = Self::spawn(
+            self.side,
+            self.slot,
+            &self.config,
+            self.physics_addr,
+            self.counters.clone(),
+        )?;
+        self.tx_state = Some(tx_state);
+        self.rx_action = rx_action;
+        self.rx_exit = rx_exit;
+        self.tracker = tracker;
+        Ok(())
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn spawn(
+        side: TeamId,
+        slot: Option<usize>,
+        config: &PyConfig,
+        physics_addr: Option<SocketAddr>,
+        counters: Arc<PolicyCounters>,
+    ) -> anyhow::Result<(
+        Sender<GameState>,
+        Receiver<ActionBatch>,
+        Receiver<PolicyExit>,
+        Arc<Mutex<ResponseTracker>>,
+    )> {
+        let agent = slot.map(|slot| side.slot_id(slot));
+        let config_json = serde_json::to_string(config)?;
+        let mut command = config.launch_spec(side).command(
+            side,
+            slot.map(|slot| (side.slot_id(slot), config.agent_ids(side)[slot].as_str())),
+            &config_json,
+            physics_addr,
+        )?;
+        if let Some(addr) = physics_addr {
+            command.env("PHYSICS_ADDR", addr.to_string());
+        }
+
+        let limits = config.limits(side);
+        apply_rlimits(&mut command, limits);
+
+        let mut child = command
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::inherit())
+            .stderr(Stdio::piped())
             .spawn()?;
 
         let stdin = child.stdin.take().unwrap();
         let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let stderr_tail = Arc::new(Mutex::new(VecDeque::new()));
+        let tail = stderr_tail.clone();
+        let label = policy_label(side, agent);
+        let stderr_reader = std::thread::spawn(move || forward_stderr(label, stderr, tail));
 
         let (tx_state, rx_state) = bounded::<GameState>(2);
-        let (tx_action, rx_action) = bounded::<Vec<Action>>(2);
+        let (tx_action, rx_action) = bounded::<ActionBatch>(2);
+        let (tx_exit, rx_exit) = bounded::<PolicyExit>(1);
 
-        std::thread::spawn(move || run_io_loop(child, stdin, stdout, rx_state, tx_action));
+        let tracker = Arc::new(Mutex::new(ResponseTracker {
+            deadline: limits.tick_deadline_ms.map(Duration::from_millis),
+            counters,
+            pending: VecDeque::new(),
+            ready: false,
+            given_up: None,
+        }));
+        let io_tracker = tracker.clone();
+        let hello = Hello::new(side, config.wire_format, agent);
+        std::thread::spawn(move || {
+            let failure = run_io_loop(
+                &mut child, stdin, stdout, rx_state, tx_action, hello, io_tracker,
+            );
+            let status = child.wait().ok();
+            let _ = stderr_reader.join();
+            if let Some(reason) = failure {
+                let _ = tx_exit.send(PolicyExit {
+                    reason,
+                    status: status.map(|s| s.to_string()),
+                    stderr_tail: stderr_tail.lock().unwrap().drain(..).collect(),
+                });
+            }
+        });
 
-        Ok(Self {
-            tx_state: Some(tx_state),
-            rx_action,
-        })
+        Ok((tx_state, rx_action, rx_exit, tracker))
     }
 
     pub fn shutdown_and_join(&mut self) {
@@ -116,8 +427,151 @@ impl PolicyBridge {
     }
 }
 
+/// Caps the child's address space and CPU time before it starts.
+#[cfg(unix)]
+fn apply_rlimits(command: &mut Command, limits: &PyPolicyLimits) {
+    use std::os::unix::process::CommandExt;
+
+    let memory = limits.memory_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    let cpu = limits.cpu_seconds;
+    if memory.is_none() && cpu.is_none() {
+        return;
+    }
+
+    let rlimit = |value: u64| libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    // SAFETY: only calls setrlimit, which is async-signal-safe.
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(bytes) = memory {
+                if libc::setrlimit(libc::RLIMIT_AS, &rlimit(bytes)) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            if let Some(seconds) = cpu {
+                if libc::setrlimit(libc::RLIMIT_CPU, &rlimit(seconds)) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_rlimits(_command: &mut Command, limits: &PyPolicyLimits) {
+    if limits.memory_mb.is_some() || limits.cpu_seconds.is_some() {
+        warn!("Policy memory and CPU limits are only supported on Unix");
+    }
+}
+
 impl Drop for PolicyBridge {
     fn drop(&mut self) {
         self.shutdown_and_join();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(deadline: Option<Duration>) -> ResponseTracker {
+        ResponseTracker {
+            deadline,
+            counters: Arc::new(PolicyCounters::default()),
+            pending: VecDeque::new(),
+            ready: true,
+            given_up: None,
+        }
+    }
+
+    #[test]
+    fn counts_answers_in_time() {
+        let mut tracker = tracker(Some(Duration::from_secs(60)));
+        tracker.sent(1);
+        tracker.sent(2);
+
+        assert!(tracker.answered(1));
+        assert!(tracker.answered(2));
+        assert!(!tracker.answered(3), "no state was sent for tick 3");
+
+        let stats = tracker.counters.snapshot(false);
+        assert_eq!((stats.answered, stats.late, stats.missed), (2, 0, 0));
+    }
+
+    #[test]
+    fn drops_late_answers() {
+        let mut tracker = tracker(Some(Duration::ZERO));
+        tracker.sent(1);
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert!(!tracker.answered(1));
+        let stats = tracker.counters.snapshot(false);
+        assert_eq!((stats.answered, stats.late, stats.missed), (0, 1, 0));
+    }
+
+    #[test]
+    fn states_left_unanswered_past_the_deadline_are_missed() {
+        let mut tracker = tracker(Some(Duration::ZERO));
+        tracker.sent(1);
+        std::thread::sleep(Duration::from_millis(1));
+        tracker.sent(2);
+
+        assert!(!tracker.answered(1), "already counted as missed");
+        let stats = tracker.counters.snapshot(false);
+        assert_eq!((stats.answered, stats.late, stats.missed), (0, 0, 1));
+    }
+
+    #[test]
+    fn a_policy_that_never_answers_misses_the_oldest_states() {
+        let mut tracker = tracker(None);
+        for tick in 1..=MAX_PENDING as u64 + 10 {
+            tracker.sent(tick);
+        }
+
+        assert_eq!(tracker.pending.len(), MAX_PENDING);
+        assert_eq!(tracker.counters.snapshot(false).missed, 10);
+    }
+
+    #[test]
+    fn skipped_states_are_not_counted() {
+        let mut tracker = tracker(None);
+        tracker.sent(1);
+        tracker.sent(2);
+
+        assert!(tracker.answered(2));
+        assert!(tracker.pending.is_empty());
+        let stats = tracker.counters.snapshot(false);
+        assert_eq!((stats.answered, stats.late, stats.missed), (1, 0, 0));
+    }
+
+    #[test]
+    fn an_answer_given_up_on_counts_once() {
+        let mut tracker = tracker(None);
+        tracker.sent(1);
+        tracker.sent(2);
+        tracker.give_up(1);
+        tracker.give_up(1);
+
+        assert!(!tracker.answered(1), "already counted as missed");
+        assert!(tracker.answered(2));
+        let stats = tracker.counters.snapshot(false);
+        assert_eq!((stats.answered, stats.late, stats.missed), (1, 0, 1));
+    }
+
+    #[test]
+    fn nothing_is_held_against_the_policy_before_the_handshake() {
+        let mut tracker = tracker(None);
+        tracker.ready = false;
+        tracker.sent(1);
+        tracker.busy();
+        tracker.give_up(1);
+        assert_eq!(tracker.counters.snapshot(false).missed, 0);
+
+        tracker.ready = true;
+        tracker.busy();
+        assert_eq!(tracker.counters.snapshot(false).missed, 1);
+    }
+}